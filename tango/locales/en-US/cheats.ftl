@@ -0,0 +1,7 @@
+cheats = Cheats
+cheats-builtin-lock-rng = Lock RNG (repeatable chip draws)
+cheats-codes-path = Named codes are loaded from {$path}
+cheats-custom = Custom codes
+    .apply = Apply
+cheats-save-warning = Progress made after using a cheat is not saved: from the first cheat on, the game saves to a scratch file that is thrown away when the session ends.
+    .used = A cheat has been used in this session: the game is saving to a scratch file that is thrown away when the session ends.
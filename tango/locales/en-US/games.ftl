@@ -81,10 +81,12 @@ game-exe6 = Rockman EXE 6
     .variant-0 = Rockman EXE 6: Dennoujuu Glaga
     .variant-1 = Rockman EXE 6: Dennoujuu Falzer
     .match-type-0-0 = Single
+    .match-type-0-1 = Single (all stages)
     .match-type-1-0 = Triple
 game-bn6 = Mega Man Battle Network 6
     .short = BN6
     .variant-0 = Mega Man Battle Network 6: Cybeast Gregar
     .variant-1 = Mega Man Battle Network 6: Cybeast Falzar
     .match-type-0-0 = Single
+    .match-type-0-1 = Single (all stages)
     .match-type-1-0 = Triple
@@ -7,3 +7,7 @@ crash = Oops, Tango has encountered an error and has crashed!
 crash-no-log = Oops, Tango has encountered an error and has crashed!
 
     {$error}
+
+game-definitions-failed = Some game definitions could not be loaded:
+
+    {$errors}
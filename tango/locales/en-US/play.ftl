@@ -4,6 +4,7 @@ play-play = Play
 play-fight = Fight!
 play-leave = Leave
 play-random = Generate random code
+play-save-container-unsupported = This save is not a raw save, so progress made in it could not be saved. Export it as a raw save to play it.
 play-ready = I'm ready!
 play-link-code = Link code
 play-no-game = None
@@ -14,6 +15,14 @@ play-cancel = Cancel
 play-details-game = Game
     .unrecognized = Unknown
 play-details-match-type = Match type
+play-details-stage = Stage
+    .battle-settings = Battle settings
+    .background = Background
+    .random = Random
+    .unsupported = This game doesn't support picking this, so it will be random.
+play-details-best-of = Set
+    .single = Single game
+    .choice = Best of {$n}
 play-details-reveal-setup = Reveal setup
 play-details-input-delay = Input delay
     .suggest = Suggest
@@ -27,6 +36,22 @@ select-save = Select save
     .no-save-selected = No save selected
     .return-to-games-list = Return to games list
     .open-folder = Open folder
+    .export-raw = Export as raw save (.sav)
+    .export-nocash-gba = Export as No$GBA save (.sav)
+    .export-sharkport = Export as GameShark SP save (.sps)
+    .convert-region = Convert to save for {$game_name}
+    .convert-region-failed = Failed to convert save: {$error}
+    .convert-region-confirm = Convert save?
+    .convert-region-confirm-description = Converting this save for {$game_name} will lose:
+    .convert-region-loss-modcards = Installed modcards: {$count}
+    .convert-region-loss-unmapped = Progress that can't be carried over to the other release: {$count} bytes
+    .convert-region-proceed = Convert anyway
+    .export-failed = Failed to export save: {$error}
+    .view-history = View backups
+    .return-to-saves-list = Return to saves list
+    .no-snapshots = No backups of this save have been taken yet.
+    .restore-snapshot = Restore this backup
+    .restore-snapshot-failed = Failed to restore backup: {$error}
 
 lobby-issue-no-local-rom = You do not have a copy of the opponent's game: {$game_name}
 lobby-issue-no-remote-rom = The opponent does not have a copy of the game: {$game_name}
@@ -36,6 +61,8 @@ lobby-issue-no-remote-patches = The opponent does not have any copy of the patch
 lobby-issue-unrecognized-game = The opponent selected an unrecognized game.
 lobby-issue-incompatible = Game is not compatible with the opponent's.
 lobby-issue-match-type-mismatch = Match type does not match the opponent's.
+lobby-issue-stage-mismatch = Stage does not match the opponent's.
+lobby-issue-best-of-mismatch = Set length does not match the opponent's.
 lobby-issue-no-local-selection = You have not selected a game.
 lobby-issue-no-remote-selection = The opponent has not selected a game.
 
@@ -50,3 +77,5 @@ connection-error-other = A connection error has occurred: { $error }
 connection-error-confirm = Damn!
 
 play-show-link-code = Show link code
+set-side-choice-prompt = You lost game {$game}. Which side do you want to play on in the next game?
+set-side-choice-waiting = Your opponent is picking a side for the next game.
@@ -2,6 +2,9 @@ replays = Replays
 
 replays-export = Export
 replays-play = Play
+replays-practice = Practice
+    .description = Play your side of this replay against the opponent's recorded inputs.
+    .unsupported = This replay was recorded by an older version of Tango and can't be practiced.
 replays-scanning = Scanning...
 
 replay-subtitle = {$game_family} @ {$link_code}: vs {$nickname}
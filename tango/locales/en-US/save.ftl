@@ -4,11 +4,18 @@ save-tab-navicust = NaviCust
 save-tab-folder = Folder
 save-tab-modcards = Modcards
 save-tab-dark-ai = Dark Soul AI
+save-tab-stats = Stats
 
 save-cover-description = This tab intentionally left blank.
 
 save-group = Group by chip
 
+save-stats-hp = HP
+save-stats-zenny = Zenny
+save-stats-bugfrags = BugFrags
+save-stats-library = Library
+    .summary = {$count} chips ({$total} in pack)
+
 dark-ai-secondary-standard-chips = Standard chips (secondary)
 dark-ai-standard-chips = Standard chips
 dark-ai-mega-chips = Mega chips
@@ -34,6 +34,7 @@ settings-max-queue-length = Max queue length
 settings-matchmaking-endpoint = Matchmaking endpoint
 settings-replaycollector-endpoint = Replay collector endpoint
 settings-patch-repo = Patches repository
+settings-require-signed-patch-index = Require signed index
 settings-enable-patch-autoupdate = Enable autoupdate
 settings-data-path = Data path
     .open = Open
@@ -58,3 +59,5 @@ settings-use-relay = Use relay
     .always = Always
     .never = Never
 settings-speed-change = Speed change
+settings-save-backup-max-count = Save backups to keep (0 = unlimited)
+settings-save-backup-max-age-days = Days to keep save backups (0 = forever)
@@ -0,0 +1,7 @@
+cheats = チート
+cheats-builtin-lock-rng = 乱数固定（チップの引きを再現）
+cheats-codes-path = 名前付きコードは{ $path }から読み込まれます
+cheats-custom = カスタムコード
+    .apply = 適用
+cheats-save-warning = チートを使った後の進行状況は保存されません。最初のチートを使った時点から、ゲームはセッション終了時に破棄される一時ファイルにセーブします。
+    .used = このセッションではチートが使われました。ゲームはセッション終了時に破棄される一時ファイルにセーブしています。
@@ -74,10 +74,12 @@ game-exe6 = ロックマンエグゼ6
     .variant-0 = ロックマンエグゼ6 電脳獣 グレイガ
     .variant-1 = ロックマンエグゼ6 電脳獣 ファルザー
     .match-type-0-0 = シングル
+    .match-type-0-1 = シングル（全てのステージ構成）
     .match-type-1-0 = トリプル
 game-bn6 = ロックマンエグゼ6（英語版）
     .short = BN6
     .variant-0 = ロックマンエグゼ6 電脳獣 グレイガ（英語版）
     .variant-1 = ロックマンエグゼ6 電脳獣 ファルザー（英語版）
     .match-type-0-0 = シングル
+    .match-type-0-1 = シングル（全てのステージ構成）
     .match-type-1-0 = トリプル
@@ -8,3 +8,8 @@ crash-no-log =
     おっと、Tangoがエラーに遭遇し、クラッシュしてしまいました。
     
     { $error }
+
+game-definitions-failed =
+    一部のゲーム定義を読み込めませんでした。
+    
+    { $errors }
@@ -3,6 +3,7 @@ play-play = プレイ
 play-fight = 対戦！
 play-leave = 出る
 play-random = ランダムリンクコードを作成
+play-save-container-unsupported = このセーブファイルは生のセーブファイルではないため、進行状況を保存できません。生のセーブファイルとしてエクスポートしてからプレイしてください。
 play-ready = 準備OK!
 play-link-code = リンクコード
 play-no-game = なし
@@ -12,6 +13,14 @@ play-cancel = キャンセル
 play-details-game = 作品
     .unrecognized = 未知
 play-details-match-type = マッチタイプ
+play-details-stage = ステージ
+    .battle-settings = バトル設定
+    .background = 背景
+    .random = ランダム
+    .unsupported = この作品では選択できないため、ランダムになります。
+play-details-best-of = セット
+    .single = 1試合のみ
+    .choice = { $n }本勝負
 play-details-reveal-setup = 構築を公開
 play-details-input-delay = 入力遅延
     .suggest = 提案
@@ -23,6 +32,13 @@ select-save = セーブファイルを選択
     .no-save-selected = セーブファイルが選択されていません
     .return-to-games-list = 作品一覧へ戻る
     .open-folder = フォルダを開く
+    .convert-region-failed = セーブファイルの変換に失敗しました：{ $error }
+    .convert-region-confirm = セーブファイルを変換しますか？
+    .convert-region-confirm-description = { $game_name }用に変換すると、次のデータが失われます：
+    .convert-region-loss-modcards = 装着中の改造カード{ $count }枚
+    .convert-region-loss-unmapped = もう一方のバージョンに引き継げない進行データ{ $count }バイト
+    .convert-region-proceed = 変換する
+    .export-failed = セーブファイルのエクスポートに失敗しました：{ $error }
 lobby-issue-no-local-rom = 相手が選択した作品を持っていません：{ $game_name }
 lobby-issue-no-remote-rom = 相手は自分が選択した作品を持っていません：{ $game_name }
 lobby-issue-no-local-patch = 自分は相手が選択したパッチを持っていません：{ $patch_name } v{ $patch_version }
@@ -31,6 +47,8 @@ lobby-issue-no-remote-patches = 相手はどのバージョンのパッチを持
 lobby-issue-unrecognized-game = 相手が未知の作品を選択しました。
 lobby-issue-incompatible = 選択した作品に互換性がありません。
 lobby-issue-match-type-mismatch = 自分と相手のマッチタイプは異なります。
+lobby-issue-stage-mismatch = 自分と相手のステージは異なります。
+lobby-issue-best-of-mismatch = 自分と相手のセットは異なります。
 lobby-issue-no-local-selection = 自分は作品を選択していません。
 lobby-issue-no-remote-selection = 相手は作品を選択していません。
 opponent-setup = 相手の構築
@@ -42,3 +60,5 @@ connection-error-eof = 相手方が切断しました。
 connection-error-other = 接続エラーが発生しました：{ $error }
 connection-error-confirm = やべっ！
 play-show-link-code = リンクコードを表示
+set-side-choice-prompt = 第{ $game }試合に負けました。次の試合でどちらのサイドでプレイしますか？
+set-side-choice-waiting = 相手が次の試合のサイドを選んでいます。
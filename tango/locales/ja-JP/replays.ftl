@@ -1,6 +1,9 @@
 replays = リプレイ
 replays-export = エクスポート
 replays-play = 再生
+replays-practice = 練習
+    .description = 相手の記録された入力を相手に、このリプレイの自分側をプレイします。
+    .unsupported = このリプレイは古いバージョンのTangoで記録されたため、練習できません。
 replays-scanning = スキャン中…
 replay-subtitle = { $game_family } @ { $link_code }：対{ $nickname }
 replays-export-path = 保存場所
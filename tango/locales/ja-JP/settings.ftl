@@ -30,6 +30,7 @@ settings-max-queue-length = 最大キューの長
 settings-matchmaking-endpoint = マッチメイキング エンドポイント
 settings-replaycollector-endpoint = リプレイコレクターエンドポイント
 settings-patch-repo = パッチリポジトリ
+settings-require-signed-patch-index = 署名済みインデックスを必須にする
 settings-enable-patch-autoupdate = 自動更新
 settings-data-path = データ経路
     .open = 開く
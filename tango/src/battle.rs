@@ -47,6 +47,107 @@ impl RoundState {
     }
 }
 
+/// Which player picks the side to play on in the next game of a set: the loser of the last game.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SideChooser {
+    Local,
+    Remote,
+}
+
+pub struct SetState {
+    pub best_of: u8,
+    pub game: u8,
+    pub local_score: u8,
+    pub remote_score: u8,
+    side_chooser: Option<SideChooser>,
+    early_remote_side: Option<(u8, u8)>,
+    next_game_ready: bool,
+    last_replay_filename: Option<std::path::PathBuf>,
+}
+
+impl SetState {
+    pub fn new(best_of: u8) -> Self {
+        Self {
+            best_of: std::cmp::max(best_of, 1),
+            game: 1,
+            local_score: 0,
+            remote_score: 0,
+            side_chooser: None,
+            early_remote_side: None,
+            next_game_ready: false,
+            last_replay_filename: None,
+        }
+    }
+
+    pub fn wins_needed(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.local_score >= self.wins_needed() || self.remote_score >= self.wins_needed()
+    }
+
+    /// The player who still has to pick a side for the next game, if the set is waiting on that.
+    pub fn side_chooser(&self) -> Option<SideChooser> {
+        self.side_chooser
+    }
+
+    /// Scores the game that just ended. If the set isn't decided yet, the loser of the game then has to pick a side for
+    /// the next one.
+    pub fn score_game(&mut self, result: BattleResult) {
+        match result {
+            BattleResult::Win => self.local_score += 1,
+            BattleResult::Loss => self.remote_score += 1,
+        }
+        if !self.is_decided() {
+            self.side_chooser = Some(match result {
+                BattleResult::Win => SideChooser::Remote,
+                BattleResult::Loss => SideChooser::Local,
+            });
+        }
+    }
+
+    /// Applies the side (0 for P1, 1 for P2) picked for the game after `game` and moves on to it. Returns the local
+    /// player index for that game.
+    pub fn choose_side(&mut self, chooser: SideChooser, game: u8, side: u8) -> anyhow::Result<u8> {
+        if game != self.game {
+            anyhow::bail!(
+                "side was picked after game {} but game {} is being played",
+                game,
+                self.game
+            );
+        }
+        if self.side_chooser != Some(chooser) {
+            anyhow::bail!("{:?} player cannot pick a side now", chooser);
+        }
+        self.side_chooser = None;
+        self.game += 1;
+        Ok(next_local_player_index(chooser, side))
+    }
+
+    /// Holds on to a side the remote picked before the local game ended, to be taken once it has.
+    fn set_early_remote_side(&mut self, game: u8, side: u8) {
+        self.early_remote_side = Some((game, side));
+    }
+
+    fn take_early_remote_side(&mut self) -> Option<(u8, u8)> {
+        if self.side_chooser != Some(SideChooser::Remote) {
+            return None;
+        }
+        self.early_remote_side.take()
+    }
+}
+
+/// The local player index for the next game of a set, given who picked the side and which side (0 for P1, 1 for P2)
+/// they picked for themselves.
+pub fn next_local_player_index(chooser: SideChooser, side: u8) -> u8 {
+    let side = std::cmp::min(side, 1);
+    match chooser {
+        SideChooser::Local => side,
+        SideChooser::Remote => 1 - side,
+    }
+}
+
 pub struct Match {
     shadow: std::sync::Arc<parking_lot::Mutex<shadow::Shadow>>,
     rom: Vec<u8>,
@@ -64,6 +165,7 @@ pub struct Match {
     config: std::sync::Arc<parking_lot::RwLock<config::Config>>,
     is_offerer: bool,
     round_state: tokio::sync::Mutex<RoundState>,
+    set_state: tokio::sync::Mutex<SetState>,
     primary_thread_handle: mgba::thread::Handle,
     round_started_tx: tokio::sync::mpsc::Sender<u8>,
     round_started_rx: tokio::sync::Mutex<tokio::sync::mpsc::Receiver<u8>>,
@@ -97,11 +199,13 @@ impl Match {
         } else {
             BattleResult::Loss
         };
+        let best_of = std::cmp::max(local_settings.best_of, 1);
         let match_ = std::sync::Arc::new(Self {
             shadow: std::sync::Arc::new(parking_lot::Mutex::new(shadow::Shadow::new(
                 &remote_rom,
                 &remote_save,
                 match_type,
+                local_settings.stage,
                 is_offerer,
                 last_result,
                 rng.clone(),
@@ -124,6 +228,7 @@ impl Match {
                 round: None,
                 last_result: Some(last_result),
             }),
+            set_state: tokio::sync::Mutex::new(SetState::new(best_of)),
             is_offerer,
             primary_thread_handle,
             round_started_tx,
@@ -145,6 +250,10 @@ impl Match {
         self.cancellation_token.cancelled()
     }
 
+    pub fn shadow(&self) -> std::sync::Arc<parking_lot::Mutex<shadow::Shadow>> {
+        self.shadow.clone()
+    }
+
     pub async fn advance_shadow_until_round_end(&self) -> anyhow::Result<()> {
         self.shadow.lock().advance_until_round_end()
     }
@@ -238,6 +347,17 @@ impl Match {
                                 joyflags: input.joyflags as u16,
                             });
                         }
+                        net::protocol::Packet::LoserSide(loser_side) => {
+                            let mut set_state = self.set_state.lock().await;
+                            if set_state.side_chooser() == Some(SideChooser::Remote) {
+                                drop(set_state);
+                                self.start_next_game(SideChooser::Remote, loser_side.game, loser_side.side)
+                                    .await?;
+                            } else {
+                                // The remote can get through the end of the game before we do.
+                                set_state.set_early_remote_side(loser_side.game, loser_side.side);
+                            }
+                        }
                         p => anyhow::bail!("unknown packet: {:?}", p),
                     }
                 }
@@ -251,6 +371,119 @@ impl Match {
         self.round_state.lock().await
     }
 
+    pub fn blocking_lock_round_state(&self) -> tokio::sync::MutexGuard<'_, RoundState> {
+        self.round_state.blocking_lock()
+    }
+
+    pub async fn lock_set_state(&self) -> tokio::sync::MutexGuard<'_, SetState> {
+        self.set_state.lock().await
+    }
+
+    /// Scores the game that just ended towards the set. Returns false if that decided the set.
+    ///
+    /// Otherwise the loser of the game picks a side for the next one first, locally through `choose_side` or remotely
+    /// over the net. The caller must keep the primary core paused until then, and reset it once `take_next_game_ready`
+    /// returns true so it boots into the next battle.
+    pub async fn end_game(&self) -> anyhow::Result<bool> {
+        let early_remote_side = {
+            let round_state = self.round_state.lock().await;
+            let mut set_state = self.set_state.lock().await;
+
+            // The round that ends a game is always won by the winner of the game.
+            let result = match round_state.last_result {
+                Some(result) => result,
+                None => {
+                    anyhow::bail!("game ended without a result");
+                }
+            };
+            set_state.score_game(result);
+            log::info!(
+                "game {} ended: {:?}, set score is {}-{}",
+                set_state.game,
+                result,
+                set_state.local_score,
+                set_state.remote_score
+            );
+
+            if set_state.is_decided() {
+                if set_state.best_of > 1 {
+                    if let Some(replay_filename) = set_state.last_replay_filename.take() {
+                        let (local_score, remote_score) = (set_state.local_score, set_state.remote_score);
+                        if let Err(e) = replay::rewrite_metadata(&replay_filename, |metadata| {
+                            if let Some(set) = metadata.set.as_mut() {
+                                set.outcome = Some(replay::metadata::set::Outcome {
+                                    local_score: local_score as u32,
+                                    remote_score: remote_score as u32,
+                                });
+                            }
+                        }) {
+                            log::error!(
+                                "failed to write set result to replay {}: {:?}",
+                                replay_filename.display(),
+                                e
+                            );
+                        }
+                        submit_replay(&self.config, replay_filename);
+                    }
+                }
+                return Ok(false);
+            }
+
+            set_state.take_early_remote_side()
+        };
+
+        if let Some((game, side)) = early_remote_side {
+            self.start_next_game(SideChooser::Remote, game, side).await?;
+        }
+        Ok(true)
+    }
+
+    /// Picks the side (0 for P1, 1 for P2) the local player plays on in the next game after losing one, tells the
+    /// remote, and starts the next game.
+    pub async fn choose_side(&self, side: u8) -> anyhow::Result<()> {
+        let game = {
+            let set_state = self.set_state.lock().await;
+            if set_state.side_chooser() != Some(SideChooser::Local) {
+                anyhow::bail!("local player cannot pick a side now");
+            }
+            set_state.game
+        };
+        self.sender.lock().await.send_loser_side(game, side).await?;
+        self.start_next_game(SideChooser::Local, game, side).await
+    }
+
+    async fn start_next_game(&self, chooser: SideChooser, game: u8, side: u8) -> anyhow::Result<()> {
+        let mut round_state = self.round_state.lock().await;
+        let mut set_state = self.set_state.lock().await;
+        let local_player_index = set_state.choose_side(chooser, game, side)?;
+        log::info!(
+            "starting game {} of the set: local_player_index = {}",
+            set_state.game,
+            local_player_index
+        );
+
+        // Both cores boot the next game from scratch, so no lockstep state of the last game may carry over into it.
+        // Round numbers keep counting up, so late inputs from the last game are still told apart.
+        if round_state.round.take().is_some() {
+            log::warn!("a round was still in progress when the game ended, dropping it");
+        }
+        let last_result = if local_player_index == 0 {
+            BattleResult::Win
+        } else {
+            BattleResult::Loss
+        };
+        round_state.last_result = Some(last_result);
+        self.shadow.lock().reset(last_result);
+        set_state.next_game_ready = true;
+        self.primary_thread_handle.unpause();
+        Ok(())
+    }
+
+    /// Returns true once the next game of the set has been set up, after which the primary core must be reset.
+    pub async fn take_next_game_ready(&self) -> bool {
+        std::mem::replace(&mut self.set_state.lock().await.next_game_ready, false)
+    }
+
     pub async fn lock_rng(&self) -> tokio::sync::MutexGuard<'_, rand_pcg::Mcg128Xsl64> {
         self.rng.lock().await
     }
@@ -259,12 +492,18 @@ impl Match {
         self.match_type
     }
 
+    /// The agreed stage selection. Matches only start if both sides' settings agree, so the local settings are used.
+    pub fn stage(&self) -> net::protocol::StageSelection {
+        self.local_settings.stage
+    }
+
     pub fn is_offerer(&self) -> bool {
         self.is_offerer
     }
 
     pub async fn start_round(self: &std::sync::Arc<Self>) -> anyhow::Result<()> {
         let mut round_state = self.round_state.lock().await;
+        let mut set_state = self.set_state.lock().await;
         round_state.number += 1;
         let local_player_index = match round_state.last_result.take().unwrap() {
             BattleResult::Win => 0,
@@ -294,6 +533,15 @@ impl Match {
 
         let replay_file = std::fs::File::create(&replay_filename)?;
 
+        // In a set, the replay of the last round is only submitted once it's known not to be the one that decided the
+        // set, as that one gets the set result written into it first.
+        let defer_replay_submission = set_state.best_of > 1;
+        if let Some(last_replay_filename) = set_state.last_replay_filename.replace(replay_filename.clone()) {
+            if defer_replay_submission {
+                submit_replay(&self.config, last_replay_filename);
+            }
+        }
+
         log::info!("preparing round state");
 
         let (first_state_committed_local_packet, first_state_committed_rx) = tokio::sync::oneshot::channel();
@@ -341,6 +589,7 @@ impl Match {
             first_state_committed_rx: Some(first_state_committed_rx),
             committed_state: None,
             replay_filename,
+            defer_replay_submission,
             replay_writer: Some(replay::Writer::new(
                 Box::new(replay_file),
                 replay::Metadata {
@@ -358,6 +607,15 @@ impl Match {
                                 Some(replay::metadata::game_info::Patch {
                                     name: patch.name.clone(),
                                     version: patch.version.to_string(),
+                                    bases: patch
+                                        .bases
+                                        .iter()
+                                        .map(|(name, version)| replay::metadata::game_info::Patch {
+                                            name: name.clone(),
+                                            version: version.to_string(),
+                                            bases: vec![],
+                                        })
+                                        .collect(),
                                 })
                             } else {
                                 None
@@ -374,6 +632,15 @@ impl Match {
                                 Some(replay::metadata::game_info::Patch {
                                     name: patch.name.clone(),
                                     version: patch.version.to_string(),
+                                    bases: patch
+                                        .bases
+                                        .iter()
+                                        .map(|(name, version)| replay::metadata::game_info::Patch {
+                                            name: name.clone(),
+                                            version: version.to_string(),
+                                            bases: vec![],
+                                        })
+                                        .collect(),
                                 })
                             } else {
                                 None
@@ -384,6 +651,17 @@ impl Match {
                     round: round_state.number as u32,
                     match_type: self.match_type.0 as u32,
                     match_subtype: self.match_type.1 as u32,
+                    set: if set_state.best_of > 1 {
+                        Some(replay::metadata::Set {
+                            best_of: set_state.best_of as u32,
+                            game: set_state.game as u32,
+                            local_score: set_state.local_score as u32,
+                            remote_score: set_state.remote_score as u32,
+                            outcome: None,
+                        })
+                    } else {
+                        None
+                    },
                 },
                 local_player_index,
                 hooks.packet_size() as u8,
@@ -412,6 +690,7 @@ pub struct Round {
     first_state_committed_rx: Option<tokio::sync::oneshot::Receiver<()>>,
     committed_state: Option<CommittedState>,
     replay_filename: std::path::PathBuf,
+    defer_replay_submission: bool,
     replay_writer: Option<replay::Writer>,
     replayer: replayer::Fastforwarder,
     primary_thread_handle: mgba::thread::Handle,
@@ -419,6 +698,35 @@ pub struct Round {
     shadow: std::sync::Arc<parking_lot::Mutex<shadow::Shadow>>,
 }
 
+/// Submits a finished replay to the replay collector, if one is configured.
+fn submit_replay(config: &parking_lot::RwLock<config::Config>, replay_path: std::path::PathBuf) {
+    let replaycollector_endpoint = config.read().replaycollector_endpoint.clone();
+    if replaycollector_endpoint.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let replay_path2 = replay_path.clone();
+        if let Err(e) = (move || async move {
+            let client = reqwest::Client::new();
+            let replay_file = tokio::fs::File::open(&replay_path2).await?;
+
+            client
+                .post(replaycollector_endpoint)
+                .header("Content-Type", "application/x-tango-replay")
+                .body(replay_file)
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok::<(), anyhow::Error>(())
+        })()
+        .await
+        {
+            log::error!("failed to submit replay {}: {:?}", replay_path.display(), e);
+        }
+    });
+}
+
 impl Round {
     pub fn current_tick(&self) -> u32 {
         self.current_tick
@@ -604,33 +912,8 @@ impl Round {
                 self.current_tick
             );
 
-            // Need to submit replay to replay collector.
-            let replaycollector_endpoint = self.config.read().replaycollector_endpoint.clone();
-            if !replaycollector_endpoint.is_empty() {
-                tokio::spawn({
-                    let replay_path = self.replay_filename.clone();
-                    async move {
-                        let replay_path2 = replay_path.clone();
-                        if let Err(e) = (move || async move {
-                            let client = reqwest::Client::new();
-                            let replay_file = tokio::fs::File::open(&replay_path2).await?;
-
-                            client
-                                .post(replaycollector_endpoint)
-                                .header("Content-Type", "application/x-tango-replay")
-                                .body(replay_file)
-                                .send()
-                                .await?
-                                .error_for_status()?;
-
-                            Ok::<(), anyhow::Error>(())
-                        })()
-                        .await
-                        {
-                            log::error!("failed to submit replay {}: {:?}", replay_path.display(), e);
-                        }
-                    }
-                });
+            if !self.defer_replay_submission {
+                submit_replay(&self.config, self.replay_filename.clone());
             }
         }
 
@@ -653,6 +936,10 @@ impl Round {
         self.committed_state.is_some()
     }
 
+    pub fn committed_state(&self) -> Option<&CommittedState> {
+        self.committed_state.as_ref()
+    }
+
     pub fn local_delay(&self) -> u32 {
         self.iq.local_delay()
     }
@@ -689,3 +976,83 @@ impl Drop for Round {
             .set_fps_target(session::EXPECTED_FPS);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_state_single_game() {
+        let mut set_state = SetState::new(0);
+        assert_eq!(set_state.best_of, 1);
+        assert_eq!(set_state.wins_needed(), 1);
+        assert!(!set_state.is_decided());
+
+        set_state.score_game(BattleResult::Loss);
+        assert!(set_state.is_decided());
+        assert_eq!(set_state.side_chooser(), None);
+    }
+
+    #[test]
+    fn test_set_state_best_of_3() {
+        let mut set_state = SetState::new(3);
+        assert_eq!(set_state.wins_needed(), 2);
+
+        set_state.score_game(BattleResult::Win);
+        assert_eq!((set_state.local_score, set_state.remote_score), (1, 0));
+        assert!(!set_state.is_decided());
+        assert_eq!(set_state.side_chooser(), Some(SideChooser::Remote));
+        assert_eq!(set_state.choose_side(SideChooser::Remote, 1, 0).unwrap(), 1);
+        assert_eq!(set_state.game, 2);
+        assert_eq!(set_state.side_chooser(), None);
+
+        set_state.score_game(BattleResult::Loss);
+        assert_eq!((set_state.local_score, set_state.remote_score), (1, 1));
+        assert_eq!(set_state.side_chooser(), Some(SideChooser::Local));
+        assert_eq!(set_state.choose_side(SideChooser::Local, 2, 0).unwrap(), 0);
+        assert_eq!(set_state.game, 3);
+
+        set_state.score_game(BattleResult::Win);
+        assert_eq!((set_state.local_score, set_state.remote_score), (2, 1));
+        assert!(set_state.is_decided());
+        assert_eq!(set_state.side_chooser(), None);
+    }
+
+    #[test]
+    fn test_set_state_choose_side_rejects_wrong_chooser_and_game() {
+        let mut set_state = SetState::new(5);
+        assert!(set_state.choose_side(SideChooser::Local, 1, 0).is_err());
+
+        set_state.score_game(BattleResult::Win);
+        assert!(set_state.choose_side(SideChooser::Local, 1, 0).is_err());
+        assert!(set_state.choose_side(SideChooser::Remote, 2, 0).is_err());
+        assert_eq!(set_state.game, 1);
+        assert_eq!(set_state.side_chooser(), Some(SideChooser::Remote));
+        assert!(set_state.choose_side(SideChooser::Remote, 1, 0).is_ok());
+    }
+
+    #[test]
+    fn test_set_state_early_remote_side() {
+        let mut set_state = SetState::new(3);
+        set_state.set_early_remote_side(1, 1);
+
+        // Not taken until the remote is the one to pick.
+        assert_eq!(set_state.take_early_remote_side(), None);
+        set_state.score_game(BattleResult::Win);
+        assert_eq!(set_state.take_early_remote_side(), Some((1, 1)));
+        assert_eq!(set_state.take_early_remote_side(), None);
+    }
+
+    #[test]
+    fn test_next_local_player_index() {
+        // The loser picks a side for themselves, so the winner gets the other one.
+        assert_eq!(next_local_player_index(SideChooser::Local, 0), 0);
+        assert_eq!(next_local_player_index(SideChooser::Local, 1), 1);
+        assert_eq!(next_local_player_index(SideChooser::Remote, 0), 1);
+        assert_eq!(next_local_player_index(SideChooser::Remote, 1), 0);
+
+        // Out of range sides are clamped to P2.
+        assert_eq!(next_local_player_index(SideChooser::Local, 7), 1);
+        assert_eq!(next_local_player_index(SideChooser::Remote, 7), 0);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::game;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Width {
+    U8,
+    U16,
+    U32,
+}
+
+/// A value written to memory after every frame, like a GameShark/Action Replay raw write code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Write {
+    pub address: u32,
+    pub width: Width,
+    pub value: u32,
+}
+
+impl Write {
+    pub fn u32(address: u32, value: u32) -> Self {
+        Self {
+            address,
+            width: Width::U32,
+            value,
+        }
+    }
+
+    pub fn apply(&self, core: &mut mgba::core::CoreMutRef) {
+        match self.width {
+            Width::U8 => core.raw_write_8(self.address, -1, self.value as u8),
+            Width::U16 => core.raw_write_16(self.address, -1, self.value as u16),
+            Width::U32 => core.raw_write_32(self.address, -1, self.value),
+        }
+    }
+}
+
+/// Parses raw write codes, one per line, in the form `AAAAAAAA VV`, `AAAAAAAA VVVV` or `AAAAAAAA VVVVVVVV`: the number
+/// of value digits decides how many bytes are written. Encrypted GameShark/Action Replay codes must be decrypted first.
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn parse(code: &str) -> Result<Vec<Write>, anyhow::Error> {
+    let mut writes = vec![];
+    for (i, line) in code.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (address, value) = match line.split_once(|c: char| c.is_whitespace() || c == ':') {
+            Some((address, value)) => (address.trim(), value.trim()),
+            None => {
+                anyhow::bail!("line {}: expected an address and a value", i + 1);
+            }
+        };
+
+        if address.len() != 8 {
+            anyhow::bail!("line {}: address must be 8 hex digits", i + 1);
+        }
+        let address =
+            u32::from_str_radix(address, 16).map_err(|e| anyhow::anyhow!("line {}: invalid address: {}", i + 1, e))?;
+
+        let width = match value.len() {
+            2 => Width::U8,
+            4 => Width::U16,
+            8 => Width::U32,
+            _ => {
+                anyhow::bail!("line {}: value must be 2, 4 or 8 hex digits", i + 1);
+            }
+        };
+        let value =
+            u32::from_str_radix(value, 16).map_err(|e| anyhow::anyhow!("line {}: invalid value: {}", i + 1, e))?;
+
+        writes.push(Write { address, width, value });
+    }
+    Ok(writes)
+}
+
+#[derive(Clone, Debug)]
+pub enum Name {
+    /// A code provided by the game's hooks, looked up as `cheats-builtin-<id>`.
+    Builtin(&'static str),
+
+    /// A code loaded from the game's code file.
+    User(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Code {
+    pub name: Name,
+    pub writes: Vec<Write>,
+}
+
+#[derive(serde::Deserialize)]
+struct CodeFile {
+    #[serde(default)]
+    code: Vec<CodeFileEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct CodeFileEntry {
+    name: String,
+    code: String,
+}
+
+/// Path of the file named codes for a game are loaded from, e.g. `BR5E_00.toml`.
+pub fn codes_path(cheats_path: &std::path::Path, game: &'static (dyn game::Game + Send + Sync)) -> std::path::PathBuf {
+    let (rom_code, revision) = game.rom_code_and_revision();
+    cheats_path.join(format!("{}_{:02}.toml", String::from_utf8_lossy(rom_code), revision))
+}
+
+/// Loads named codes from a code file, which has a `[[code]]` table with a `name` and a `code` per code. A missing
+/// file has no codes.
+pub fn load_codes(path: &std::path::Path) -> Result<Vec<Code>, anyhow::Error> {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(vec![]);
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+    toml::from_str::<CodeFile>(&raw)?
+        .code
+        .into_iter()
+        .map(|entry| {
+            Ok(Code {
+                writes: parse(&entry.code).map_err(|e| anyhow::anyhow!("{}: {}", entry.name, e))?,
+                name: Name::User(entry.name),
+            })
+        })
+        .collect()
+}
+
+/// The codes available in a single-player session and which of them are enabled. Nothing here is ever sent over the
+/// network or written to a replay.
+pub struct Engine {
+    codes_path: std::path::PathBuf,
+    codes: Vec<(Code, bool)>,
+    custom: Vec<Write>,
+}
+
+impl Engine {
+    pub fn new(codes_path: std::path::PathBuf, codes: Vec<Code>) -> Self {
+        Self {
+            codes_path,
+            codes: codes.into_iter().map(|code| (code, false)).collect(),
+            custom: vec![],
+        }
+    }
+
+    pub fn codes_path(&self) -> &std::path::Path {
+        &self.codes_path
+    }
+
+    pub fn codes(&self) -> impl Iterator<Item = &(Code, bool)> {
+        self.codes.iter()
+    }
+
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        self.codes[index].1 = enabled;
+    }
+
+    pub fn set_custom(&mut self, writes: Vec<Write>) {
+        self.custom = writes;
+    }
+
+    pub fn disable_all(&mut self) {
+        for (_, enabled) in self.codes.iter_mut() {
+            *enabled = false;
+        }
+        self.custom.clear();
+    }
+
+    /// Whether any code is enabled, so applying would write to memory.
+    pub fn is_active(&self) -> bool {
+        self.codes.iter().any(|(_, enabled)| *enabled) || !self.custom.is_empty()
+    }
+
+    /// Writes every enabled code to memory.
+    pub fn apply(&self, core: &mut mgba::core::CoreMutRef) {
+        for write in self
+            .codes
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .flat_map(|(code, _)| code.writes.iter())
+            .chain(self.custom.iter())
+        {
+            write.apply(core);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_widths() {
+        assert_eq!(
+            parse("02000000 12\n02000004 1234\n02000008 12345678").unwrap(),
+            vec![
+                Write {
+                    address: 0x02000000,
+                    width: Width::U8,
+                    value: 0x12,
+                },
+                Write {
+                    address: 0x02000004,
+                    width: Width::U16,
+                    value: 0x1234,
+                },
+                Write {
+                    address: 0x02000008,
+                    width: Width::U32,
+                    value: 0x12345678,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_separators_comments_and_blank_lines() {
+        assert_eq!(
+            parse("# a comment\n\n  0203a9f4:FFFF  \n0203A9F6\tffff\n").unwrap(),
+            vec![
+                Write {
+                    address: 0x0203a9f4,
+                    width: Width::U16,
+                    value: 0xffff,
+                },
+                Write {
+                    address: 0x0203a9f6,
+                    width: Width::U16,
+                    value: 0xffff,
+                },
+            ]
+        );
+        assert_eq!(parse("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (code, error) in [
+            ("02000000", "line 1: expected an address and a value"),
+            ("0200000 12", "line 1: address must be 8 hex digits"),
+            ("0200000g 12", "line 1: invalid address"),
+            ("02000000 123", "line 1: value must be 2, 4 or 8 hex digits"),
+            ("# ok\n02000000 1g", "line 2: invalid value"),
+        ] {
+            let e = parse(code).unwrap_err().to_string();
+            assert!(e.starts_with(error), "{:?}: {}", code, e);
+        }
+    }
+}
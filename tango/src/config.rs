@@ -3,7 +3,7 @@ use std::io::{Read, Write};
 use fluent_templates::Loader;
 use serde::Deserialize;
 
-use crate::{i18n, input, version};
+use crate::{i18n, input, save, version};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
 pub enum GraphicsBackend {
@@ -80,6 +80,10 @@ where
     buf.parse().map_err(serde::de::Error::custom)
 }
 
+/// An ed25519 public key that patch repository indexes may be signed with.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct PatchPublisherKey(#[serde(with = "serde_hex::SerHex::<serde_hex::Strict>")] pub [u8; 32]);
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
 #[serde(default)]
 pub struct Config {
@@ -98,6 +102,9 @@ pub struct Config {
     pub matchmaking_endpoint: String,
     pub replaycollector_endpoint: String,
     pub patch_repo: String,
+    pub trusted_patch_publisher_keys: Vec<PatchPublisherKey>,
+    /// Patch repositories whose index must be signed by one of `trusted_patch_publisher_keys`.
+    pub signed_patch_repos: Vec<String>,
     pub enable_patch_autoupdate: bool,
     pub input_delay: u32,
     pub default_match_type: u8,
@@ -119,6 +126,7 @@ pub struct Config {
     pub last_version: semver::Version,
     pub use_relay: Option<bool>,
     pub speed_change_percent: u32,
+    pub save_backup_retention: save::backup::Retention,
 }
 
 impl Default for Config {
@@ -137,6 +145,8 @@ impl Default for Config {
             matchmaking_endpoint: "".to_string(),
             replaycollector_endpoint: "https://replaycollector.tango.n1gp.net".to_string(),
             patch_repo: "".to_string(),
+            trusted_patch_publisher_keys: vec![],
+            signed_patch_repos: vec![],
             enable_patch_autoupdate: true,
             input_delay: 2,
             default_match_type: 1,
@@ -156,6 +166,7 @@ impl Default for Config {
             last_version: version,
             use_relay: None,
             speed_change_percent: 300,
+            save_backup_retention: Default::default(),
         }
     }
 }
@@ -257,10 +268,26 @@ impl Config {
         self.data_path.join("replays")
     }
 
+    pub fn patch_repo_url(&self) -> &str {
+        if !self.patch_repo.is_empty() {
+            &self.patch_repo
+        } else {
+            DEFAULT_PATCH_REPO
+        }
+    }
+
     pub fn patches_path(&self) -> std::path::PathBuf {
         self.data_path.join("patches")
     }
 
+    pub fn games_path(&self) -> std::path::PathBuf {
+        self.data_path.join("games")
+    }
+
+    pub fn cheats_path(&self) -> std::path::PathBuf {
+        self.data_path.join("cheats")
+    }
+
     pub fn logs_path(&self) -> std::path::PathBuf {
         self.data_path.join("logs")
     }
@@ -269,13 +296,20 @@ impl Config {
         self.data_path.join("crashstates")
     }
 
+    pub fn backups_path(&self) -> std::path::PathBuf {
+        self.data_path.join("backups")
+    }
+
     pub fn ensure_dirs(&self) -> Result<(), anyhow::Error> {
         std::fs::create_dir_all(&self.saves_path())?;
         std::fs::create_dir_all(&self.roms_path())?;
         std::fs::create_dir_all(&self.replays_path())?;
         std::fs::create_dir_all(&self.patches_path())?;
+        std::fs::create_dir_all(&self.games_path())?;
+        std::fs::create_dir_all(&self.cheats_path())?;
         std::fs::create_dir_all(&self.logs_path())?;
         std::fs::create_dir_all(&self.crashstates_path())?;
+        std::fs::create_dir_all(&self.backups_path())?;
         Ok(())
     }
 }
@@ -10,6 +10,20 @@ pub enum Entry {
     File(#[serde(with = "serde_hex::SerHex::<serde_hex::Strict>")] [u8; 32]),
 }
 
+async fn hash_file(path: &std::path::Path) -> std::io::Result<[u8; 32]> {
+    let mut f = tokio::fs::File::open(path).await?;
+    let mut hasher = sha2::Sha256::new();
+    let mut buf = [0u8; 8196];
+    loop {
+        let n = f.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().into())
+}
+
 #[async_recursion::async_recursion]
 async fn sync_entry(
     root: &std::path::Path,
@@ -47,20 +61,7 @@ async fn sync_entry(
                 .await
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
             let needs_fetch = match tokio::fs::metadata(&real_path).await {
-                Ok(_) => {
-                    let mut f = tokio::fs::File::open(&real_path).await?;
-                    let mut hasher = sha2::Sha256::new();
-                    let mut buf = [0u8; 8196];
-                    loop {
-                        let n = f.read(&mut buf).await?;
-                        if n == 0 {
-                            break;
-                        }
-                        hasher.update(&buf[..n]);
-                    }
-
-                    &hasher.finalize()[..] != &hash[..]
-                }
+                Ok(_) => &hash_file(&real_path).await? != hash,
                 Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
                 Err(e) => {
                     return Err(e);
@@ -69,6 +70,16 @@ async fn sync_entry(
 
             if needs_fetch {
                 fetch_cb(path).await?;
+
+                // Don't leave behind anything that doesn't match the index, whether it came from a bad download or a
+                // tampered source.
+                if &hash_file(&real_path).await? != hash {
+                    tokio::fs::remove_file(&real_path).await?;
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("hash mismatch for {}", path.display()),
+                    ));
+                }
             }
         }
     }
@@ -1,6 +1,6 @@
 use std::any::Any;
 
-use crate::{battle, replayer, rom, save, session, shadow};
+use crate::{battle, cheats, replayer, rom, save, session, shadow};
 
 mod bn1;
 mod bn2;
@@ -8,11 +8,14 @@ mod bn3;
 mod bn4;
 mod bn5;
 mod bn6;
+mod definition;
 mod exe45;
+pub mod field;
+pub mod stage;
 
 impl PartialEq for &'static (dyn Game + Send + Sync) {
     fn eq(&self, other: &Self) -> bool {
-        (*self).type_id() == (*other).type_id()
+        (*self).rom_code_and_revision() == (*other).rom_code_and_revision()
     }
 }
 
@@ -20,17 +23,17 @@ impl Eq for &'static (dyn Game + Send + Sync) {}
 
 impl std::hash::Hash for &'static (dyn Game + Send + Sync) {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        (*self).type_id().hash(state)
+        (*self).rom_code_and_revision().hash(state)
     }
 }
 
 impl std::fmt::Debug for &'static (dyn Game + Send + Sync) {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        (*self).type_id().fmt(f)
+        (*self).family_and_variant().fmt(f)
     }
 }
 
-pub const GAMES: &[&'static (dyn Game + Send + Sync)] = &[
+pub const BUILTIN_GAMES: &[&'static (dyn Game + Send + Sync)] = &[
     bn1::EXE1,
     bn1::BN1,
     bn2::EXE2,
@@ -54,6 +57,41 @@ pub const GAMES: &[&'static (dyn Game + Send + Sync)] = &[
     bn6::BN6F,
 ];
 
+lazy_static! {
+    /// All supported games: the built-in ones followed by those loaded from game definitions. Definitions are only
+    /// loaded once at startup, so the slice is replaced (and the old one leaked) rather than copied on every read.
+    static ref GAMES: parking_lot::RwLock<&'static [&'static (dyn Game + Send + Sync)]> =
+        parking_lot::RwLock::new(BUILTIN_GAMES);
+    static ref DEFINITION_ERRORS: parking_lot::RwLock<Vec<(std::path::PathBuf, String)>> =
+        parking_lot::RwLock::new(vec![]);
+}
+
+/// All supported games: the built-in ones followed by those loaded from game definitions.
+pub fn games() -> &'static [&'static (dyn Game + Send + Sync)] {
+    *GAMES.read()
+}
+
+pub fn is_builtin(game: &'static (dyn Game + Send + Sync)) -> bool {
+    BUILTIN_GAMES.contains(&game)
+}
+
+/// Loads game definitions from the given directory, adding them to the list of supported games. Returns the
+/// definitions that could not be loaded, which are also kept for `definition_errors`.
+pub fn load_definitions(path: &std::path::Path) -> Vec<(std::path::PathBuf, anyhow::Error)> {
+    let mut games = GAMES.write();
+    let (defined_games, errors) = definition::load_all(path, *games);
+    *games = games.iter().copied().chain(defined_games).collect::<Vec<_>>().leak();
+    DEFINITION_ERRORS
+        .write()
+        .extend(errors.iter().map(|(path, e)| (path.clone(), format!("{:?}", e))));
+    errors
+}
+
+/// Game definitions that failed to load, and why.
+pub fn definition_errors() -> Vec<(std::path::PathBuf, String)> {
+    DEFINITION_ERRORS.read().clone()
+}
+
 pub fn scan_roms(path: &std::path::Path) -> std::collections::HashMap<&'static (dyn Game + Send + Sync), Vec<u8>> {
     let mut roms = std::collections::HashMap::new();
 
@@ -107,23 +145,23 @@ pub fn sort_games(lang: &unic_langid::LanguageIdentifier, games: &mut [&'static
 }
 
 pub fn sorted_all_games(lang: &unic_langid::LanguageIdentifier) -> Vec<&'static (dyn Game + Send + Sync)> {
-    let mut games = GAMES.to_vec();
+    let mut games = games().to_vec();
     sort_games(lang, &mut games);
     games
 }
 
 pub fn find_by_family_and_variant(family: &str, variant: u8) -> Option<&'static (dyn Game + Send + Sync)> {
-    GAMES
+    games()
         .iter()
+        .copied()
         .find(|game| game.family_and_variant() == (family, variant))
-        .map(|g| *g)
 }
 
 pub fn find_by_rom_info(code: &[u8; 4], revision: u8) -> Option<&'static (dyn Game + Send + Sync)> {
-    GAMES
+    games()
         .iter()
+        .copied()
         .find(|game| game.rom_code_and_revision() == (code, revision))
-        .map(|g| *g)
 }
 
 pub fn detect(rom: &[u8]) -> Result<&'static (dyn Game + Send + Sync), anyhow::Error> {
@@ -149,6 +187,11 @@ where
     Self: Any,
 {
     fn family_and_variant(&self) -> (&str, u8);
+    /// Family and variant to look up localized strings (game names, etc.) with. Games loaded from definitions use
+    /// the strings of the game they are based on.
+    fn strings_family_and_variant(&self) -> (&str, u8) {
+        self.family_and_variant()
+    }
     fn language(&self) -> unic_langid::LanguageIdentifier;
     fn rom_code_and_revision(&self) -> (&[u8; 4], u8);
     fn expected_crc32(&self) -> u32;
@@ -156,6 +199,26 @@ where
     fn hooks(&self) -> &'static (dyn Hooks + Send + Sync);
     fn parse_save(&self, data: &[u8]) -> Result<Box<dyn save::Save + Send + Sync>, anyhow::Error>;
     fn save_from_wram(&self, data: &[u8]) -> Result<Box<dyn save::Save + Send + Sync>, anyhow::Error>;
+    /// Built-in game a game loaded from a definition is based on. Saves for it are scanned as saves of that game.
+    fn base_game(&self) -> Option<&'static (dyn Game + Send + Sync)> {
+        None
+    }
+    fn region_counterpart(&self) -> Option<&'static (dyn Game + Send + Sync)> {
+        None
+    }
+    /// Converts a save of the region counterpart of this game into a save for this game.
+    fn convert_save_from_region_counterpart(
+        &self,
+        data: &[u8],
+    ) -> Result<Box<dyn save::Save + Send + Sync>, anyhow::Error> {
+        let counterpart = self
+            .region_counterpart()
+            .ok_or_else(|| anyhow::anyhow!("game has no region counterpart"))?;
+        counterpart
+            .parse_save(data)?
+            .to_region_counterpart()
+            .ok_or_else(|| anyhow::anyhow!("save cannot be converted"))
+    }
     fn load_rom_assets(
         &self,
         _rom: &[u8],
@@ -167,6 +230,9 @@ where
 }
 
 pub trait Hooks {
+    /// Offsets these hooks trap and read, in the same shape as the offsets of a game definition.
+    fn offsets(&self) -> toml::Value;
+
     fn patch(&self, _core: mgba::core::CoreMutRef) {}
 
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)>;
@@ -182,6 +248,48 @@ pub trait Hooks {
         completion_token: session::CompletionToken,
     ) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)>;
 
+    /// Number of battle settings and backgrounds that can be selected for a match type instead of random ones. 0 means
+    /// that one can't be selected.
+    fn num_stage_choices(&self, _match_type: (u8, u8)) -> (u16, u16) {
+        (0, 0)
+    }
+
+    /// Labels for the battle settings and backgrounds from `num_stage_choices`, in the same order. A choice with the
+    /// same label as an earlier one picks the same stage.
+    fn stage_choice_labels(&self, match_type: (u8, u8)) -> (Vec<String>, Vec<String>) {
+        let (num_battle_settings, num_backgrounds) = self.num_stage_choices(match_type);
+        (
+            (0..num_battle_settings).map(|i| format!("#{}", i + 1)).collect(),
+            (0..num_backgrounds).map(|i| format!("#{}", i + 1)).collect(),
+        )
+    }
+
+    /// Addresses of the RNG states the builtin `lock-rng` training code holds at 0.
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![]
+    }
+
+    /// Named codes for training in single-player sessions, built on the game's offsets.
+    fn training_codes(&self) -> Vec<cheats::Code> {
+        let rng_states = self.lockable_rng_states();
+        if rng_states.is_empty() {
+            return vec![];
+        }
+        vec![cheats::Code {
+            name: cheats::Name::Builtin("lock-rng"),
+            writes: rng_states
+                .into_iter()
+                .map(|address| cheats::Write::u32(address, 0))
+                .collect(),
+        }]
+    }
+
+    /// Fields of the battle state, and of the state the netplay hooks sync around it, that the debug window shows for
+    /// both the primary and the shadow core.
+    fn battle_state_fields(&self) -> Vec<field::Field> {
+        vec![]
+    }
+
     fn packet_size(&self) -> usize {
         return 0x10;
     }
@@ -24,6 +24,16 @@ impl Hooks {
     }
 }
 
+/// Builds hooks for a revision of this game that isn't built in, from offsets in a game definition. The hooks are
+/// leaked, as games live for the rest of the program.
+pub fn from_offsets(offsets: toml::Value) -> Result<&'static Hooks, toml::de::Error> {
+    Ok(Box::leak(Box::new(Hooks {
+        offsets: Box::leak(Box::new(offsets.try_into::<offsets::Offsets>()?)),
+    })))
+}
+
+const NUM_BATTLE_STAGES: u16 = 0xc;
+
 fn step_rng(seed: u32) -> u32 {
     let seed = std::num::Wrapping(seed);
     ((seed << 1) + (seed >> 0x1f) + std::num::Wrapping(1)).0 ^ 0x873ca9e5
@@ -42,6 +52,65 @@ const INIT_RX: [u8; 16] = [
 ];
 
 impl game::Hooks for Hooks {
+    fn offsets(&self) -> toml::Value {
+        toml::Value::try_from(self.offsets).unwrap()
+    }
+
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![self.offsets.ewram.rng_state]
+    }
+
+    fn battle_state_fields(&self) -> Vec<game::field::Field> {
+        vec![
+            game::field::Field {
+                name: "battle state",
+                address: self.offsets.ewram.battle_state,
+                kind: game::field::FieldKind::Bytes(0x80),
+                per_side: false,
+            },
+            game::field::Field {
+                name: "battle stage",
+                address: self.offsets.ewram.battle_state + 0xd,
+                kind: game::field::FieldKind::U8,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng",
+                address: self.offsets.ewram.rng_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tx packet",
+                address: self.offsets.ewram.tx_packet,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P1)",
+                address: self.offsets.ewram.rx_packet_arr,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P2)",
+                address: self.offsets.ewram.rx_packet_arr + 0x10,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "packet seqnum",
+                address: self.offsets.ewram.packet_seqnum,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+        ]
+    }
+
+    fn num_stage_choices(&self, _match_type: (u8, u8)) -> (u16, u16) {
+        (NUM_BATTLE_STAGES, 0)
+    }
+
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> {
         vec![
             (self.offsets.rom.start_screen_jump_table_entry, {
@@ -190,7 +259,11 @@ impl game::Hooks for Hooks {
                     };
                     sync::block_on(match_.start_round()).expect("start round");
                     let mut rng = sync::block_on(match_.lock_rng());
-                    munger.set_battle_stage(core, rng.gen_range(0..0xc));
+                    munger.set_battle_stage(
+                        core,
+                        game::stage::pick_stage_choice(&mut *rng, NUM_BATTLE_STAGES, match_.stage().battle_settings)
+                            as u8,
+                    );
                 })
             }),
             (self.offsets.rom.link_is_p2_ret, {
@@ -419,7 +492,14 @@ impl game::Hooks for Hooks {
                 Box::new(move |core| {
                     shadow_state.start_round();
                     let mut rng = shadow_state.lock_rng();
-                    munger.set_battle_stage(core, rng.gen_range(0..0xc));
+                    munger.set_battle_stage(
+                        core,
+                        game::stage::pick_stage_choice(
+                            &mut *rng,
+                            NUM_BATTLE_STAGES,
+                            shadow_state.stage().battle_settings,
+                        ) as u8,
+                    );
                 })
             }),
             (self.offsets.rom.round_end_set_win, {
@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct EWRAMOffsets {
     // Outgoing packet.
     pub(super) tx_packet: u32,
@@ -23,7 +23,7 @@ pub(super) struct EWRAMOffsets {
     pub(super) packet_seqnum: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct ROMOffsets {
     /// This is the entry point for the start screen, i.e. when the CAPCOM logo is displayed.
     ///
@@ -98,7 +98,7 @@ static EWRAM_OFFSETS: EWRAMOffsets = EWRAMOffsets {
     battle_state:           0x02003710,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Offsets {
     pub(super) rom: ROMOffsets,
     pub(super) ewram: EWRAMOffsets,
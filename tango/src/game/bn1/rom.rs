@@ -185,13 +185,7 @@ impl Assets {
 
         Self {
             offsets,
-            text_parse_options: rom::text::ParseOptions {
-                charset,
-                extension_ops: 0xe5..=0xe6,
-                eof_op: 0xe7,
-                newline_op: 0xe8,
-                commands: std::collections::HashMap::new(),
-            },
+            text_parse_options: text_parse_options(charset),
             mapper,
             chip_icon_palette,
             element_icon_palette,
@@ -224,6 +218,55 @@ impl rom::Assets for Assets {
             &self.element_icon_palette,
         ))
     }
+
+    fn text_parse_options(&self) -> &rom::text::ParseOptions {
+        &self.text_parse_options
+    }
+
+    fn text_archives(&self) -> Vec<rom::TextArchive> {
+        let pointers = vec![
+            ("chip_names".to_string(), self.offsets.chip_names_pointer),
+            ("chip_descriptions".to_string(), self.offsets.chip_descriptions_pointer),
+        ];
+        rom::read_text_archives(&self.mapper, &self.text_parse_options, pointers)
+    }
+
+    fn graphics_presets(&self) -> Result<Vec<rom::graphics::Preset>, anyhow::Error> {
+        let mut presets = vec![rom::graphics::Preset {
+            name: "element_icons".to_string(),
+            sheet: rom::graphics::Sheet {
+                address: rom::graphics::deref(&self.mapper, self.offsets.element_icons_pointer)?,
+                compressed: false,
+                num_tiles: 5 * 4,
+                cols: 2,
+                bpp: rom::graphics::Bpp::Four,
+                palette_address: Some(rom::graphics::deref(
+                    &self.mapper,
+                    self.offsets.element_icon_palette_pointer,
+                )?),
+            },
+        }];
+        presets.extend(rom::graphics::chip_presets(
+            &self.mapper,
+            self.offsets.chip_data,
+            0x1c,
+            self.num_chips(),
+            0x10,
+            (8, 7),
+            rom::graphics::deref(&self.mapper, self.offsets.chip_icon_palette_pointer)?,
+        )?);
+        Ok(presets)
+    }
+}
+
+fn text_parse_options(charset: Vec<String>) -> rom::text::ParseOptions {
+    rom::text::ParseOptions {
+        charset,
+        extension_ops: 0xe5..=0xe6,
+        eof_op: 0xe7,
+        newline_op: 0xe8,
+        commands: std::collections::HashMap::new(),
+    }
 }
 
 #[rustfmt::skip]
@@ -231,3 +274,15 @@ pub const EN_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "
 
 #[rustfmt::skip]
 pub const JA_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "ア", "イ", "ウ", "エ", "オ", "カ", "キ", "ク", "ケ", "コ", "サ", "シ", "ス", "セ", "ソ", "タ", "チ", "ツ", "テ", "ト", "ナ", "ニ", "ヌ", "ネ", "ノ", "ハ", "ヒ", "フ", "ヘ", "ホ", "マ", "ミ", "ム", "メ", "モ", "ヤ", "ユ", "ヨ", "ラ", "リ", "ル", "レ", "ロ", "ワ", "ヰ", "ヱ", "ヲ", "ン", "ガ", "ギ", "グ", "ゲ", "ゴ", "ザ", "ジ", "ズ", "ゼ", "ゾ", "ダ", "ヂ", "ヅ", "デ", "ド", "バ", "ビ", "ブ", "ベ", "ボ", "パ", "ピ", "プ", "ペ", "ポ", "ァ", "ィ", "ゥ", "ェ", "ォ", "ッ", "ャ", "ュ", "ョ", "ヴ", "ー", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z", "あ", "い", "う", "え", "お", "か", "き", "く", "け", "こ", "さ", "し", "す", "せ", "そ", "た", "ち", "つ", "て", "と", "な", "に", "ぬ", "ね", "の", "は", "ひ", "ふ", "へ", "ほ", "ま", "み", "む", "め", "も", "や", "ゆ", "よ", "ら", "り", "る", "れ", "ろ", "わ", "ゐ", "ゑ", "を", "ん", "が", "ぎ", "ぐ", "げ", "ご", "ざ", "じ", "ず", "ぜ", "ぞ", "だ", "ぢ", "づ", "で", "ど", "ば", "び", "ぶ", "べ", "ぼ", "ぱ", "ぴ", "ぷ", "ぺ", "ぽ", "ぁ", "ぃ", "ぅ", "ぇ", "ぉ", "っ", "ゃ", "ゅ", "ょ", "!", "‼", "?", "“", "„", "#", "♭", "$", "%", "&", "'", "(", ")", "~", "^", "\"", "∧", "∨", "<", ">", "、", "。", ".", "・", "/", "\\\\", "_", "「", "」", "\\[", "\\]", "[bracket1]", "[bracket2]", "⊂", "⊃", "∩", "[raindrop]", "↑", "→", "↓", "←", "∀", "α", "β", "@", "★", "♥", "♪", "℃", "♂", "♀", "＿", "｜", "￣", ":", ";", "…", "¥", "+", "×", "÷", "=", "※", "*", "○", "●", "◎", "□", "■", "◇", "◆", "△", "▲", "▽", "▼", "▶", "◀", "☛", "止", "彩", "起", "父", "博", "士", "一", "二", "三", "四", "五", "六", "七", "八", "九", "十", "百", "千", "万", "億", "上", "下", "左", "右", "手", "足", "日", "目", "月", "顔", "頭", "人", "入", "出", "山", "口", "光", "電", "気", "話", "広", "雨", "名", "前", "学", "校", "保", "健", "室", "世", "界", "体", "育", "館", "信", "号", "機", "器", "大", "小", "中", "自", "分", "間", "開", "閉", "問", "聞", "門", "熱", "斗", "要", "住", "道", "行", "街", "屋", "水", "見", "家", "教", "走", "先", "生", "長", "今", "事", "点", "女", "子", "言", "会", "来", "¼", "[infinity1]", "[infinity2]", "思", "時", "円", "知", "毎", "年", "火", "朝", "計", "画", "休", "曜", "帰", "回", "外", "多", "考", "正", "死", "値", "合", "戦", "争", "秋", "原", "町", "天", "用", "金", "男", "作", "数", "方", "社", "攻", "撃", "力", "同", "武", "何", "発", "少", "度", "以", "楽", "早", "暮", "面", "組", "後", "文", "字", "本", "階", "岩", "才", "者", "立", "官", "庁", "ヶ", "連", "射", "国", "局", "耳", "土", "炎", "伊", "集", "院", "各", "科", "省", "祐", "朗", "枚", "永", "川", "花", "兄", "茶", "音", "属", "性", "持", "勝", "赤", "充", "池", "停", "丁", "舎", "地", "所", "明", "切", "急", "木", "無", "高", "駅", "店", "闘", "絵", "球", "研", "究", "香"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        for charset in [EN_CHARSET, JA_CHARSET] {
+            rom::text::tests::assert_round_trips(&text_parse_options(charset.iter().map(|s| s.to_string()).collect()));
+        }
+    }
+}
@@ -3,7 +3,7 @@ mod offsets;
 
 use byteorder::ByteOrder;
 
-use crate::{battle, game, lockstep, replayer, session, shadow, sync};
+use crate::{battle, game, lockstep, net, replayer, session, shadow, sync};
 
 pub struct Hooks {
     offsets: &'static offsets::Offsets,
@@ -15,6 +15,14 @@ impl Hooks {
     }
 }
 
+/// Builds hooks for a revision of this game that isn't built in, from offsets in a game definition. The hooks are
+/// leaked, as games live for the rest of the program.
+pub fn from_offsets(offsets: toml::Value) -> Result<&'static Hooks, toml::de::Error> {
+    Ok(Box::leak(Box::new(Hooks {
+        offsets: Box::leak(Box::new(offsets.try_into::<offsets::Offsets>()?)),
+    })))
+}
+
 pub static AE2E_00: Hooks = Hooks {
     offsets: &offsets::AE2E_00,
 };
@@ -23,9 +31,10 @@ pub static AE2J_01: Hooks = Hooks {
     offsets: &offsets::AE2J_01,
 };
 
-fn random_background(rng: &mut impl rand::Rng) -> u8 {
-    const BATTLE_BACKGROUNDS: &[u8] = &[0x00, 0x01, 0x02, 0x03, 0x05, 0x08, 0x15, 0x18];
-    BATTLE_BACKGROUNDS[rng.gen_range(0..BATTLE_BACKGROUNDS.len())]
+const BATTLE_BACKGROUNDS: &[u8] = &[0x00, 0x01, 0x02, 0x03, 0x05, 0x08, 0x15, 0x18];
+
+fn background(rng: &mut impl rand::Rng, stage: net::protocol::StageSelection) -> u8 {
+    BATTLE_BACKGROUNDS[game::stage::pick_stage_choice(rng, BATTLE_BACKGROUNDS.len() as u16, stage.background) as usize]
 }
 
 fn step_rng(seed: u32) -> u32 {
@@ -46,6 +55,59 @@ const INIT_RX: [u8; 16] = [
 ];
 
 impl game::Hooks for Hooks {
+    fn offsets(&self) -> toml::Value {
+        toml::Value::try_from(self.offsets).unwrap()
+    }
+
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![self.offsets.ewram.rng_state]
+    }
+
+    fn battle_state_fields(&self) -> Vec<game::field::Field> {
+        vec![
+            game::field::Field {
+                name: "rng",
+                address: self.offsets.ewram.rng_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tx packet",
+                address: self.offsets.ewram.tx_packet,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P1)",
+                address: self.offsets.ewram.rx_packet_arr,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P2)",
+                address: self.offsets.ewram.rx_packet_arr + 0x10,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "packet seqnum",
+                address: self.offsets.ewram.packet_seqnum,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "is linking",
+                address: self.offsets.ewram.is_linking,
+                kind: game::field::FieldKind::U8,
+                per_side: false,
+            },
+        ]
+    }
+
+    fn num_stage_choices(&self, _match_type: (u8, u8)) -> (u16, u16) {
+        (0, BATTLE_BACKGROUNDS.len() as u16)
+    }
+
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> {
         vec![
             (self.offsets.rom.start_screen_jump_table_entry, {
@@ -125,7 +187,7 @@ impl game::Hooks for Hooks {
                             answerer_rng_state
                         },
                     );
-                    munger.start_battle_from_comm_menu(core, random_background(&mut *rng));
+                    munger.start_battle_from_comm_menu(core, background(&mut *rng, match_.stage()));
                 })
             }),
             (
@@ -495,7 +557,7 @@ impl game::Hooks for Hooks {
                             offerer_rng_state
                         },
                     );
-                    munger.start_battle_from_comm_menu(core, random_background(&mut *rng));
+                    munger.start_battle_from_comm_menu(core, background(&mut *rng, shadow_state.stage()));
                 })
             }),
             (self.offsets.rom.round_start_ret, {
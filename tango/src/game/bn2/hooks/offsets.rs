@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct EWRAMOffsets {
     // Outgoing packet.
     pub(super) tx_packet: u32,
@@ -22,7 +22,7 @@ pub(super) struct EWRAMOffsets {
     pub(super) packet_seqnum: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct ROMOffsets {
     /// This is the entry point for the start screen, i.e. when the CAPCOM logo is displayed.
     ///
@@ -100,7 +100,7 @@ static EWRAM_OFFSETS: EWRAMOffsets = EWRAMOffsets {
     packet_seqnum:          0x0200ea9c,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Offsets {
     pub(super) rom: ROMOffsets,
     pub(super) ewram: EWRAMOffsets,
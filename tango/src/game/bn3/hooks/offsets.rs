@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct EWRAMOffsets {
     // Outgoing packet.
     pub(super) tx_packet: u32,
@@ -24,7 +24,7 @@ pub(super) struct EWRAMOffsets {
     pub(super) is_linking: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct ROMOffsets {
     /// This is the entry point for the start screen, i.e. when the CAPCOM logo is displayed.
     ///
@@ -107,7 +107,7 @@ static EWRAM_OFFSETS: EWRAMOffsets = EWRAMOffsets {
     is_linking:             0x0203b36e,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Offsets {
     pub(super) rom: ROMOffsets,
     pub(super) ewram: EWRAMOffsets,
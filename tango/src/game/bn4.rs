@@ -4,6 +4,8 @@ mod save;
 
 use crate::game;
 
+pub(super) use hooks::from_offsets as hooks_from_offsets;
+
 const MATCH_TYPES: &[usize] = &[2, 2];
 
 struct EXE4RSImpl;
@@ -1,7 +1,7 @@
 mod munger;
 mod offsets;
 
-use crate::{battle, game, lockstep, replayer, session, shadow, sync};
+use crate::{battle, game, lockstep, net, replayer, session, shadow, sync};
 
 pub struct Hooks {
     offsets: &'static offsets::Offsets,
@@ -13,6 +13,14 @@ impl Hooks {
     }
 }
 
+/// Builds hooks for a revision of this game that isn't built in, from offsets in a game definition. The hooks are
+/// leaked, as games live for the rest of the program.
+pub fn from_offsets(offsets: toml::Value) -> Result<&'static Hooks, toml::de::Error> {
+    Ok(Box::leak(Box::new(Hooks {
+        offsets: Box::leak(Box::new(offsets.try_into::<offsets::Offsets>()?)),
+    })))
+}
+
 pub static B4BE_00: Hooks = Hooks {
     offsets: &offsets::B4BE_00,
 };
@@ -59,23 +67,96 @@ fn generate_rng2_state(rng: &mut impl rand::Rng) -> u32 {
     rng2_state
 }
 
-fn random_battle_settings_and_background(rng: &mut impl rand::Rng, match_type: (u8, u8)) -> (u8, u8) {
+fn num_battle_settings(match_type: (u8, u8)) -> u16 {
+    match match_type.0 {
+        0 => 0x44,
+        1 => 0x60,
+        2 => 0x44,
+        _ => 0,
+    }
+}
+
+/// Returns the first background that can be picked for the match type and how many can be picked.
+fn battle_backgrounds(match_type: (u8, u8)) -> (u8, u16) {
+    match match_type.1 {
+        0 => (0, 0x18),
+        1 => (0x18, 0x03),
+        _ => (0, 0),
+    }
+}
+
+fn battle_settings_and_background(
+    rng: &mut impl rand::Rng,
+    match_type: (u8, u8),
+    stage: net::protocol::StageSelection,
+) -> (u8, u8) {
+    let (first_background, num_backgrounds) = battle_backgrounds(match_type);
     (
-        match match_type.0 {
-            0 => rng.gen_range(0..0x44),
-            1 => rng.gen_range(0..0x60),
-            2 => rng.gen_range(0..0x44),
-            _ => 0,
-        },
-        match match_type.1 {
-            0 => rng.gen_range(0..0x18),
-            1 => rng.gen_range(0x18..0x1b),
-            _ => 0,
-        },
+        game::stage::pick_stage_choice(rng, num_battle_settings(match_type), stage.battle_settings) as u8,
+        first_background + game::stage::pick_stage_choice(rng, num_backgrounds, stage.background) as u8,
     )
 }
 
 impl game::Hooks for Hooks {
+    fn offsets(&self) -> toml::Value {
+        toml::Value::try_from(self.offsets).unwrap()
+    }
+
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![self.offsets.ewram.rng2_state, self.offsets.ewram.rng3_state]
+    }
+
+    fn battle_state_fields(&self) -> Vec<game::field::Field> {
+        vec![
+            game::field::Field {
+                name: "rng1",
+                address: self.offsets.ewram.rng1_state,
+                kind: game::field::FieldKind::U32,
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rng2",
+                address: self.offsets.ewram.rng2_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng3",
+                address: self.offsets.ewram.rng3_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tx packet",
+                address: self.offsets.ewram.tx_packet,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P1)",
+                address: self.offsets.ewram.rx_packet_arr,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P2)",
+                address: self.offsets.ewram.rx_packet_arr + 0x10,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "copy input state",
+                address: self.offsets.ewram.copy_data_input_state,
+                kind: game::field::FieldKind::U8,
+                per_side: false,
+            },
+        ]
+    }
+
+    fn num_stage_choices(&self, match_type: (u8, u8)) -> (u16, u16) {
+        (num_battle_settings(match_type), battle_backgrounds(match_type).1)
+    }
+
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> {
         vec![
             (self.offsets.rom.start_screen_jump_table_entry, {
@@ -245,7 +326,7 @@ impl game::Hooks for Hooks {
                     let mut rng = sync::block_on(match_.lock_rng());
 
                     let (battle_settings, background) =
-                        random_battle_settings_and_background(&mut *rng, match_.match_type());
+                        battle_settings_and_background(&mut *rng, match_.match_type(), match_.stage());
 
                     munger.start_battle_from_comm_menu(core, match_.match_type().0, battle_settings, background);
                 })
@@ -583,7 +664,7 @@ impl game::Hooks for Hooks {
                     let mut rng = shadow_state.lock_rng();
 
                     let (battle_settings, background) =
-                        random_battle_settings_and_background(&mut *rng, shadow_state.match_type());
+                        battle_settings_and_background(&mut *rng, shadow_state.match_type(), shadow_state.stage());
 
                     munger.start_battle_from_comm_menu(core, shadow_state.match_type().0, battle_settings, background);
                 })
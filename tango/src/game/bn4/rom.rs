@@ -450,19 +450,7 @@ impl Assets {
 
         Self {
             offsets,
-            text_parse_options: rom::text::ParseOptions {
-                charset,
-                extension_ops: 0xe4..=0xe4,
-                eof_op: 0xe5,
-                newline_op: 0xe8,
-                commands: std::collections::HashMap::from([
-                    (EREADER_COMMAND, 2),
-                    (0xe6, 1),
-                    (0xe7, 1),
-                    (0xed, 3),
-                    (0xf0, 2),
-                ]),
-            },
+            text_parse_options: text_parse_options(charset),
             modcards,
             mapper,
             chip_icon_palette,
@@ -524,6 +512,64 @@ impl rom::Assets for Assets {
     fn num_modcard4s(&self) -> usize {
         self.modcards.len()
     }
+
+    fn text_parse_options(&self) -> &rom::text::ParseOptions {
+        &self.text_parse_options
+    }
+
+    fn text_archives(&self) -> Vec<rom::TextArchive> {
+        let mut pointers = vec![];
+        for i in 0..(self.num_chips() + 0xff) / 0x100 {
+            pointers.push((
+                format!("chip_names_{}", i),
+                self.offsets.chip_names_pointers + (i * 4) as u32,
+            ));
+            pointers.push((
+                format!("chip_descriptions_{}", i),
+                self.offsets.chip_descriptions_pointers + (i * 4) as u32,
+            ));
+        }
+        pointers.push(("ncp_names".to_string(), self.offsets.ncp_names_pointer));
+        pointers.push(("ncp_descriptions".to_string(), self.offsets.ncp_descriptions_pointer));
+        rom::read_text_archives(&self.mapper, &self.text_parse_options, pointers)
+    }
+
+    fn graphics_presets(&self) -> Result<Vec<rom::graphics::Preset>, anyhow::Error> {
+        let mut presets = vec![rom::graphics::Preset {
+            name: "element_icons".to_string(),
+            sheet: rom::graphics::Sheet {
+                address: rom::graphics::deref(&self.mapper, self.offsets.element_icons_pointer)?,
+                compressed: false,
+                num_tiles: 13 * 4,
+                cols: 2,
+                bpp: rom::graphics::Bpp::Four,
+                palette_address: Some(rom::graphics::deref(
+                    &self.mapper,
+                    self.offsets.element_icon_palette_pointer,
+                )?),
+            },
+        }];
+        presets.extend(rom::graphics::chip_presets(
+            &self.mapper,
+            self.offsets.chip_data,
+            0x2c,
+            self.num_chips(),
+            0x20,
+            (7, 6),
+            rom::graphics::deref(&self.mapper, self.offsets.chip_icon_palette_pointer)?,
+        )?);
+        Ok(presets)
+    }
+}
+
+fn text_parse_options(charset: Vec<String>) -> rom::text::ParseOptions {
+    rom::text::ParseOptions {
+        charset,
+        extension_ops: 0xe4..=0xe4,
+        eof_op: 0xe5,
+        newline_op: 0xe8,
+        commands: std::collections::HashMap::from([(EREADER_COMMAND, 2), (0xe6, 1), (0xe7, 1), (0xed, 3), (0xf0, 2)]),
+    }
 }
 
 #[rustfmt::skip]
@@ -531,3 +577,15 @@ pub const EN_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "
 
 #[rustfmt::skip]
 pub const JA_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "ア", "イ", "ウ", "エ", "オ", "カ", "キ", "ク", "ケ", "コ", "サ", "シ", "ス", "セ", "ソ", "タ", "チ", "ツ", "テ", "ト", "ナ", "ニ", "ヌ", "ネ", "ノ", "ハ", "ヒ", "フ", "ヘ", "ホ", "マ", "ミ", "ム", "メ", "モ", "ヤ", "ユ", "ヨ", "ラ", "リ", "ル", "レ", "ロ", "ワ", "熱", "斗", "ヲ", "ン", "ガ", "ギ", "グ", "ゲ", "ゴ", "ザ", "ジ", "ズ", "ゼ", "ゾ", "ダ", "ヂ", "ヅ", "デ", "ド", "バ", "ビ", "ブ", "ベ", "ボ", "パ", "ピ", "プ", "ペ", "ポ", "ァ", "ィ", "ゥ", "ェ", "ォ", "ッ", "ャ", "ュ", "ョ", "ヴ", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "*", "-", "×", "=", ":", "%", "?", "+", "空", "港", "ー", "!", "現", "実", "&", "、", "。", ".", "・", ";", "’", "\"", "~", "/", "(", ")", "「", "」", "V2", "V3", "V4", "V5", "_", "[z]", "周", "あ", "い", "う", "え", "お", "か", "き", "く", "け", "こ", "さ", "し", "す", "せ", "そ", "た", "ち", "つ", "て", "と", "な", "に", "ぬ", "ね", "の", "は", "ひ", "ふ", "へ", "ほ", "ま", "み", "む", "め", "も", "や", "ゆ", "よ", "ら", "り", "る", "れ", "ろ", "わ", "研", "究", "を", "ん", "が", "ぎ", "ぐ", "げ", "ご", "ざ", "じ", "ず", "ぜ", "ぞ", "だ", "ぢ", "づ", "で", "ど", "ば", "び", "ぶ", "べ", "ぼ", "ぱ", "ぴ", "ぷ", "ぺ", "ぽ", "ぁ", "ぃ", "ぅ", "ぇ", "ぉ", "っ", "ゃ", "ゅ", "ょ", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z", "容", "量", "全", "木", "[MB]", "無", "嵐", "[square]", "[circle]", "[cross]", "駅", "匠", "不", "止", "彩", "起", "父", "集", "院", "一", "二", "三", "四", "五", "六", "七", "八", "陽", "十", "百", "千", "万", "脳", "上", "下", "左", "右", "手", "足", "日", "目", "月", "転", "各", "人", "入", "出", "山", "口", "光", "電", "気", "助", "科", "戸", "名", "前", "学", "校", "省", "祐", "室", "世", "界", "舟", "朗", "枚", "野", "悪", "路", "闇", "大", "小", "中", "自", "分", "間", "村", "花", "問", "異", "門", "城", "王", "兄", "帯", "道", "行", "街", "屋", "水", "見", "終", "丁", "桜", "先", "生", "長", "今", "了", "点", "井", "子", "言", "太", "属", "風", "会", "性", "持", "時", "勝", "赤", "毎", "年", "火", "改", "計", "画", "休", "体", "波", "回", "外", "地", "病", "正", "造", "値", "合", "戦", "川", "秋", "原", "町", "所", "用", "金", "郎", "作", "数", "方", "社", "攻", "撃", "力", "同", "武", "何", "発", "少", "味", "以", "白", "早", "暮", "面", "組", "後", "文", "字", "本", "階", "明", "才", "者", "立", "泉", "々", "ヶ", "連", "射", "国", "綾", "切", "土", "炎", "伊", "■"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        for charset in [EN_CHARSET, JA_CHARSET] {
+            rom::text::tests::assert_round_trips(&text_parse_options(charset.iter().map(|s| s.to_string()).collect()));
+        }
+    }
+}
@@ -4,6 +4,8 @@ mod save;
 
 use crate::game;
 
+pub(super) use hooks::from_offsets as hooks_from_offsets;
+
 const MATCH_TYPES: &[usize] = &[2, 2];
 
 struct EXE5BImpl;
@@ -57,6 +59,10 @@ impl game::Game for EXE5BImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(BN5P)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -127,6 +133,10 @@ impl game::Game for EXE5CImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(BN5C)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -197,6 +207,10 @@ impl game::Game for BN5PImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(EXE5B)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -267,6 +281,10 @@ impl game::Game for BN5CImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(EXE5C)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
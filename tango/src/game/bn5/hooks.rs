@@ -1,4 +1,4 @@
-use crate::{battle, game, lockstep, replayer, session, shadow, sync};
+use crate::{battle, game, lockstep, net, replayer, session, shadow, sync};
 
 mod munger;
 mod offsets;
@@ -13,6 +13,14 @@ impl Hooks {
     }
 }
 
+/// Builds hooks for a revision of this game that isn't built in, from offsets in a game definition. The hooks are
+/// leaked, as games live for the rest of the program.
+pub fn from_offsets(offsets: toml::Value) -> Result<&'static Hooks, toml::de::Error> {
+    Ok(Box::leak(Box::new(Hooks {
+        offsets: Box::leak(Box::new(offsets.try_into::<offsets::Offsets>()?)),
+    })))
+}
+
 pub static BRBE_00: Hooks = Hooks {
     offsets: &offsets::BRBE_00,
 };
@@ -42,10 +50,24 @@ fn generate_rng2_state(rng: &mut impl rand::Rng) -> u32 {
     rng2_state
 }
 
-fn random_battle_settings_and_background(extended: bool, rng: &mut impl rand::Rng) -> (u8, u8) {
+const NUM_BATTLE_BACKGROUNDS: u16 = 0x1b;
+
+fn num_battle_settings(extended: bool) -> u16 {
+    if !extended {
+        0x44
+    } else {
+        0x60
+    }
+}
+
+fn battle_settings_and_background(
+    extended: bool,
+    rng: &mut impl rand::Rng,
+    stage: net::protocol::StageSelection,
+) -> (u8, u8) {
     (
-        rng.gen_range(0..if !extended { 0x44u8 } else { 0x60 }),
-        rng.gen_range(0..0x1bu8),
+        game::stage::pick_stage_choice(rng, num_battle_settings(extended), stage.battle_settings) as u8,
+        game::stage::pick_stage_choice(rng, NUM_BATTLE_BACKGROUNDS, stage.background) as u8,
     )
 }
 
@@ -55,6 +77,77 @@ fn step_rng(seed: u32) -> u32 {
 }
 
 impl game::Hooks for Hooks {
+    fn offsets(&self) -> toml::Value {
+        toml::Value::try_from(self.offsets).unwrap()
+    }
+
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![self.offsets.ewram.rng2_state, self.offsets.ewram.rng3_state]
+    }
+
+    fn battle_state_fields(&self) -> Vec<game::field::Field> {
+        vec![
+            game::field::Field {
+                name: "battle state",
+                address: self.offsets.ewram.battle_state,
+                kind: game::field::FieldKind::Bytes(0x80),
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tick",
+                address: self.offsets.ewram.battle_state + 0x60,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng1",
+                address: self.offsets.ewram.rng1_state,
+                kind: game::field::FieldKind::U32,
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rng2",
+                address: self.offsets.ewram.rng2_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng3",
+                address: self.offsets.ewram.rng3_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tx packet",
+                address: self.offsets.ewram.tx_packet,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P1)",
+                address: self.offsets.ewram.rx_packet_arr,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P2)",
+                address: self.offsets.ewram.rx_packet_arr + 0x10,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "copy input state",
+                address: self.offsets.ewram.copy_data_input_state,
+                kind: game::field::FieldKind::U8,
+                per_side: false,
+            },
+        ]
+    }
+
+    fn num_stage_choices(&self, match_type: (u8, u8)) -> (u16, u16) {
+        (num_battle_settings(match_type.1 == 1), NUM_BATTLE_BACKGROUNDS)
+    }
+
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> {
         vec![
             (self.offsets.rom.start_screen_jump_table_entry, {
@@ -265,7 +358,7 @@ impl game::Hooks for Hooks {
 
                     let mut rng = sync::block_on(match_.lock_rng());
                     let (battle_settings, background) =
-                        random_battle_settings_and_background(match_.match_type().1 == 1, &mut *rng);
+                        battle_settings_and_background(match_.match_type().1 == 1, &mut *rng, match_.stage());
                     munger.set_battle_settings_and_background(core, battle_settings, background);
                 })
             }),
@@ -498,8 +591,11 @@ impl game::Hooks for Hooks {
                 let munger = self.munger();
                 Box::new(move |core| {
                     let mut rng = shadow_state.lock_rng();
-                    let (battle_settings, background) =
-                        random_battle_settings_and_background(shadow_state.match_type().1 == 1, &mut *rng);
+                    let (battle_settings, background) = battle_settings_and_background(
+                        shadow_state.match_type().1 == 1,
+                        &mut *rng,
+                        shadow_state.stage(),
+                    );
                     munger.set_battle_settings_and_background(core, battle_settings, background);
                 })
             }),
@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct EWRAMOffsets {
     // Outgoing packet.
     pub(super) tx_packet: u32,
@@ -33,7 +33,7 @@ pub(super) struct EWRAMOffsets {
     pub(super) copy_data_input_state: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub(super) struct ROMOffsets {
     /// This is the entry point for the start screen, i.e. when the CAPCOM logo is displayed.
     ///
@@ -144,7 +144,7 @@ static EWRAM_OFFSETS: EWRAMOffsets = EWRAMOffsets {
     copy_data_input_state:  0x0203f245,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct Offsets {
     pub(super) rom: ROMOffsets,
     pub(super) ewram: EWRAMOffsets,
@@ -432,20 +432,7 @@ impl Assets {
 
         Self {
             offsets,
-            text_parse_options: rom::text::ParseOptions {
-                charset,
-                extension_ops: 0xe4..=0xe4,
-                eof_op: 0xe6,
-                newline_op: 0xe9,
-                commands: std::collections::HashMap::from([
-                    (PRINT_VAR_COMMAND, 3),
-                    (EREADER_COMMAND, 2),
-                    (0xe7, 1),
-                    (0xe8, 3),
-                    (0xee, 3),
-                    (0xf1, 2),
-                ]),
-            },
+            text_parse_options: text_parse_options(charset),
             mapper,
             chip_icon_palette,
             element_icon_palette,
@@ -619,6 +606,80 @@ impl rom::Assets for Assets {
     fn num_modcard56s(&self) -> usize {
         112
     }
+
+    fn text_parse_options(&self) -> &rom::text::ParseOptions {
+        &self.text_parse_options
+    }
+
+    fn text_archives(&self) -> Vec<rom::TextArchive> {
+        let mut pointers = vec![];
+        for i in 0..(self.num_chips() + 0xff) / 0x100 {
+            pointers.push((
+                format!("chip_names_{}", i),
+                self.offsets.chip_names_pointers + (i * 4) as u32,
+            ));
+            pointers.push((
+                format!("chip_descriptions_{}", i),
+                self.offsets.chip_descriptions_pointers + (i * 4) as u32,
+            ));
+        }
+        pointers.push(("ncp_names".to_string(), self.offsets.ncp_names_pointer));
+        pointers.push(("ncp_descriptions".to_string(), self.offsets.ncp_descriptions_pointer));
+        if self.offsets.modcard_names_pointer != 0 {
+            pointers.push(("modcard_names".to_string(), self.offsets.modcard_names_pointer));
+        }
+        if self.offsets.modcard_details_names_pointer != 0 {
+            pointers.push((
+                "modcard_details_names".to_string(),
+                self.offsets.modcard_details_names_pointer,
+            ));
+        }
+        rom::read_text_archives(&self.mapper, &self.text_parse_options, pointers)
+    }
+
+    fn graphics_presets(&self) -> Result<Vec<rom::graphics::Preset>, anyhow::Error> {
+        let mut presets = vec![rom::graphics::Preset {
+            name: "element_icons".to_string(),
+            sheet: rom::graphics::Sheet {
+                address: rom::graphics::deref(&self.mapper, self.offsets.element_icons_pointer)?,
+                compressed: false,
+                num_tiles: 13 * 4,
+                cols: 2,
+                bpp: rom::graphics::Bpp::Four,
+                palette_address: Some(rom::graphics::deref(
+                    &self.mapper,
+                    self.offsets.element_icon_palette_pointer,
+                )?),
+            },
+        }];
+        presets.extend(rom::graphics::chip_presets(
+            &self.mapper,
+            self.offsets.chip_data,
+            0x2c,
+            self.num_chips(),
+            0x20,
+            (7, 6),
+            rom::graphics::deref(&self.mapper, self.offsets.chip_icon_palette_pointer)?,
+        )?);
+        Ok(presets)
+    }
+}
+
+fn text_parse_options(charset: Vec<String>) -> rom::text::ParseOptions {
+    rom::text::ParseOptions {
+        charset,
+        extension_ops: 0xe4..=0xe4,
+        eof_op: 0xe6,
+        newline_op: 0xe9,
+        commands: std::collections::HashMap::from([
+            (PRINT_VAR_COMMAND, 3),
+            (EREADER_COMMAND, 2),
+            (0xe7, 1),
+            (0xe8, 3),
+            (0xee, 3),
+            (0xf1, 2),
+        ]),
+    }
 }
 
 #[rustfmt::skip]
@@ -626,3 +687,15 @@ pub const EN_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "
 
 #[rustfmt::skip]
 pub const JA_CHARSET: &[&str] = &[" ", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "ウ", "ア", "イ", "オ", "エ", "ケ", "コ", "カ", "ク", "キ", "セ", "サ", "ソ", "シ", "ス", "テ", "ト", "ツ", "タ", "チ", "ネ", "ノ", "ヌ", "ナ", "ニ", "ヒ", "ヘ", "ホ", "ハ", "フ", "ミ", "マ", "メ", "ム", "モ", "ヤ", "ヨ", "ユ", "ロ", "ル", "リ", "レ", "ラ", "ン", "熱", "斗", "ワ", "ヲ", "ギ", "ガ", "ゲ", "ゴ", "グ", "ゾ", "ジ", "ゼ", "ズ", "ザ", "デ", "ド", "ヅ", "ダ", "ヂ", "ベ", "ビ", "ボ", "バ", "ブ", "ピ", "パ", "ペ", "プ", "ポ", "ゥ", "ァ", "ィ", "ォ", "ェ", "ュ", "ヴ", "ッ", "ョ", "ャ", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "*", "-", "×", "=", ":", "%", "?", "+", "■", "[bat]", "ー", "!", "SP", "DS", "&", "、", "゜", ".", "・", ";", "’", "\"", "~", "/", "(", ")", "「", "」", "V2", "V3", "V4", "V5", "_", "[z]", "周", "え", "お", "う", "あ", "い", "け", "く", "き", "こ", "か", "せ", "そ", "す", "さ", "し", "つ", "と", "て", "た", "ち", "ね", "の", "な", "ぬ", "に", "へ", "ふ", "ほ", "は", "ひ", "め", "む", "み", "も", "ま", "ゆ", "よ", "や", "る", "ら", "り", "ろ", "れ", "究", "ん", "を", "わ", "研", "げ", "ぐ", "ご", "が", "ぎ", "ぜ", "ず", "じ", "ぞ", "ざ", "で", "ど", "づ", "だ", "ぢ", "べ", "ば", "び", "ぼ", "ぶ", "ぽ", "ぷ", "ぴ", "ぺ", "ぱ", "ぅ", "ぁ", "ぃ", "ぉ", "ぇ", "ゅ", "ょ", "っ", "ゃ", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z", "容", "量", "全", "木", "[MB]", "無", "現", "実", "[circle]", "[cross]", "緑", "尺", "不", "止", "彩", "起", "父", "集", "院", "一", "二", "三", "四", "五", "六", "七", "八", "陽", "十", "百", "千", "万", "脳", "上", "下", "左", "右", "手", "足", "日", "目", "月", "玉", "各", "人", "入", "出", "山", "口", "光", "電", "気", "助", "科", "次", "名", "前", "学", "校", "省", "祐", "室", "世", "界", "燃", "朗", "枚", "島", "悪", "路", "闇", "大", "小", "中", "自", "分", "間", "系", "花", "問", "異", "門", "城", "王", "兄", "化", "行", "街", "屋", "水", "見", "終", "丁", "桜", "先", "生", "長", "今", "了", "点", "井", "子", "言", "太", "属", "風", "会", "性", "持", "時", "勝", "赤", "毎", "年", "火", "改", "計", "画", "休", "体", "波", "回", "外", "地", "病", "正", "造", "値", "合", "戦", "川", "秋", "原", "町", "所", "用", "金", "郎", "作", "数", "方", "社", "攻", "撃", "力", "同", "武", "何", "発", "少", "以", "白", "早", "暮", "面", "組", "後", "文", "字", "本", "階", "明", "才", "者", "立", "泉", "々", "ヶ", "連", "射", "国", "綾", "切", "土", "炎", "伊"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_round_trip() {
+        for charset in [EN_CHARSET, JA_CHARSET] {
+            rom::text::tests::assert_round_trips(&text_parse_options(charset.iter().map(|s| s.to_string()).collect()));
+        }
+    }
+}
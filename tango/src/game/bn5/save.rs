@@ -101,6 +101,44 @@ impl Save {
                 Variant::Colonel => 0x18,
             }
     }
+
+    fn game_name(&self) -> &'static [u8; 20] {
+        match self.game_info {
+            GameInfo {
+                region: Region::JP,
+                variant: Variant::Protoman,
+            } => b"REXE5TOB 20041104 JP",
+            GameInfo {
+                region: Region::JP,
+                variant: Variant::Colonel,
+            } => b"REXE5TOK 20041104 JP",
+            GameInfo {
+                region: Region::US,
+                variant: Variant::Protoman,
+            } => b"REXE5TOB 20041006 US",
+            GameInfo {
+                region: Region::US,
+                variant: Variant::Colonel,
+            } => b"REXE5TOK 20041006 US",
+        }
+    }
+
+    fn rebuild_checksum(&mut self) {
+        let checksum = self.compute_checksum();
+        byteorder::LittleEndian::write_u32(&mut self.buf[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4], checksum);
+    }
+
+    /// Converts the save to the given region.
+    ///
+    /// Both releases share the same save layout, so only the game name and checksum need to be rewritten.
+    pub fn convert_region(&self, region: Region) -> Self {
+        let mut save = self.clone();
+        save.game_info.region = region;
+        let game_name = save.game_name();
+        save.buf[GAME_NAME_OFFSET..GAME_NAME_OFFSET + 20].copy_from_slice(game_name);
+        save.rebuild_checksum();
+        save
+    }
 }
 
 impl save::Save for Save {
@@ -124,6 +162,17 @@ impl save::Save for Save {
         Some(Box::new(DarkAIView { save: self }))
     }
 
+    fn view_stats(&self) -> Option<Box<dyn save::StatsView + '_>> {
+        Some(Box::new(StatsView { save: self }))
+    }
+
+    fn to_region_counterpart(&self) -> Option<Box<dyn save::Save + Send + Sync>> {
+        Some(Box::new(self.convert_region(match self.game_info.region {
+            Region::US => Region::JP,
+            Region::JP => Region::US,
+        })))
+    }
+
     fn as_raw_wram(&self) -> &[u8] {
         &self.buf
     }
@@ -276,3 +325,16 @@ impl<'a> save::NaviView<'a> for NaviView<'a> {
         self.save.buf[0x2940] as usize
     }
 }
+
+pub struct StatsView<'a> {
+    save: &'a Save,
+}
+
+impl<'a> save::StatsView<'a> for StatsView<'a> {
+    fn pack_count(&self, id: usize, variant: usize) -> Option<usize> {
+        if variant >= 0xc {
+            return None;
+        }
+        self.save.buf.get(0x2eac + id * 0xc + variant).map(|v| *v as usize)
+    }
+}
@@ -4,7 +4,9 @@ mod save;
 
 use crate::game;
 
-const MATCH_TYPES: &[usize] = &[1, 1];
+pub(super) use hooks::from_offsets as hooks_from_offsets;
+
+const MATCH_TYPES: &[usize] = &[2, 1];
 
 struct EXE6GImpl;
 pub const EXE6G: &'static (dyn game::Game + Send + Sync) = &EXE6GImpl {};
@@ -57,6 +59,10 @@ impl game::Game for EXE6GImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(BN6G)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -124,6 +130,10 @@ impl game::Game for EXE6FImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(BN6F)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -191,6 +201,10 @@ impl game::Game for BN6GImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(EXE6G)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
@@ -258,6 +272,10 @@ impl game::Game for BN6FImpl {
         )?))
     }
 
+    fn region_counterpart(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(EXE6F)
+    }
+
     fn load_rom_assets(
         &self,
         rom: &[u8],
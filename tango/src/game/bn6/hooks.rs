@@ -1,4 +1,4 @@
-use crate::{battle, game, lockstep, replayer, session, shadow, sync};
+use crate::{battle, game, lockstep, net, replayer, session, shadow, sync};
 
 mod munger;
 mod offsets;
@@ -13,6 +13,14 @@ impl Hooks {
     }
 }
 
+/// Builds hooks for a revision of this game that isn't built in, from offsets in a game definition. The hooks are
+/// leaked, as games live for the rest of the program.
+pub fn from_offsets(offsets: toml::Value) -> Result<&'static Hooks, toml::de::Error> {
+    Ok(Box::leak(Box::new(Hooks {
+        offsets: Box::leak(Box::new(offsets.try_into::<offsets::Offsets>()?)),
+    })))
+}
+
 pub static BR6E_00: Hooks = Hooks {
     offsets: &offsets::MEGAMAN6_FXXBR6E_00,
 };
@@ -42,20 +50,30 @@ fn generate_rng2_state(rng: &mut impl rand::Rng) -> u32 {
     rng2_state
 }
 
-fn random_battle_settings_and_background(rng: &mut impl rand::Rng, match_type: u8) -> u16 {
-    const BATTLE_BACKGROUNDS: &[u16] = &[
-        0x00, 0x01, 0x01, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11,
-        0x11, 0x13, 0x13,
-    ];
+const BATTLE_BACKGROUNDS: &[u16] = &[
+    0x00, 0x01, 0x01, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x11,
+    0x13, 0x13,
+];
+
+/// Number of battle settings a match type picks from. Singles normally only use the first 0x44, like in BN5, but can
+/// use all of them like triples do.
+fn num_battle_settings(match_type: (u8, u8)) -> u16 {
+    match match_type {
+        (0, 0) => 0x44,
+        (0, 1) | (1, 0) => 0x60,
+        _ => 0,
+    }
+}
 
-    let lo = match match_type {
-        0 => rng.gen_range(0..0x44u16),
-        1 => rng.gen_range(0..0x60u16),
-        2 => rng.gen_range(0..0x44u16) + 0x60u16,
-        _ => 0u16,
-    };
+fn battle_settings_and_background(
+    rng: &mut impl rand::Rng,
+    match_type: (u8, u8),
+    stage: net::protocol::StageSelection,
+) -> u16 {
+    let lo = game::stage::pick_stage_choice(rng, num_battle_settings(match_type), stage.battle_settings);
 
-    let hi = BATTLE_BACKGROUNDS[rng.gen_range(0..BATTLE_BACKGROUNDS.len())];
+    let hi = BATTLE_BACKGROUNDS
+        [game::stage::pick_stage_choice(rng, BATTLE_BACKGROUNDS.len() as u16, stage.background) as usize];
 
     hi << 0x8 | lo
 }
@@ -66,6 +84,87 @@ fn step_rng(seed: u32) -> u32 {
 }
 
 impl game::Hooks for Hooks {
+    fn offsets(&self) -> toml::Value {
+        toml::Value::try_from(self.offsets).unwrap()
+    }
+
+    fn lockable_rng_states(&self) -> Vec<u32> {
+        vec![self.offsets.ewram.rng2_state, self.offsets.ewram.rng3_state]
+    }
+
+    fn battle_state_fields(&self) -> Vec<game::field::Field> {
+        vec![
+            game::field::Field {
+                name: "battle state",
+                address: self.offsets.ewram.battle_state,
+                kind: game::field::FieldKind::Bytes(0x80),
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tick",
+                address: self.offsets.ewram.battle_state + 0x60,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng1",
+                address: self.offsets.ewram.rng1_state,
+                kind: game::field::FieldKind::U32,
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rng2",
+                address: self.offsets.ewram.rng2_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "rng3",
+                address: self.offsets.ewram.rng3_state,
+                kind: game::field::FieldKind::U32,
+                per_side: false,
+            },
+            game::field::Field {
+                name: "tx packet",
+                address: self.offsets.ewram.tx_packet,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P1)",
+                address: self.offsets.ewram.rx_packet_arr,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "rx packet (P2)",
+                address: self.offsets.ewram.rx_packet_arr + 0x10,
+                kind: game::field::FieldKind::Bytes(0x10),
+                per_side: true,
+            },
+            game::field::Field {
+                name: "copy input state",
+                address: self.offsets.ewram.copy_data_input_state,
+                kind: game::field::FieldKind::U8,
+                per_side: false,
+            },
+        ]
+    }
+
+    fn num_stage_choices(&self, match_type: (u8, u8)) -> (u16, u16) {
+        (num_battle_settings(match_type), BATTLE_BACKGROUNDS.len() as u16)
+    }
+
+    fn stage_choice_labels(&self, match_type: (u8, u8)) -> (Vec<String>, Vec<String>) {
+        // The names the game gives these aren't known, so they are labelled by the IDs the game uses for them instead.
+        (
+            (0..num_battle_settings(match_type))
+                .map(|i| format!("{:#04x}", i))
+                .collect(),
+            BATTLE_BACKGROUNDS.iter().map(|id| format!("{:#04x}", id)).collect(),
+        )
+    }
+
     fn common_traps(&self) -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> {
         vec![
             (self.offsets.rom.start_screen_jump_table_entry, {
@@ -277,7 +376,7 @@ impl game::Hooks for Hooks {
                     let mut rng = sync::block_on(match_.lock_rng());
                     munger.set_link_battle_settings_and_background(
                         core,
-                        random_battle_settings_and_background(&mut *rng, match_.match_type().0),
+                        battle_settings_and_background(&mut *rng, match_.match_type(), match_.stage()),
                     );
                 })
             }),
@@ -524,7 +623,7 @@ impl game::Hooks for Hooks {
                     let mut rng = shadow_state.lock_rng();
                     munger.set_link_battle_settings_and_background(
                         core,
-                        random_battle_settings_and_background(&mut *rng, shadow_state.match_type().0),
+                        battle_settings_and_background(&mut *rng, shadow_state.match_type(), shadow_state.stage()),
                     );
                 })
             }),
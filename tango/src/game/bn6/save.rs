@@ -7,6 +7,22 @@ const SRAM_SIZE: usize = 0x6710;
 const MASK_OFFSET: usize = 0x1064;
 const GAME_NAME_OFFSET: usize = 0x1c70;
 const CHECKSUM_OFFSET: usize = 0x1c6c;
+const MODCARD_COUNT_OFFSET: usize = 0x65f0;
+const MODCARD_LIST_OFFSET: usize = 0x6620;
+const MODCARD_LIST_SIZE: usize = 0xf0;
+
+// Everything before this offset is laid out the same in both releases. After it, the US release inserts 0x40 bytes
+// before the NaviCust, and the JP release keeps modcards at the end of the save.
+const REGION_SPECIFIC_START_OFFSET: usize = 0x4150;
+
+// Sections after REGION_SPECIFIC_START_OFFSET that are known to hold the same data in both releases, as
+// (JP offset, US offset, size).
+const REGION_SPECIFIC_SECTIONS: &[(usize, usize, usize)] = &[
+    // NaviCust parts.
+    (0x4150, 0x4190, 25 * 8),
+    // Navi stats, one block per navi.
+    (0x478c, 0x47cc, 2 * 0x64),
+];
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Region {
@@ -102,6 +118,96 @@ impl Save {
             }
     }
 
+    fn game_name(&self) -> &'static [u8; 20] {
+        match self.game_info {
+            GameInfo {
+                region: Region::JP,
+                variant: Variant::Gregar,
+            } => b"REXE6 G 20050924a JP",
+            GameInfo {
+                region: Region::JP,
+                variant: Variant::Falzar,
+            } => b"REXE6 F 20050924a JP",
+            GameInfo {
+                region: Region::US,
+                variant: Variant::Gregar,
+            } => b"REXE6 G 20060110a US",
+            GameInfo {
+                region: Region::US,
+                variant: Variant::Falzar,
+            } => b"REXE6 F 20060110a US",
+        }
+    }
+
+    fn rebuild_checksum(&mut self) {
+        let checksum = self.compute_checksum();
+        byteorder::LittleEndian::write_u32(&mut self.buf[CHECKSUM_OFFSET..CHECKSUM_OFFSET + 4], checksum);
+    }
+
+    /// Whether an offset in the region-specific area holds data conversion copies over.
+    fn is_mapped(&self, offset: usize) -> bool {
+        REGION_SPECIFIC_SECTIONS.iter().any(|&(jp_offset, us_offset, size)| {
+            let section_offset = match self.game_info.region {
+                Region::JP => jp_offset,
+                Region::US => us_offset,
+            };
+            (section_offset..section_offset + size).contains(&offset)
+        })
+    }
+
+    fn is_modcard_data(&self, offset: usize) -> bool {
+        self.game_info.region == Region::JP
+            && (offset == MODCARD_COUNT_OFFSET
+                || (MODCARD_LIST_OFFSET..MODCARD_LIST_OFFSET + MODCARD_LIST_SIZE).contains(&offset))
+    }
+
+    /// Lists what convert_region drops: the JP-only modcards, and any data in the region-specific area outside the
+    /// sections whose layout is known in both releases.
+    pub fn region_conversion_losses(&self) -> Vec<save::RegionConversionLoss> {
+        let mut losses = vec![];
+        if self.game_info.region == Region::JP && self.buf[MODCARD_COUNT_OFFSET] > 0 {
+            losses.push(save::RegionConversionLoss::Modcards(
+                self.buf[MODCARD_COUNT_OFFSET] as usize,
+            ));
+        }
+        let unmapped_bytes = (REGION_SPECIFIC_START_OFFSET..SRAM_SIZE)
+            .filter(|offset| !self.is_mapped(*offset) && !self.is_modcard_data(*offset) && self.buf[*offset] != 0)
+            .count();
+        if unmapped_bytes > 0 {
+            losses.push(save::RegionConversionLoss::UnmappedBytes(unmapped_bytes));
+        }
+        losses
+    }
+
+    /// Converts the save to the given region.
+    ///
+    /// The shared part of the save and the known region-specific sections (NaviCust and navi stats) are copied over.
+    /// The rest of the region-specific area, including the JP-only modcards, is cleared: region_conversion_losses
+    /// lists what that drops, so it can be confirmed first.
+    pub fn convert_region(&self, region: Region) -> Self {
+        let mut save = self.clone();
+        if self.game_info.region == region {
+            return save;
+        }
+
+        save.game_info.region = region;
+        save.buf[REGION_SPECIFIC_START_OFFSET..].fill(0);
+        for &(jp_offset, us_offset, size) in REGION_SPECIFIC_SECTIONS {
+            let (src_offset, dst_offset) = match region {
+                Region::US => (jp_offset, us_offset),
+                Region::JP => (us_offset, jp_offset),
+            };
+            save.buf[dst_offset..dst_offset + size].copy_from_slice(&self.buf[src_offset..src_offset + size]);
+        }
+        save.buf[MODCARD_COUNT_OFFSET] = 0;
+        save.buf[MODCARD_LIST_OFFSET..MODCARD_LIST_OFFSET + MODCARD_LIST_SIZE].fill(0);
+
+        let game_name = save.game_name();
+        save.buf[GAME_NAME_OFFSET..GAME_NAME_OFFSET + 20].copy_from_slice(game_name);
+        save.rebuild_checksum();
+        save
+    }
+
     fn navi_stats_offset(&self, id: usize) -> usize {
         (if self.game_info.region == Region::JP {
             0x478c
@@ -132,6 +238,21 @@ impl save::Save for Save {
     //     Some(Box::new(NaviView { save: self }))
     // }
 
+    fn view_stats(&self) -> Option<Box<dyn save::StatsView + '_>> {
+        Some(Box::new(StatsView { save: self }))
+    }
+
+    fn to_region_counterpart(&self) -> Option<Box<dyn save::Save + Send + Sync>> {
+        Some(Box::new(self.convert_region(match self.game_info.region {
+            Region::US => Region::JP,
+            Region::JP => Region::US,
+        })))
+    }
+
+    fn region_conversion_losses(&self) -> Vec<save::RegionConversionLoss> {
+        Save::region_conversion_losses(self)
+    }
+
     fn as_raw_wram(&self) -> &[u8] {
         &self.buf
     }
@@ -204,14 +325,14 @@ pub struct Modcard56sView<'a> {
 
 impl<'a> save::Modcard56sView<'a> for Modcard56sView<'a> {
     fn count(&self) -> usize {
-        self.save.buf[0x65f0] as usize
+        self.save.buf[MODCARD_COUNT_OFFSET] as usize
     }
 
     fn modcard(&self, slot: usize) -> Option<save::Modcard> {
         if slot >= self.count() {
             return None;
         }
-        let raw = self.save.buf[0x6620 + slot];
+        let raw = self.save.buf[MODCARD_LIST_OFFSET + slot];
         Some(save::Modcard {
             id: (raw & 0x7f) as usize,
             enabled: raw >> 7 == 0,
@@ -276,3 +397,232 @@ impl<'a> save::NaviView<'a> for NaviView<'a> {
         self.save.buf[0x1b81] as usize
     }
 }
+
+pub struct StatsView<'a> {
+    save: &'a Save,
+}
+
+impl<'a> save::StatsView<'a> for StatsView<'a> {
+    fn hp(&self) -> Option<u16> {
+        let navi_stats_offset = self.save.navi_stats_offset(NaviView { save: self.save }.navi());
+        Some(byteorder::LittleEndian::read_u16(
+            &self.save.buf[navi_stats_offset + 0x3e..navi_stats_offset + 0x40],
+        ))
+    }
+
+    fn max_hp(&self) -> Option<u16> {
+        let navi_stats_offset = self.save.navi_stats_offset(NaviView { save: self.save }.navi());
+        Some(byteorder::LittleEndian::read_u16(
+            &self.save.buf[navi_stats_offset + 0x40..navi_stats_offset + 0x42],
+        ))
+    }
+
+    fn pack_count(&self, id: usize, variant: usize) -> Option<usize> {
+        if variant >= 0xc {
+            return None;
+        }
+        self.save.buf.get(0x2230 + id * 0xc + variant).map(|v| *v as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save::Save as _;
+
+    use super::*;
+
+    fn jp_save() -> Save {
+        let mut buf = vec![0u8; SRAM_SIZE];
+        let mut x = 0x12345678u32;
+        for b in buf.iter_mut() {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            *b = x as u8;
+        }
+        // Keep the folders and equipped navi in range so the views read real data.
+        buf[0x1c09] = 3;
+        for i in 0..3 * 30 {
+            byteorder::LittleEndian::write_u16(&mut buf[0x2178 + i * 2..], (i as u16 % 0x1ff) | (i as u16 % 27) << 9);
+        }
+        buf[0x1b81] = 0;
+        buf[MODCARD_COUNT_OFFSET] = 5;
+        let mut save = Save::from_wram(
+            &buf,
+            GameInfo {
+                region: Region::JP,
+                variant: Variant::Falzar,
+            },
+        )
+        .unwrap();
+        let game_name = save.game_name();
+        save.buf[GAME_NAME_OFFSET..GAME_NAME_OFFSET + 20].copy_from_slice(game_name);
+        save.rebuild_checksum();
+        save
+    }
+
+    #[test]
+    fn test_convert_region_round_trip() {
+        let jp = jp_save();
+        let us = jp.convert_region(Region::US);
+        assert_eq!(us.game_info().region, Region::US);
+        assert!(us.view_modcards().is_none());
+
+        let round_tripped = us.convert_region(Region::JP);
+        assert_eq!(round_tripped.game_info(), jp.game_info());
+
+        // Everything the two releases share survives as is, except for the checksum over the cleared bytes.
+        assert_eq!(round_tripped.buf[..CHECKSUM_OFFSET], jp.buf[..CHECKSUM_OFFSET]);
+        assert_eq!(
+            round_tripped.buf[CHECKSUM_OFFSET + 4..REGION_SPECIFIC_START_OFFSET],
+            jp.buf[CHECKSUM_OFFSET + 4..REGION_SPECIFIC_START_OFFSET]
+        );
+        for &(jp_offset, _, size) in REGION_SPECIFIC_SECTIONS {
+            assert_eq!(
+                round_tripped.buf[jp_offset..jp_offset + size],
+                jp.buf[jp_offset..jp_offset + size]
+            );
+        }
+
+        for save in [&us, &round_tripped] {
+            let (chips, expected_chips) = (save.view_chips().unwrap(), jp.view_chips().unwrap());
+            assert_eq!(chips.equipped_folder_index(), expected_chips.equipped_folder_index());
+            for folder_index in 0..expected_chips.num_folders() {
+                for chip_index in 0..30 {
+                    assert_eq!(
+                        chips.chip(folder_index, chip_index),
+                        expected_chips.chip(folder_index, chip_index)
+                    );
+                }
+            }
+
+            let (navicust, expected_navicust) = (save.view_navicust().unwrap(), jp.view_navicust().unwrap());
+            for i in 0..25 {
+                assert_eq!(navicust.navicust_part(i), expected_navicust.navicust_part(i));
+            }
+
+            let (stats, expected_stats) = (save.view_stats().unwrap(), jp.view_stats().unwrap());
+            assert_eq!(stats.hp(), expected_stats.hp());
+            assert_eq!(stats.max_hp(), expected_stats.max_hp());
+        }
+
+        // Modcards are JP-only, so they are cleared rather than carried through the US save.
+        assert_eq!(round_tripped.buf[MODCARD_COUNT_OFFSET], 0);
+        assert!(
+            round_tripped.buf[MODCARD_LIST_OFFSET..MODCARD_LIST_OFFSET + MODCARD_LIST_SIZE]
+                .iter()
+                .all(|b| *b == 0)
+        );
+
+        // The converted save has to load as a real save again.
+        assert!(Save::new(&round_tripped.to_vec()).is_ok());
+        assert!(Save::new(&us.to_vec()).is_ok());
+    }
+
+    fn save_with_fields(region: Region) -> Save {
+        let mut save = Save::from_wram(
+            &vec![0u8; SRAM_SIZE],
+            GameInfo {
+                region,
+                variant: Variant::Gregar,
+            },
+        )
+        .unwrap();
+        save.buf[0x1c09] = 1;
+        // Folder 0, chip 0: chip 0x123 with code C.
+        byteorder::LittleEndian::write_u16(&mut save.buf[0x2178..], 0x123 | 2 << 9);
+        // Pack: 7 copies of chip 5 with code variant 1.
+        save.buf[0x2230 + 5 * 0xc + 1] = 7;
+        // NaviCust part 0: part 10 variant 2 at column 3, row 4, rotated once, compressed.
+        let ncp_offset = if region == Region::JP { 0x4150 } else { 0x4190 };
+        save.buf[ncp_offset..ncp_offset + 8].copy_from_slice(&[10 * 4 + 2, 0, 0, 3, 4, 1, 1, 0]);
+        // MegaMan's HP and max HP.
+        let navi_stats_offset = save.navi_stats_offset(0);
+        byteorder::LittleEndian::write_u16(&mut save.buf[navi_stats_offset + 0x3e..], 0x1234);
+        byteorder::LittleEndian::write_u16(&mut save.buf[navi_stats_offset + 0x40..], 0x2345);
+        let game_name = save.game_name();
+        save.buf[GAME_NAME_OFFSET..GAME_NAME_OFFSET + 20].copy_from_slice(game_name);
+        save.rebuild_checksum();
+        save
+    }
+
+    #[test]
+    fn test_convert_region_keeps_fields() {
+        for (from, to) in [(Region::JP, Region::US), (Region::US, Region::JP)] {
+            let converted = save_with_fields(from).convert_region(to);
+            assert_eq!(converted.game_info().region, to);
+
+            let chips = converted.view_chips().unwrap();
+            assert_eq!(chips.chip(0, 0), Some(save::Chip { id: 0x123, code: 'C' }));
+
+            assert_eq!(
+                converted.view_navicust().unwrap().navicust_part(0),
+                Some(save::NavicustPart {
+                    id: 10,
+                    variant: 2,
+                    col: 3,
+                    row: 4,
+                    rot: 1,
+                    compressed: true,
+                })
+            );
+
+            let stats = converted.view_stats().unwrap();
+            assert_eq!(stats.pack_count(5, 1), Some(7));
+            assert_eq!(stats.hp(), Some(0x1234));
+            assert_eq!(stats.max_hp(), Some(0x2345));
+
+            assert!(Save::new(&converted.to_vec()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_region_conversion_losses() {
+        // Everything in these saves is in a known section.
+        assert_eq!(Save::region_conversion_losses(&save_with_fields(Region::JP)), vec![]);
+        assert_eq!(Save::region_conversion_losses(&save_with_fields(Region::US)), vec![]);
+
+        let mut jp = save_with_fields(Region::JP);
+        jp.buf[MODCARD_COUNT_OFFSET] = 2;
+        jp.buf[MODCARD_LIST_OFFSET] = 0x12;
+        // Between the NaviCust and the navi stats, which isn't mapped between releases.
+        jp.buf[0x4500] = 1;
+        jp.buf[0x4501] = 1;
+        assert_eq!(
+            Save::region_conversion_losses(&jp),
+            vec![
+                save::RegionConversionLoss::Modcards(2),
+                save::RegionConversionLoss::UnmappedBytes(2)
+            ]
+        );
+
+        // The modcard area of a US save isn't modcards, and is unmapped like the rest.
+        let mut us = save_with_fields(Region::US);
+        us.buf[MODCARD_COUNT_OFFSET] = 2;
+        assert_eq!(
+            Save::region_conversion_losses(&us),
+            vec![save::RegionConversionLoss::UnmappedBytes(1)]
+        );
+    }
+
+    #[test]
+    fn test_region_conversion_losses_match_what_is_dropped() {
+        let jp = jp_save();
+        let round_tripped = jp.convert_region(Region::US).convert_region(Region::JP);
+        let changed = (REGION_SPECIFIC_START_OFFSET..SRAM_SIZE)
+            .filter(|offset| round_tripped.buf[*offset] != jp.buf[*offset])
+            .count();
+        let lost = Save::region_conversion_losses(&jp)
+            .into_iter()
+            .map(|loss| match loss {
+                save::RegionConversionLoss::Modcards(_) => 0,
+                save::RegionConversionLoss::UnmappedBytes(n) => n,
+            })
+            .sum::<usize>();
+        let modcard_bytes = std::iter::once(MODCARD_COUNT_OFFSET)
+            .chain(MODCARD_LIST_OFFSET..MODCARD_LIST_OFFSET + MODCARD_LIST_SIZE)
+            .filter(|offset| jp.buf[*offset] != 0)
+            .count();
+        assert_eq!(changed, lost + modcard_bytes);
+    }
+}
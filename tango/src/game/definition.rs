@@ -0,0 +1,293 @@
+use crate::{game, rom, save};
+
+#[derive(serde::Deserialize)]
+struct BaseGame {
+    family: String,
+    variant: u8,
+}
+
+#[derive(serde::Deserialize)]
+struct Definition {
+    rom_code: String,
+    revision: u8,
+    crc32: u32,
+    family: String,
+    variant: u8,
+    language: String,
+    match_types: Vec<usize>,
+
+    /// Which built-in hook family's traps to use with the offsets below.
+    hooks: String,
+
+    /// Built-in game whose save format and strings this game uses.
+    base: BaseGame,
+
+    /// EWRAM and ROM offsets for the hook family, in the same shape as its offsets::Offsets.
+    offsets: toml::Value,
+}
+
+struct DefinedGame {
+    rom_code: [u8; 4],
+    revision: u8,
+    crc32: u32,
+    family: String,
+    variant: u8,
+    language: unic_langid::LanguageIdentifier,
+    match_types: Vec<usize>,
+    hooks: &'static (dyn game::Hooks + Send + Sync),
+    base: &'static (dyn game::Game + Send + Sync),
+}
+
+impl game::Game for DefinedGame {
+    fn rom_code_and_revision(&self) -> (&[u8; 4], u8) {
+        (&self.rom_code, self.revision)
+    }
+
+    fn family_and_variant(&self) -> (&str, u8) {
+        (&self.family, self.variant)
+    }
+
+    fn strings_family_and_variant(&self) -> (&str, u8) {
+        self.base.strings_family_and_variant()
+    }
+
+    fn language(&self) -> unic_langid::LanguageIdentifier {
+        self.language.clone()
+    }
+
+    fn expected_crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    fn match_types(&self) -> &[usize] {
+        &self.match_types
+    }
+
+    fn hooks(&self) -> &'static (dyn game::Hooks + Send + Sync) {
+        self.hooks
+    }
+
+    fn base_game(&self) -> Option<&'static (dyn game::Game + Send + Sync)> {
+        Some(self.base)
+    }
+
+    fn parse_save(&self, data: &[u8]) -> Result<Box<dyn save::Save + Send + Sync>, anyhow::Error> {
+        self.base.parse_save(data)
+    }
+
+    fn save_from_wram(&self, data: &[u8]) -> Result<Box<dyn save::Save + Send + Sync>, anyhow::Error> {
+        self.base.save_from_wram(data)
+    }
+
+    fn load_rom_assets(
+        &self,
+        rom: &[u8],
+        wram: &[u8],
+        overrides: &rom::Overrides,
+    ) -> Result<Box<dyn rom::Assets + Send + Sync>, anyhow::Error> {
+        self.base.load_rom_assets(rom, wram, overrides)
+    }
+}
+
+fn load(
+    raw: &[u8],
+    existing: &[&'static (dyn game::Game + Send + Sync)],
+) -> Result<&'static (dyn game::Game + Send + Sync), anyhow::Error> {
+    let definition = toml::from_slice::<Definition>(raw)?;
+
+    let rom_code: [u8; 4] = definition
+        .rom_code
+        .as_bytes()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("rom_code must be 4 bytes"))?;
+
+    if let Some(game) = existing
+        .iter()
+        .find(|g| g.rom_code_and_revision() == (&rom_code, definition.revision))
+    {
+        anyhow::bail!(
+            "{}_{:02} is already defined by {:?}",
+            definition.rom_code,
+            definition.revision,
+            game
+        );
+    }
+
+    if existing
+        .iter()
+        .any(|g| g.family_and_variant() == (definition.family.as_str(), definition.variant))
+    {
+        anyhow::bail!("{:?} is already defined", (&definition.family, definition.variant));
+    }
+
+    let base = game::BUILTIN_GAMES
+        .iter()
+        .find(|g| g.family_and_variant() == (definition.base.family.as_str(), definition.base.variant))
+        .copied()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown base game: {:?}",
+                (&definition.base.family, definition.base.variant)
+            )
+        })?;
+
+    if definition.match_types.len() != base.match_types().len()
+        || definition
+            .match_types
+            .iter()
+            .zip(base.match_types().iter())
+            .any(|(n, max)| n > max)
+    {
+        anyhow::bail!("match_types must not have more match types than the base game");
+    }
+
+    let hooks: &'static (dyn game::Hooks + Send + Sync) = match definition.hooks.as_str() {
+        "bn1" => game::bn1::hooks_from_offsets(definition.offsets)?,
+        "bn2" => game::bn2::hooks_from_offsets(definition.offsets)?,
+        "bn3" => game::bn3::hooks_from_offsets(definition.offsets)?,
+        "bn4" => game::bn4::hooks_from_offsets(definition.offsets)?,
+        "exe45" => game::exe45::hooks_from_offsets(definition.offsets)?,
+        "bn5" => game::bn5::hooks_from_offsets(definition.offsets)?,
+        "bn6" => game::bn6::hooks_from_offsets(definition.offsets)?,
+        hooks => {
+            anyhow::bail!("unknown hook family: {}", hooks);
+        }
+    };
+
+    Ok(Box::leak(Box::new(DefinedGame {
+        rom_code,
+        revision: definition.revision,
+        crc32: definition.crc32,
+        family: definition.family,
+        variant: definition.variant,
+        language: definition.language.parse()?,
+        match_types: definition.match_types,
+        hooks,
+        base,
+    })))
+}
+
+/// Loads all game definitions (*.toml) in the given directory. Definitions that fail to load are skipped and returned
+/// alongside their errors.
+pub fn load_all(
+    path: &std::path::Path,
+    existing: &[&'static (dyn game::Game + Send + Sync)],
+) -> (
+    Vec<&'static (dyn game::Game + Send + Sync)>,
+    Vec<(std::path::PathBuf, anyhow::Error)>,
+) {
+    let mut games = vec![];
+    let mut errors = vec![];
+
+    let mut paths = match std::fs::read_dir(path) {
+        Ok(read_dir) => read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension() == Some(std::ffi::OsStr::new("toml")))
+            .collect::<Vec<_>>(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => {
+            errors.push((path.to_path_buf(), e.into()));
+            vec![]
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        let result = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|raw| load(&raw, &existing.iter().chain(games.iter()).copied().collect::<Vec<_>>()));
+        match result {
+            Ok(game) => {
+                games.push(game);
+            }
+            Err(e) => {
+                errors.push((path, e));
+            }
+        }
+    }
+
+    (games, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bn6f() -> &'static (dyn game::Game + Send + Sync) {
+        game::BUILTIN_GAMES
+            .iter()
+            .copied()
+            .find(|g| g.family_and_variant() == ("bn6", 1))
+            .unwrap()
+    }
+
+    /// A definition for a hack of BN6 Falzar, which uses the same offsets as the game it's based on.
+    fn definition() -> toml::value::Table {
+        let mut base = toml::value::Table::new();
+        base.insert("family".to_string(), toml::Value::from("bn6"));
+        base.insert("variant".to_string(), toml::Value::Integer(1));
+
+        let mut definition = toml::value::Table::new();
+        definition.insert("rom_code".to_string(), toml::Value::from("BR6X"));
+        definition.insert("revision".to_string(), toml::Value::Integer(0));
+        definition.insert("crc32".to_string(), toml::Value::Integer(0x12345678));
+        definition.insert("family".to_string(), toml::Value::from("bn6-hack"));
+        definition.insert("variant".to_string(), toml::Value::Integer(0));
+        definition.insert("language".to_string(), toml::Value::from("en-US"));
+        definition.insert(
+            "match_types".to_string(),
+            toml::Value::Array(vec![toml::Value::Integer(1), toml::Value::Integer(1)]),
+        );
+        definition.insert("hooks".to_string(), toml::Value::from("bn6"));
+        definition.insert("base".to_string(), toml::Value::Table(base));
+        definition.insert("offsets".to_string(), bn6f().hooks().offsets());
+        definition
+    }
+
+    fn load_table(definition: toml::value::Table) -> Result<&'static (dyn game::Game + Send + Sync), anyhow::Error> {
+        load(
+            toml::to_string(&toml::Value::Table(definition)).unwrap().as_bytes(),
+            game::BUILTIN_GAMES,
+        )
+    }
+
+    #[test]
+    fn test_load() {
+        let game = load_table(definition()).unwrap();
+        assert_eq!(game.rom_code_and_revision(), (b"BR6X", 0));
+        assert_eq!(game.family_and_variant(), ("bn6-hack", 0));
+        assert_eq!(game.strings_family_and_variant(), ("bn6", 1));
+        assert_eq!(game.expected_crc32(), 0x12345678);
+        assert_eq!(game.base_game(), Some(bn6f()));
+        assert!(!game::is_builtin(game));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        for (key, value) in [
+            ("rom_code", toml::Value::from("BR6")),
+            // Already used by BN6 Falzar.
+            ("rom_code", toml::Value::from("BR6E")),
+            ("family", toml::Value::from("bn6")),
+            ("language", toml::Value::from("not a language!")),
+            (
+                "match_types",
+                toml::Value::Array(vec![toml::Value::Integer(3), toml::Value::Integer(1)]),
+            ),
+            ("match_types", toml::Value::Array(vec![toml::Value::Integer(1)])),
+            ("hooks", toml::Value::from("bn7")),
+            ("offsets", toml::Value::Table(toml::value::Table::new())),
+        ] {
+            let mut definition = definition();
+            definition.insert(key.to_string(), value.clone());
+            assert!(load_table(definition).is_err(), "{} = {}", key, value);
+        }
+
+        let mut definition = definition();
+        definition["base"]
+            .as_table_mut()
+            .unwrap()
+            .insert("variant".to_string(), toml::Value::Integer(7));
+        assert!(load_table(definition).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    U8,
+    U16,
+    U32,
+    Bytes(u32),
+}
+
+impl FieldKind {
+    fn size(&self) -> usize {
+        match self {
+            FieldKind::U8 => 1,
+            FieldKind::U16 => 2,
+            FieldKind::U32 => 4,
+            FieldKind::Bytes(len) => *len as usize,
+        }
+    }
+
+    /// Formats a little-endian value as hex, so the same field read from two cores can be compared as text.
+    fn format(&self, buf: &[u8]) -> String {
+        match self {
+            FieldKind::U8 => format!("{:02x}", buf[0]),
+            FieldKind::U16 => format!("{:04x}", u16::from_le_bytes(buf.try_into().unwrap())),
+            FieldKind::U32 => format!("{:08x}", u32::from_le_bytes(buf.try_into().unwrap())),
+            FieldKind::Bytes(_) => buf.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    /// Reads the value at an address out of a saved state. Only EWRAM and IWRAM are saved as plain memory, so anything
+    /// elsewhere reads as None.
+    pub fn read_state(&self, state: &mgba::state::State, address: u32) -> Option<String> {
+        Some(self.format(memory_range(state.wram(), state.iwram(), address, self.size())?))
+    }
+}
+
+fn memory_range<'a>(wram: &'a [u8], iwram: &'a [u8], address: u32, len: usize) -> Option<&'a [u8]> {
+    let (memory, offset) = match address >> 24 {
+        0x02 => (wram, address - 0x02000000),
+        0x03 => (iwram, address - 0x03000000),
+        _ => {
+            return None;
+        }
+    };
+    memory.get(offset as usize..offset as usize + len)
+}
+
+/// A named value in memory the debug window decodes from a core.
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub address: u32,
+    pub kind: FieldKind,
+    /// Whether each core holds its own side's value here, e.g. its tx packet or its local RNG. The primary and the
+    /// shadow are expected to differ on these, so they aren't compared.
+    pub per_side: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_kind_format() {
+        assert_eq!(FieldKind::U8.format(&[0x12]), "12");
+        assert_eq!(FieldKind::U16.format(&[0x34, 0x12]), "1234");
+        assert_eq!(FieldKind::U32.format(&[0x78, 0x56, 0x34, 0x12]), "12345678");
+        assert_eq!(FieldKind::Bytes(3).format(&[0x01, 0xab, 0x00]), "01 ab 00");
+    }
+
+    #[test]
+    fn test_memory_range() {
+        let wram = (0..0x10).collect::<Vec<u8>>();
+        let iwram = (0x80..0x90).collect::<Vec<u8>>();
+        assert_eq!(memory_range(&wram, &iwram, 0x02000004, 2), Some(&[0x04, 0x05][..]));
+        assert_eq!(memory_range(&wram, &iwram, 0x0300000e, 2), Some(&[0x8e, 0x8f][..]));
+        // Past the end of the memory.
+        assert_eq!(memory_range(&wram, &iwram, 0x0200000f, 2), None);
+        // ROM and I/O aren't saved as plain memory.
+        assert_eq!(memory_range(&wram, &iwram, 0x08000000, 1), None);
+        assert_eq!(memory_range(&wram, &iwram, 0x04000000, 1), None);
+    }
+}
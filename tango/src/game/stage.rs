@@ -0,0 +1,56 @@
+/// Picks one of a number of stage choices: the selected one if it is valid, otherwise a random one. The RNG is drawn
+/// from either way, so it advances the same regardless of the selection.
+pub fn pick_stage_choice(rng: &mut impl rand::Rng, num_choices: u16, selected: Option<u16>) -> u16 {
+    if num_choices == 0 {
+        return 0;
+    }
+    let random = rng.gen_range(0..num_choices);
+    selected.filter(|i| *i < num_choices).unwrap_or(random)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_stage_choice_selected() {
+        let mut rng = rand_pcg::Mcg128Xsl64::new(1);
+        assert_eq!(pick_stage_choice(&mut rng, 10, Some(3)), 3);
+        assert_eq!(pick_stage_choice(&mut rng, 10, Some(9)), 9);
+    }
+
+    #[test]
+    fn test_pick_stage_choice_random() {
+        let mut rng = rand_pcg::Mcg128Xsl64::new(1);
+        for _ in 0..100 {
+            assert!(pick_stage_choice(&mut rng, 10, None) < 10);
+        }
+    }
+
+    #[test]
+    fn test_pick_stage_choice_out_of_range_is_random() {
+        let mut rng = rand_pcg::Mcg128Xsl64::new(1);
+        let mut expected_rng = rng.clone();
+        assert_eq!(
+            pick_stage_choice(&mut rng, 10, Some(10)),
+            pick_stage_choice(&mut expected_rng, 10, None)
+        );
+    }
+
+    #[test]
+    fn test_pick_stage_choice_no_choices() {
+        let mut rng = rand_pcg::Mcg128Xsl64::new(1);
+        let expected_rng = rng.clone();
+        assert_eq!(pick_stage_choice(&mut rng, 0, Some(3)), 0);
+        assert_eq!(rng, expected_rng);
+    }
+
+    #[test]
+    fn test_pick_stage_choice_advances_rng_regardless_of_selection() {
+        let mut selected_rng = rand_pcg::Mcg128Xsl64::new(1);
+        let mut random_rng = rand_pcg::Mcg128Xsl64::new(1);
+        pick_stage_choice(&mut selected_rng, 10, Some(3));
+        pick_stage_choice(&mut random_rng, 10, None);
+        assert_eq!(selected_rng, random_rng);
+    }
+}
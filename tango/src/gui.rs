@@ -3,6 +3,7 @@ use fluent_templates::Loader;
 use crate::{audio, config, discord, game, i18n, input, patch, rom, save, session, stats, updater};
 use std::str::FromStr;
 
+mod cheats_window;
 mod debug_window;
 mod escape_window;
 mod language_select;
@@ -57,8 +58,9 @@ impl Selection {
     }
 
     pub fn reload_save(&mut self) -> anyhow::Result<()> {
-        let raw = std::fs::read(&self.save.path)?;
+        let (container, raw) = save::read_raw(&self.save.path)?;
         self.save.save = self.game.parse_save(&raw)?;
+        self.save.container = container;
         self.save_view_state = save_view::State::new();
         Ok(())
     }
@@ -0,0 +1,119 @@
+use fluent_templates::Loader;
+
+use crate::{cheats, gui, i18n, session};
+
+pub struct State {
+    custom: String,
+    error: Option<String>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            custom: "".to_string(),
+            error: None,
+        }
+    }
+}
+
+pub fn show(
+    ctx: &egui::Context,
+    language: &unic_langid::LanguageIdentifier,
+    session: &session::Session,
+    state: &mut Option<State>,
+) {
+    let sp = if let session::Mode::SinglePlayer(sp) = session.mode() {
+        sp
+    } else {
+        *state = None;
+        return;
+    };
+
+    let mut open = state.is_some();
+    egui::Window::new(format!("🧪 {}", i18n::LOCALES.lookup(language, "cheats").unwrap()))
+        .id(egui::Id::new("cheats"))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let state = state.as_mut().unwrap();
+
+            ui.horizontal_wrapped(|ui| {
+                let text = i18n::LOCALES
+                    .lookup(
+                        language,
+                        if sp.cheats_used() {
+                            "cheats-save-warning.used"
+                        } else {
+                            "cheats-save-warning"
+                        },
+                    )
+                    .unwrap();
+                gui::warning::show(ui, text.clone());
+                ui.label(text);
+            });
+            ui.separator();
+
+            let mut engine = sp.cheats().lock();
+
+            let mut toggled = None;
+            for (i, (code, enabled)) in engine.codes().enumerate() {
+                let mut checked = *enabled;
+                let name = match &code.name {
+                    cheats::Name::Builtin(id) => i18n::LOCALES
+                        .lookup(language, &format!("cheats-builtin-{}", id))
+                        .unwrap_or_else(|| id.to_string()),
+                    cheats::Name::User(name) => name.clone(),
+                };
+                if ui.checkbox(&mut checked, name).changed() {
+                    toggled = Some((i, checked));
+                }
+            }
+            if let Some((i, checked)) = toggled {
+                engine.set_enabled(i, checked);
+            }
+            ui.label(
+                egui::RichText::new(
+                    i18n::LOCALES
+                        .lookup_with_args(
+                            language,
+                            "cheats-codes-path",
+                            &std::collections::HashMap::from([(
+                                "path",
+                                engine.codes_path().display().to_string().into(),
+                            )]),
+                        )
+                        .unwrap(),
+                )
+                .weak(),
+            );
+
+            ui.separator();
+
+            ui.strong(i18n::LOCALES.lookup(language, "cheats-custom").unwrap());
+            ui.add(
+                egui::TextEdit::multiline(&mut state.custom)
+                    .desired_width(ui.available_width())
+                    .hint_text("02000000 0000")
+                    .font(egui::TextStyle::Monospace),
+            );
+            if ui
+                .button(i18n::LOCALES.lookup(language, "cheats-custom.apply").unwrap())
+                .clicked()
+            {
+                match cheats::parse(&state.custom) {
+                    Ok(writes) => {
+                        engine.set_custom(writes);
+                        state.error = None;
+                    }
+                    Err(e) => {
+                        state.error = Some(e.to_string());
+                    }
+                }
+            }
+            if let Some(error) = state.error.as_ref() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+    if !open {
+        *state = None;
+    }
+}
@@ -1,19 +1,125 @@
 use fluent_templates::Loader;
 
-use crate::{i18n, session};
+use crate::{game, i18n, rom, session};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Tab {
+    Memory,
+    Graphics,
+    BattleState,
+}
+
+struct GraphicsState {
+    preset: Option<usize>,
+    address: String,
+    compressed: bool,
+    num_tiles: usize,
+    cols: usize,
+    bpp: rom::graphics::Bpp,
+    palette_address: String,
+    scale: f32,
+    image: Option<image::RgbaImage>,
+    texture: Option<egui::TextureHandle>,
+    error: Option<String>,
+}
+
+impl GraphicsState {
+    fn new() -> Self {
+        Self {
+            preset: None,
+            address: "08000000".to_string(),
+            compressed: false,
+            num_tiles: 16,
+            cols: 4,
+            bpp: rom::graphics::Bpp::Four,
+            palette_address: "".to_string(),
+            scale: 2.0,
+            image: None,
+            texture: None,
+            error: None,
+        }
+    }
+
+    fn load_preset(&mut self, index: usize, preset: &rom::graphics::Preset) {
+        self.preset = Some(index);
+        self.address = format!("{:08x}", preset.sheet.address);
+        self.compressed = preset.sheet.compressed;
+        self.num_tiles = preset.sheet.num_tiles;
+        self.cols = preset.sheet.cols;
+        self.bpp = preset.sheet.bpp;
+        self.palette_address = preset
+            .sheet
+            .palette_address
+            .map(|address| format!("{:08x}", address))
+            .unwrap_or_default();
+    }
+
+    fn sheet(&self) -> Option<rom::graphics::Sheet> {
+        Some(rom::graphics::Sheet {
+            address: u32::from_str_radix(&self.address, 16).ok()?,
+            compressed: self.compressed,
+            num_tiles: self.num_tiles,
+            cols: self.cols,
+            bpp: self.bpp,
+            palette_address: if self.palette_address.is_empty() {
+                None
+            } else {
+                Some(u32::from_str_radix(&self.palette_address, 16).ok()?)
+            },
+        })
+    }
+}
+
+struct Watch {
+    name: String,
+    address: String,
+    kind: game::field::FieldKind,
+}
+
+impl Watch {
+    fn new() -> Self {
+        Self {
+            name: "".to_string(),
+            address: "02000000".to_string(),
+            kind: game::field::FieldKind::U8,
+        }
+    }
+}
 
 pub struct State {
+    tab: Tab,
     jump_to: String,
+    graphics: GraphicsState,
+    watches: Vec<Watch>,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
+            tab: Tab::Memory,
             jump_to: "".to_string(),
+            graphics: GraphicsState::new(),
+            watches: vec![],
         }
     }
 }
 
+fn hex_input(ui: &mut egui::Ui, value: &mut String) -> egui::Response {
+    let resp = ui.add(
+        egui::TextEdit::singleline(value)
+            .desired_width(8.0 * FONT_WIDTH)
+            .font(egui::TextStyle::Monospace),
+    );
+    *value = value
+        .chars()
+        .filter(|c| "0123456789abcdefABCDEF".chars().any(|c2| c2 == *c))
+        .take(8)
+        .collect();
+    resp
+}
+
+const FONT_WIDTH: f32 = 8.0;
+
 pub fn show(
     ctx: &egui::Context,
     language: &unic_langid::LanguageIdentifier,
@@ -25,101 +131,449 @@ pub fn show(
         .id(egui::Id::new("debug"))
         .open(&mut open)
         .show(ctx, |ui| {
+            let state = state.as_mut().unwrap();
+
             ui.horizontal(|ui| {
-                let _ = ui.selectable_label(true, "Memory");
+                ui.selectable_value(&mut state.tab, Tab::Memory, "Memory");
+                ui.selectable_value(&mut state.tab, Tab::Graphics, "Graphics");
+                ui.selectable_value(&mut state.tab, Tab::BattleState, "Battle state");
             });
 
             ui.separator();
 
-            let state = state.as_mut().unwrap();
+            match state.tab {
+                Tab::Memory => show_memory(ui, session, &mut state.jump_to),
+                Tab::Graphics => show_graphics(ui, session, &mut state.graphics),
+                Tab::BattleState => show_battle_state(ui, session, &mut state.watches),
+            }
+        });
+    if !open {
+        *state = None;
+    }
+}
 
-            let mut jumping = false;
-            ui.horizontal(|ui| {
-                let input_resp = ui.add(
-                    egui::TextEdit::singleline(&mut state.jump_to)
-                        .desired_width(8.0 * FONT_WIDTH)
-                        .hint_text("Jump to")
-                        .font(egui::TextStyle::Monospace),
-                );
-                state.jump_to = state
-                    .jump_to
-                    .chars()
-                    .filter(|c| "0123456789abcdefABCDEF".chars().any(|c2| c2 == *c))
-                    .collect();
-                if input_resp.lost_focus() && ui.ctx().input().key_pressed(egui::Key::Enter) {
-                    jumping = true;
+fn show_memory(ui: &mut egui::Ui, session: &session::Session, jump_to: &mut String) {
+    let mut jumping = false;
+    ui.horizontal(|ui| {
+        let input_resp = ui.add(
+            egui::TextEdit::singleline(jump_to)
+                .desired_width(8.0 * FONT_WIDTH)
+                .hint_text("Jump to")
+                .font(egui::TextStyle::Monospace),
+        );
+        *jump_to = jump_to
+            .chars()
+            .filter(|c| "0123456789abcdefABCDEF".chars().any(|c2| c2 == *c))
+            .collect();
+        if input_resp.lost_focus() && ui.ctx().input().key_pressed(egui::Key::Enter) {
+            jumping = true;
+        }
+
+        if ui.button("Go!").clicked() {
+            jumping = true;
+        }
+    });
+
+    let thread_handle = session.thread_handle();
+    let mut audio_guard = thread_handle.lock_audio();
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    let mut sa = egui::ScrollArea::vertical().auto_shrink([true, false]);
+    if jumping {
+        if let Ok(jump_to) = u32::from_str_radix(jump_to, 16) {
+            sa = sa.vertical_scroll_offset((row_height + ui.spacing().item_spacing.y) * (jump_to / 0x10) as f32);
+        }
+    }
+
+    sa.show_rows(ui, row_height, 0x0fffffff / 0x10, |ui, range| {
+        egui_extras::StripBuilder::new(ui)
+            .sizes(egui_extras::Size::exact(row_height), range.len())
+            .vertical(|mut outer_strip| {
+                for i in range {
+                    outer_strip.cell(|ui| {
+                        let rect = ui.available_rect_before_wrap().expand(ui.spacing().item_spacing.y);
+                        if i % 2 == 0 {
+                            ui.painter().rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
+                        }
+
+                        egui_extras::StripBuilder::new(ui)
+                            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                            .size(egui_extras::Size::exact(8.0 * FONT_WIDTH))
+                            .size(egui_extras::Size::exact(48.0 * FONT_WIDTH))
+                            .size(egui_extras::Size::remainder())
+                            .horizontal(|mut strip| {
+                                let offset = i * 16;
+                                strip.cell(|ui| {
+                                    ui.label(egui::RichText::new(format!("{:08x}", offset)).monospace().weak());
+                                });
+                                let mut buf = [0u8; 0x10];
+                                audio_guard.core_mut().raw_read_range(offset as u32, -1, &mut buf[..]);
+                                strip.cell(|ui| {
+                                    ui.add(
+                                        egui::TextEdit::singleline(
+                                            &mut buf.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" "),
+                                        )
+                                        .desired_width(ui.available_width())
+                                        .frame(false)
+                                        .font(egui::TextStyle::Monospace),
+                                    );
+                                });
+
+                                strip.cell(|ui| {
+                                    ui.monospace(
+                                        buf.map(|b| if b >= 32 && b < 127 { b as char } else { '.' })
+                                            .iter()
+                                            .collect::<String>(),
+                                    );
+                                });
+                            });
+                    });
                 }
+            });
+    });
+}
+
+fn show_graphics(ui: &mut egui::Ui, session: &session::Session, state: &mut GraphicsState) {
+    let presets = session.graphics_presets();
 
-                if ui.button("Go!").clicked() {
-                    jumping = true;
+    let mut rendering = false;
+    ui.horizontal(|ui| {
+        ui.label("Preset");
+        egui::ComboBox::from_id_source("debug-graphics-preset")
+            .width(200.0)
+            .selected_text(
+                state
+                    .preset
+                    .and_then(|i| presets.get(i))
+                    .map(|preset| preset.name.as_str())
+                    .unwrap_or(""),
+            )
+            .show_ui(ui, |ui| {
+                for (i, preset) in presets.iter().enumerate() {
+                    if ui.selectable_label(state.preset == Some(i), &preset.name).clicked() {
+                        state.load_preset(i, preset);
+                        rendering = true;
+                    }
                 }
             });
+    });
+
+    egui::Grid::new("debug-graphics-sheet").num_columns(2).show(ui, |ui| {
+        ui.label("Address");
+        ui.horizontal(|ui| {
+            hex_input(ui, &mut state.address);
+            ui.checkbox(&mut state.compressed, "LZ77");
+        });
+        ui.end_row();
+
+        ui.label("Tiles");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut state.num_tiles).clamp_range(1..=1024));
+            ui.label("×");
+            ui.add(egui::DragValue::new(&mut state.cols).clamp_range(1..=64));
+            ui.label("per row");
+        });
+        ui.end_row();
+
+        ui.label("Bits per pixel");
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut state.bpp, rom::graphics::Bpp::Four, "4");
+            ui.selectable_value(&mut state.bpp, rom::graphics::Bpp::Eight, "8");
+        });
+        ui.end_row();
+
+        ui.label("Palette");
+        hex_input(ui, &mut state.palette_address).on_hover_text("Leave empty for grayscale");
+        ui.end_row();
 
+        ui.label("Scale");
+        ui.add(egui::Slider::new(&mut state.scale, 1.0..=8.0).step_by(1.0));
+        ui.end_row();
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button("Render").clicked() {
+            rendering = true;
+        }
+
+        if ui
+            .add_enabled(state.image.is_some(), egui::Button::new("Export PNG"))
+            .clicked()
+        {
+            if let Some(path) = rfd::FileDialog::new()
+                .set_file_name(&format!("{}.png", state.address))
+                .add_filter("PNG", &["png"])
+                .save_file()
+            {
+                if let Err(e) = state.image.as_ref().unwrap().save(&path) {
+                    log::error!("failed to export graphics: {:?}", e);
+                }
+            }
+        }
+    });
+
+    if rendering {
+        state.error = None;
+        state.texture = None;
+        state.image = None;
+        if let Some(sheet) = state.sheet() {
             let thread_handle = session.thread_handle();
             let mut audio_guard = thread_handle.lock_audio();
-
-            let row_height = ui.text_style_height(&egui::TextStyle::Body);
-            let mut sa = egui::ScrollArea::vertical().auto_shrink([true, false]);
-            if jumping {
-                if let Ok(jump_to) = u32::from_str_radix(&state.jump_to, 16) {
-                    sa =
-                        sa.vertical_scroll_offset((row_height + ui.spacing().item_spacing.y) * (jump_to / 0x10) as f32);
+            let mut core = audio_guard.core_mut();
+            match rom::graphics::render(&sheet, &mut |address, len| {
+                let mut buf = vec![0u8; len];
+                core.raw_read_range(address, -1, &mut buf[..]);
+                buf
+            }) {
+                Ok(image) => {
+                    state.texture = Some(ui.ctx().load_texture(
+                        "debug graphics",
+                        egui::ColorImage::from_rgba_unmultiplied(
+                            [image.width() as usize, image.height() as usize],
+                            &image,
+                        ),
+                        egui::TextureFilter::Nearest,
+                    ));
+                    state.image = Some(image);
+                }
+                Err(e) => {
+                    state.error = Some(e.to_string());
                 }
             }
+        } else {
+            state.error = Some("invalid address".to_string());
+        }
+    }
 
-            const FONT_WIDTH: f32 = 8.0;
-            sa.show_rows(ui, row_height, 0x0fffffff / 0x10, |ui, range| {
-                egui_extras::StripBuilder::new(ui)
-                    .sizes(egui_extras::Size::exact(row_height), range.len())
-                    .vertical(|mut outer_strip| {
-                        for i in range {
-                            outer_strip.cell(|ui| {
-                                let rect = ui.available_rect_before_wrap().expand(ui.spacing().item_spacing.y);
-                                if i % 2 == 0 {
-                                    ui.painter().rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
-                                }
-
-                                egui_extras::StripBuilder::new(ui)
-                                    .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                                    .size(egui_extras::Size::exact(8.0 * FONT_WIDTH))
-                                    .size(egui_extras::Size::exact(48.0 * FONT_WIDTH))
-                                    .size(egui_extras::Size::remainder())
-                                    .horizontal(|mut strip| {
-                                        let offset = i * 16;
-                                        strip.cell(|ui| {
-                                            ui.label(egui::RichText::new(format!("{:08x}", offset)).monospace().weak());
-                                        });
-                                        let mut buf = [0u8; 0x10];
-                                        audio_guard.core_mut().raw_read_range(offset as u32, -1, &mut buf[..]);
-                                        strip.cell(|ui| {
-                                            ui.add(
-                                                egui::TextEdit::singleline(
-                                                    &mut buf
-                                                        .iter()
-                                                        .map(|b| format!("{:02x}", b))
-                                                        .collect::<Vec<_>>()
-                                                        .join(" "),
-                                                )
-                                                .desired_width(ui.available_width())
-                                                .frame(false)
-                                                .font(egui::TextStyle::Monospace),
-                                            );
-                                        });
-
-                                        strip.cell(|ui| {
-                                            ui.monospace(
-                                                buf.map(|b| if b >= 32 && b < 127 { b as char } else { '.' })
-                                                    .iter()
-                                                    .collect::<String>(),
-                                            );
-                                        });
-                                    });
-                            });
-                        }
+    ui.separator();
+
+    if let Some(error) = state.error.as_ref() {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+
+    if let Some(texture) = state.texture.as_ref() {
+        egui::ScrollArea::both().auto_shrink([true, true]).show(ui, |ui| {
+            ui.image(texture.id(), texture.size_vec2() * state.scale);
+        });
+    }
+}
+
+struct CoreValues {
+    fields: Vec<(&'static str, bool, Option<String>)>,
+    watches: Vec<Option<String>>,
+}
+
+fn read_state_values(
+    state: &mgba::state::State,
+    fields: &[game::field::Field],
+    watches: &[Option<(u32, game::field::FieldKind)>],
+) -> CoreValues {
+    CoreValues {
+        fields: fields
+            .iter()
+            .map(|field| (field.name, field.per_side, field.kind.read_state(state, field.address)))
+            .collect(),
+        watches: watches
+            .iter()
+            .map(|watch| watch.and_then(|(address, kind)| kind.read_state(state, address)))
+            .collect(),
+    }
+}
+
+/// Both cores' values at the primary's latest committed tick, or just the primary's live values if there is no shadow.
+struct Snapshot {
+    tick: Option<u32>,
+    primary: CoreValues,
+    shadow: Option<CoreValues>,
+    last_shadow_tick: Option<u32>,
+}
+
+/// Whether a value is highlighted as differing between the primary and the shadow. Fields that hold each core's own
+/// side of the link differ by design, so they never are.
+fn is_mismatched(per_side: bool, value: Option<&String>, shadow_value: Option<&String>) -> bool {
+    !per_side && value.is_some() && shadow_value.is_some() && value != shadow_value
+}
+
+fn value_label(ui: &mut egui::Ui, value: Option<&String>, mismatched: bool) {
+    let text = if let Some(value) = value {
+        // Long byte strings are broken into rows of 16 bytes, like the memory view.
+        value
+            .split(' ')
+            .collect::<Vec<_>>()
+            .chunks(16)
+            .map(|chunk| chunk.join(" "))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        "—".to_string()
+    };
+    let text = egui::RichText::new(text).monospace();
+    if mismatched {
+        ui.label(text.color(egui::Color32::RED));
+    } else {
+        ui.label(text);
+    }
+}
+
+fn show_battle_state(ui: &mut egui::Ui, session: &session::Session, watches: &mut Vec<Watch>) {
+    let watch_addresses = watches
+        .iter()
+        .map(|watch| {
+            u32::from_str_radix(&watch.address, 16)
+                .ok()
+                .map(|address| (address, watch.kind))
+        })
+        .collect::<Vec<_>>();
+
+    // Live cores are never on the same tick: the primary runs ahead on predicted input while the shadow only gets
+    // committed input. Compare the primary's committed state against the shadow's state at that same tick instead.
+    let primary_fields = session.game_info().game.hooks().battle_state_fields();
+    let snapshot = session.with_committed_states(|committed_state, shadow| {
+        let committed_state = committed_state?;
+        Some(Snapshot {
+            tick: Some(committed_state.tick),
+            primary: read_state_values(&committed_state.state, &primary_fields, &watch_addresses),
+            shadow: shadow
+                .applied_state_at(committed_state.tick)
+                .map(|state| read_state_values(state, &shadow.hooks().battle_state_fields(), &watch_addresses)),
+            last_shadow_tick: shadow.last_applied_tick(),
+        })
+    });
+
+    let has_shadow = snapshot.is_some();
+    let snapshot = snapshot.unwrap_or_else(|| {
+        let thread_handle = session.thread_handle();
+        let mut audio_guard = thread_handle.lock_audio();
+        let state = audio_guard.core_mut().save_state().ok()?;
+        Some(Snapshot {
+            tick: None,
+            primary: read_state_values(&state, &primary_fields, &watch_addresses),
+            shadow: None,
+            last_shadow_tick: None,
+        })
+    });
+    let snapshot = if let Some(snapshot) = snapshot {
+        snapshot
+    } else {
+        ui.label(egui::RichText::new("No state has been committed yet.").weak());
+        return;
+    };
+
+    if !has_shadow {
+        ui.label(egui::RichText::new("This session has no shadow core.").weak());
+    } else if let Some(tick) = snapshot.tick {
+        ui.label(format!("Committed tick: {}", tick));
+        if snapshot.shadow.is_none() {
+            ui.label(
+                egui::RichText::new(format!(
+                    "The shadow has no state for this tick (last applied: {}), so nothing is compared.",
+                    snapshot
+                        .last_shadow_tick
+                        .map(|tick| tick.to_string())
+                        .unwrap_or_else(|| "none".to_string())
+                ))
+                .weak(),
+            );
+        }
+    }
+    let primary = snapshot.primary;
+    let shadow = snapshot.shadow;
+
+    egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+        egui::Grid::new("debug-battle-state")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Field");
+                ui.strong("Primary");
+                ui.strong("Shadow");
+                ui.end_row();
+
+                for (name, per_side, value) in primary.fields.iter() {
+                    let shadow_value = shadow.as_ref().and_then(|shadow| {
+                        shadow
+                            .fields
+                            .iter()
+                            .find(|(shadow_name, _, _)| shadow_name == name)
+                            .and_then(|(_, _, value)| value.as_ref())
                     });
+                    let mismatched = is_mismatched(*per_side, value.as_ref(), shadow_value);
+                    if *per_side {
+                        ui.label(*name)
+                            .on_hover_text("Each core has its own side's value here, so it isn't compared.");
+                    } else {
+                        ui.label(*name);
+                    }
+                    value_label(ui, value.as_ref(), mismatched);
+                    value_label(ui, shadow_value, mismatched);
+                    ui.end_row();
+                }
             });
-        });
-    if !open {
-        *state = None;
+
+        ui.separator();
+
+        ui.strong("Watches");
+        let mut removed = None;
+        egui::Grid::new("debug-battle-state-watches")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, watch) in watches.iter_mut().enumerate() {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut watch.name)
+                            .desired_width(16.0 * FONT_WIDTH)
+                            .hint_text("Name"),
+                    );
+                    hex_input(ui, &mut watch.address);
+                    egui::ComboBox::from_id_source(("debug-battle-state-watch-kind", i))
+                        .width(6.0 * FONT_WIDTH)
+                        .selected_text(match watch.kind {
+                            game::field::FieldKind::U8 => "u8",
+                            game::field::FieldKind::U16 => "u16",
+                            _ => "u32",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut watch.kind, game::field::FieldKind::U8, "u8");
+                            ui.selectable_value(&mut watch.kind, game::field::FieldKind::U16, "u16");
+                            ui.selectable_value(&mut watch.kind, game::field::FieldKind::U32, "u32");
+                        });
+
+                    let value = primary.watches[i].as_ref();
+                    let shadow_value = shadow.as_ref().and_then(|shadow| shadow.watches[i].as_ref());
+                    let mismatched = is_mismatched(false, value, shadow_value);
+                    value_label(ui, value, mismatched);
+                    value_label(ui, shadow_value, mismatched);
+
+                    if ui.button("🗑").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = removed {
+            watches.remove(i);
+        }
+
+        if ui.button("Add watch").clicked() {
+            watches.push(Watch::new());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_mismatched() {
+        let a = "01".to_string();
+        let b = "02".to_string();
+        assert!(!is_mismatched(false, Some(&a), Some(&a)));
+        assert!(is_mismatched(false, Some(&a), Some(&b)));
+        // Per-side fields, like the tx packet, are expected to differ.
+        assert!(!is_mismatched(true, Some(&a), Some(&b)));
+        // Nothing to compare against.
+        assert!(!is_mismatched(false, Some(&a), None));
+        assert!(!is_mismatched(false, None, Some(&b)));
     }
 }
@@ -1,6 +1,6 @@
 use fluent_templates::Loader;
 
-use crate::{audio, config, discord, gui, i18n, patch, rom, save, session, stats, sync, updater};
+use crate::{audio, config, discord, game, gui, i18n, patch, rom, save, session, stats, sync, updater};
 
 pub struct State {
     tab: Tab,
@@ -66,6 +66,27 @@ pub fn show(
                             None
                         };
                     }
+                    let definition_errors = game::definition_errors();
+                    if !definition_errors.is_empty() {
+                        gui::warning::show(
+                            ui,
+                            i18n::LOCALES
+                                .lookup_with_args(
+                                    &config.language,
+                                    "game-definitions-failed",
+                                    &std::collections::HashMap::from([(
+                                        "errors",
+                                        definition_errors
+                                            .iter()
+                                            .map(|(path, e)| format!("{}: {}", path.display(), e))
+                                            .collect::<Vec<_>>()
+                                            .join("\n")
+                                            .into(),
+                                    )]),
+                                )
+                                .unwrap(),
+                        );
+                    }
                     let updater_status = sync::block_on(updater.status());
                     match updater_status {
                         updater::Status::UpToDate { .. } => {}
@@ -198,11 +219,8 @@ pub fn show(
                     ui,
                     &mut state.patches_pane,
                     &config.language,
-                    if !config.patch_repo.is_empty() {
-                        config.patch_repo.as_str()
-                    } else {
-                        config::DEFAULT_PATCH_REPO
-                    },
+                    config.patch_repo_url(),
+                    &patch::IndexPolicy::from_config(&config, config.patch_repo_url()),
                     &mut state.patch_selection,
                     &config.patches_path(),
                     patches_scanner.clone(),
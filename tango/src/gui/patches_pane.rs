@@ -15,6 +15,7 @@ pub fn show(
     _state: &mut State,
     language: &unic_langid::LanguageIdentifier,
     repo_url: &str,
+    index_policy: &patch::IndexPolicy,
     patch_selection: &mut Option<String>,
     patches_path: &std::path::Path,
     patches_scanner: patch::Scanner,
@@ -33,10 +34,11 @@ pub fn show(
                     tokio::task::spawn_blocking({
                         let patches_scanner = patches_scanner.clone();
                         let repo_url = repo_url.to_owned();
+                        let index_policy = index_policy.clone();
                         let patches_path = patches_path.to_path_buf();
                         move || {
                             patches_scanner.rescan(move || {
-                                if let Err(e) = sync::block_on(patch::update(&repo_url, &patches_path)) {
+                                if let Err(e) = sync::block_on(patch::update(&repo_url, &patches_path, &index_policy)) {
                                     log::error!("failed to update patches: {:?}", e);
                                 }
                                 patch::scan(&patches_path).ok()
@@ -173,7 +175,7 @@ pub fn show(
                                     let mut games = version_info.supported_games.iter().cloned().collect::<Vec<_>>();
                                     game::sort_games(language, &mut games);
                                     for game in games.iter() {
-                                        let (family, variant) = game.family_and_variant();
+                                        let (family, variant) = game.strings_family_and_variant();
                                         ui.label(
                                             i18n::LOCALES
                                                 .lookup(language, &format!("game-{}.variant-{}", family, variant))
@@ -39,8 +39,8 @@ impl Warning {
                                 language,
                                 &format!(
                                     "game-{}.variant-{}",
-                                    game.family_and_variant().0,
-                                    game.family_and_variant().1
+                                    game.strings_family_and_variant().0,
+                                    game.strings_family_and_variant().1
                                 ),
                             )
                             .unwrap()
@@ -69,8 +69,8 @@ impl Warning {
                                 language,
                                 &format!(
                                     "game-{}.variant-{}",
-                                    game.family_and_variant().0,
-                                    game.family_and_variant().1
+                                    game.strings_family_and_variant().0,
+                                    game.strings_family_and_variant().1
                                 ),
                             )
                             .unwrap()
@@ -138,21 +138,26 @@ fn make_warning(
     }
 
     if let Some(pi) = remote_gi.patch.as_ref() {
-        if !patches.iter().any(|(patch_name, patch_metadata)| {
-            *patch_name == pi.name && patch_metadata.versions.keys().any(|v| v == &pi.version)
-        }) {
-            return Some(Warning::NoLocalPatch(pi.name.clone(), pi.version.clone()));
+        for (patch_name, patch_version) in pi.chain() {
+            if !patches.iter().any(|(name, patch_metadata)| {
+                *name == patch_name && patch_metadata.versions.keys().any(|v| v == &patch_version)
+            }) {
+                return Some(Warning::NoLocalPatch(patch_name, patch_version));
+            }
         }
     }
 
     if let Some((patch_name, patch_version, _)) = local_selection.patch.as_ref() {
-        if !lobby
-            .remote_settings
-            .available_patches
-            .iter()
-            .any(|(name, versions)| patch_name == name && versions.iter().any(|v| v == patch_version))
+        for (patch_name, patch_version) in patch::resolve_chain(patches, patch_name, patch_version).unwrap_or_default()
         {
-            return Some(Warning::NoRemotePatch(patch_name.clone(), patch_version.clone()));
+            if !lobby
+                .remote_settings
+                .available_patches
+                .iter()
+                .any(|(name, versions)| &patch_name == name && versions.iter().any(|v| v == &patch_version))
+            {
+                return Some(Warning::NoRemotePatch(patch_name, patch_version));
+            }
         }
     }
 
@@ -165,11 +170,8 @@ fn make_warning(
         &patches,
     );
 
-    let remote_netplay_compatibility = get_netplay_compatibility(
-        remote_game,
-        remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
-        &patches,
-    );
+    let remote_netplay_compatibility =
+        get_netplay_compatibility_from_patch_info(remote_game, remote_gi.patch.as_ref(), &patches);
 
     if local_netplay_compatibility != remote_netplay_compatibility {
         return Some(Warning::Incompatible);
@@ -198,6 +200,8 @@ struct Lobby {
     remote_selection: Option<RemoteSelection>,
     nickname: String,
     match_type: (u8, u8),
+    stage: net::protocol::StageSelection,
+    best_of: u8,
     reveal_setup: bool,
     remote_settings: net::protocol::Settings,
     remote_commitment: Option<[u8; 16]>,
@@ -207,17 +211,45 @@ struct Lobby {
     patches_scanner: patch::Scanner,
 }
 
+/// Joins the netplay compatibility of every patch in a chain, so stacked patches are only compatible if every layer
+/// is.
+fn get_chain_netplay_compatibility(
+    chain: &[(String, semver::Version)],
+    patches: &std::collections::BTreeMap<String, patch::Patch>,
+) -> Option<String> {
+    chain
+        .iter()
+        .map(|(name, version)| {
+            patches
+                .get(name)
+                .and_then(|p| p.versions.get(version))
+                .map(|vinfo| vinfo.netplay_compatibility.clone())
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|ncs| ncs.join("+"))
+}
+
 pub fn get_netplay_compatibility(
     game: &'static (dyn game::Game + Send + Sync),
     patch: Option<(&str, &semver::Version)>,
     patches: &std::collections::BTreeMap<String, patch::Patch>,
 ) -> Option<String> {
     if let Some(patch) = patch.as_ref() {
-        patches.get(patch.0).and_then(|p| {
-            p.versions
-                .get(&patch.1)
-                .map(|vinfo| vinfo.netplay_compatibility.clone())
-        })
+        get_chain_netplay_compatibility(&patch::resolve_chain(patches, patch.0, patch.1)?, patches)
+    } else {
+        Some(game.family_and_variant().0.to_string())
+    }
+}
+
+/// Like `get_netplay_compatibility`, but uses the chain of base patches the other side sent instead of resolving it
+/// locally.
+pub fn get_netplay_compatibility_from_patch_info(
+    game: &'static (dyn game::Game + Send + Sync),
+    pi: Option<&net::protocol::PatchInfo>,
+    patches: &std::collections::BTreeMap<String, patch::Patch>,
+) -> Option<String> {
+    if let Some(pi) = pi {
+        get_chain_netplay_compatibility(&pi.chain(), patches)
     } else {
         Some(game.family_and_variant().0.to_string())
     }
@@ -227,13 +259,22 @@ pub fn get_netplay_compatibility_from_game_info(
     g: &net::protocol::GameInfo,
     patches: &std::collections::BTreeMap<String, patch::Patch>,
 ) -> Option<String> {
-    game::find_by_family_and_variant(g.family_and_variant.0.as_str(), g.family_and_variant.1).and_then(|game| {
-        get_netplay_compatibility(
-            game,
-            g.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
-            patches,
-        )
-    })
+    game::find_by_family_and_variant(g.family_and_variant.0.as_str(), g.family_and_variant.1)
+        .and_then(|game| get_netplay_compatibility_from_patch_info(game, g.patch.as_ref(), patches))
+}
+
+fn make_patch_info(
+    name: &str,
+    version: &semver::Version,
+    patches: &std::collections::BTreeMap<String, patch::Patch>,
+) -> net::protocol::PatchInfo {
+    let mut bases = patch::resolve_chain(patches, name, version).unwrap_or_default();
+    bases.pop();
+    net::protocol::PatchInfo {
+        name: name.to_string(),
+        version: version.clone(),
+        bases,
+    }
 }
 
 fn are_settings_compatible(
@@ -270,21 +311,23 @@ fn are_settings_compatible(
     }
 
     if let Some(patch) = local_game_info.patch.as_ref() {
-        if !remote_settings
-            .available_patches
-            .iter()
-            .any(|(pn, pvs)| pn == &patch.name && pvs.contains(&patch.version))
-        {
+        if !patch.chain().iter().all(|(name, version)| {
+            remote_settings
+                .available_patches
+                .iter()
+                .any(|(pn, pvs)| pn == name && pvs.contains(version))
+        }) {
             return false;
         }
     }
 
     if let Some(patch) = remote_game_info.patch.as_ref() {
-        if !local_settings
-            .available_patches
-            .iter()
-            .any(|(pn, pvs)| pn == &patch.name && pvs.contains(&patch.version))
-        {
+        if !patch.chain().iter().all(|(name, version)| {
+            local_settings
+                .available_patches
+                .iter()
+                .any(|(pn, pvs)| pn == name && pvs.contains(version))
+        }) {
             return false;
         }
     }
@@ -293,6 +336,8 @@ fn are_settings_compatible(
     struct SimplifiedSettings {
         netplay_compatibility: Option<String>,
         match_type: (u8, u8),
+        stage: net::protocol::StageSelection,
+        best_of: u8,
     }
 
     impl SimplifiedSettings {
@@ -303,6 +348,8 @@ fn are_settings_compatible(
                     .as_ref()
                     .and_then(|gi| get_netplay_compatibility_from_game_info(gi, patches)),
                 match_type: settings.match_type,
+                stage: settings.stage,
+                best_of: std::cmp::max(settings.best_of, 1),
             }
         }
     }
@@ -315,6 +362,34 @@ fn are_settings_compatible(
         && local_simplified_settings == remote_simplified_settings
 }
 
+/// Set lengths that can be picked in the lobby.
+const BEST_OF_CHOICES: &[u8] = &[1, 3, 5, 7, 9];
+
+fn format_best_of(language: &unic_langid::LanguageIdentifier, best_of: u8) -> String {
+    if best_of <= 1 {
+        i18n::LOCALES.lookup(language, "play-details-best-of.single").unwrap()
+    } else {
+        i18n::LOCALES
+            .lookup_with_args(
+                language,
+                "play-details-best-of.choice",
+                &std::collections::HashMap::from([("n", best_of.to_string().into())]),
+            )
+            .unwrap()
+    }
+}
+
+fn format_stage_choice(language: &unic_langid::LanguageIdentifier, labels: &[String], choice: Option<u16>) -> String {
+    if let Some(choice) = choice {
+        labels
+            .get(choice as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", choice + 1))
+    } else {
+        i18n::LOCALES.lookup(language, "play-details-stage.random").unwrap()
+    }
+}
+
 fn make_commitment(buf: &[u8]) -> [u8; 16] {
     let mut shake128 = sha3::Shake128::default();
     shake128.update(b"tango:lobby:");
@@ -369,6 +444,8 @@ impl Lobby {
         net::protocol::Settings {
             nickname: self.nickname.clone(),
             match_type: self.match_type,
+            stage: self.stage,
+            best_of: self.best_of,
             game_info: self.local_selection.as_ref().map(|local_selection| {
                 let (family, variant) = local_selection.game.family_and_variant();
                 net::protocol::GameInfo {
@@ -376,10 +453,7 @@ impl Lobby {
                     patch: local_selection
                         .patch
                         .as_ref()
-                        .map(|(name, version, _)| net::protocol::PatchInfo {
-                            name: name.clone(),
-                            version: version.clone(),
-                        }),
+                        .map(|(name, version, _)| make_patch_info(name, version, &patches)),
                 }
             }),
             available_games: roms
@@ -423,16 +497,44 @@ impl Lobby {
         Ok(())
     }
 
+    async fn set_best_of(&mut self, best_of: u8) -> Result<(), anyhow::Error> {
+        if best_of == self.best_of {
+            return Ok(());
+        }
+        self.send_settings(net::protocol::Settings {
+            best_of,
+            ..self.make_local_settings()
+        })
+        .await?;
+        self.best_of = best_of;
+        Ok(())
+    }
+
     async fn set_match_type(&mut self, match_type: (u8, u8)) -> Result<(), anyhow::Error> {
         if match_type == self.match_type {
             return Ok(());
         }
         self.send_settings(net::protocol::Settings {
             match_type,
+            stage: Default::default(),
             ..self.make_local_settings()
         })
         .await?;
         self.match_type = match_type;
+        self.stage = Default::default();
+        Ok(())
+    }
+
+    async fn set_stage(&mut self, stage: net::protocol::StageSelection) -> Result<(), anyhow::Error> {
+        if stage == self.stage {
+            return Ok(());
+        }
+        self.send_settings(net::protocol::Settings {
+            stage,
+            ..self.make_local_settings()
+        })
+        .await?;
+        self.stage = stage;
         Ok(())
     }
 
@@ -472,21 +574,23 @@ impl Lobby {
             0,
         );
 
+        let patch_info = selection.as_ref().and_then(|selection| {
+            selection
+                .patch
+                .as_ref()
+                .map(|(name, version, _)| make_patch_info(name, version, &self.patches_scanner.read()))
+        });
+
         self.send_settings(net::protocol::Settings {
             game_info: selection.as_ref().map(|selection| {
                 let (family, variant) = selection.game.family_and_variant();
                 net::protocol::GameInfo {
                     family_and_variant: (family.to_string(), variant),
-                    patch: selection
-                        .patch
-                        .as_ref()
-                        .map(|(name, version, _)| net::protocol::PatchInfo {
-                            name: name.clone(),
-                            version: version.clone(),
-                        }),
+                    patch: patch_info,
                 }
             }),
             match_type,
+            stage: Default::default(),
             ..self.make_local_settings()
         })
         .await?;
@@ -501,6 +605,7 @@ impl Lobby {
             None
         };
         self.match_type = match_type;
+        self.stage = Default::default();
         if !self.can_ready() {
             self.remote_commitment = None;
         }
@@ -538,7 +643,7 @@ impl Lobby {
                             return None;
                         };
 
-                        let rom = match patch::apply_patch_from_disk(&rom, game, patches_path, &pi.name, &pi.version) {
+                        let rom = match patch::apply_patch_chain_from_disk(&rom, game, patches_path, &pi.chain()) {
                             Ok(r) => r,
                             Err(e) => {
                                 log::error!("failed to apply patch {}: {:?}: {:?}", pi.name, (rom_code, revision), e);
@@ -661,6 +766,8 @@ async fn run_connection_task(
                         nickname,
                         link_code,
                         match_type: (default_match_type, 0),
+                        stage: Default::default(),
+                        best_of: 1,
                         reveal_setup: false,
                         remote_settings: net::protocol::Settings::default(),
                         remote_commitment: None,
@@ -819,15 +926,18 @@ async fn run_connection_task(
                     }
 
                     log::info!("starting session");
+                    let netplay_compatibility = get_netplay_compatibility(
+                        local_selection.game,
+                        local_selection.patch.as_ref().map(|(name, version, _)| (name.as_str(), version)),
+                        &patches_scanner.read(),
+                    ).ok_or_else(|| anyhow::anyhow!("could not resolve base patches"))?;
                     let is_offerer = peer_conn.local_description().unwrap().sdp_type == datachannel_wrapper::SdpType::Offer;
                     {
                         *session.lock() = Some(session::Session::new_pvp(
                             config.clone(),
                             audio_binder,
                             link_code,
-                            local_selection.patch.as_ref()
-                                .map(|(_, _, metadata)| metadata.netplay_compatibility.clone())
-                                .unwrap_or(local_selection.game.family_and_variant().0.to_owned()),
+                            netplay_compatibility,
                             local_settings,
                             local_selection.game,
                             local_selection.patch.as_ref().map(|(name, version, _)| {
@@ -955,6 +1065,8 @@ fn show_lobby_table(
         .size(egui_extras::Size::exact(row_height + spacing_y))
         .size(egui_extras::Size::exact(row_height + spacing_y))
         .size(egui_extras::Size::exact(row_height + spacing_y))
+        .size(egui_extras::Size::exact(row_height + spacing_y))
+        .size(egui_extras::Size::exact(row_height + spacing_y))
         .vertical(|mut outer_strip| {
             const CELL_WIDTH: f32 = 200.0;
             outer_strip.strip(|sb| {
@@ -1023,9 +1135,12 @@ fn show_lobby_table(
                             ui.vertical(|ui| {
                                 if let Some(local_selection) = lobby.local_selection.as_ref() {
                                     let (family, variant) = local_selection.game.family_and_variant();
-                                    ui.label(if game::find_by_family_and_variant(family, variant).is_some() {
+                                    ui.label(if let Some(game) = game::find_by_family_and_variant(family, variant) {
                                         i18n::LOCALES
-                                            .lookup(&config.language, &format!("game-{}", family))
+                                            .lookup(
+                                                &config.language,
+                                                &format!("game-{}", game.strings_family_and_variant().0),
+                                            )
                                             .unwrap()
                                     } else {
                                         i18n::LOCALES
@@ -1045,7 +1160,7 @@ fn show_lobby_table(
                                 if let Some(game_info) = lobby.remote_settings.game_info.as_ref() {
                                     let (family, variant) = &game_info.family_and_variant;
                                     if let Some(game) = game::find_by_family_and_variant(&family, *variant) {
-                                        let (family, _) = game.family_and_variant();
+                                        let (family, _) = game.strings_family_and_variant();
                                         ui.label(
                                             i18n::LOCALES
                                                 .lookup(&config.language, &format!("game-{}", family))
@@ -1104,7 +1219,7 @@ fn show_lobby_table(
                                                 &config.language,
                                                 &format!(
                                                     "game-{}.match-type-{}-{}",
-                                                    game.family_and_variant().0,
+                                                    game.strings_family_and_variant().0,
                                                     lobby.match_type.0,
                                                     lobby.match_type.1
                                                 ),
@@ -1126,7 +1241,7 @@ fn show_lobby_table(
                                                                 &config.language,
                                                                 &format!(
                                                                     "game-{}.match-type-{}-{}",
-                                                                    game.family_and_variant().0,
+                                                                    game.strings_family_and_variant().0,
                                                                     typ,
                                                                     subtype
                                                                 ),
@@ -1144,22 +1259,168 @@ fn show_lobby_table(
                             });
                         });
                         strip.cell(|ui| {
-                            ui.label(if let Some(game_info) = lobby.remote_settings.game_info.as_ref() {
-                                i18n::LOCALES
-                                    .lookup(
+                            ui.label(
+                                if let Some(game) = lobby.remote_settings.game_info.as_ref().and_then(|gi| {
+                                    game::find_by_family_and_variant(&gi.family_and_variant.0, gi.family_and_variant.1)
+                                }) {
+                                    i18n::LOCALES
+                                        .lookup(
+                                            &config.language,
+                                            &format!(
+                                                "game-{}.match-type-{}-{}",
+                                                game.strings_family_and_variant().0,
+                                                lobby.remote_settings.match_type.0,
+                                                lobby.remote_settings.match_type.1,
+                                            ),
+                                        )
+                                        .unwrap()
+                                } else {
+                                    "".to_string()
+                                },
+                            );
+                        });
+                    });
+            });
+
+            let (battle_settings_labels, background_labels) = lobby
+                .local_selection
+                .as_ref()
+                .map(|local_selection| local_selection.game.hooks().stage_choice_labels(lobby.match_type))
+                .unwrap_or_default();
+            outer_strip.strip(|sb| {
+                sb.size(egui_extras::Size::remainder())
+                    .size(egui_extras::Size::exact(CELL_WIDTH))
+                    .size(egui_extras::Size::exact(CELL_WIDTH))
+                    .horizontal(|mut strip| {
+                        strip.cell(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.strong(i18n::LOCALES.lookup(&config.language, "play-details-stage").unwrap());
+                                if lobby.local_selection.is_some()
+                                    && lobby.remote_settings.game_info.is_some()
+                                    && lobby.stage != lobby.remote_settings.stage
+                                {
+                                    gui::warning::show(
+                                        ui,
+                                        i18n::LOCALES
+                                            .lookup(&config.language, "lobby-issue-stage-mismatch")
+                                            .unwrap(),
+                                    );
+                                }
+                            });
+                        });
+                        strip.cell(|ui| {
+                            let mut stage = lobby.stage;
+                            ui.horizontal(|ui| {
+                                for (id, key, labels, choice) in [
+                                    (
+                                        "start-stage-battle-settings-combobox",
+                                        "play-details-stage.battle-settings",
+                                        &battle_settings_labels,
+                                        &mut stage.battle_settings,
+                                    ),
+                                    (
+                                        "start-stage-background-combobox",
+                                        "play-details-stage.background",
+                                        &background_labels,
+                                        &mut stage.background,
+                                    ),
+                                ] {
+                                    ui.add_enabled_ui(!labels.is_empty(), |ui| {
+                                        egui::ComboBox::new(id, "")
+                                            .width((CELL_WIDTH - spacing_x) / 2.0 - ui.spacing().icon_width)
+                                            .selected_text(format_stage_choice(&config.language, labels, *choice))
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(
+                                                    choice,
+                                                    None,
+                                                    format_stage_choice(&config.language, labels, None),
+                                                );
+                                                for (i, label) in labels.iter().enumerate() {
+                                                    // Choices labelled the same as an earlier one pick the same stage.
+                                                    if labels[..i].contains(label) {
+                                                        continue;
+                                                    }
+                                                    ui.selectable_value(choice, Some(i as u16), label.as_str());
+                                                }
+                                            })
+                                            .response
+                                            .on_hover_text(i18n::LOCALES.lookup(&config.language, key).unwrap())
+                                            .on_disabled_hover_text(
+                                                i18n::LOCALES
+                                                    .lookup(&config.language, "play-details-stage.unsupported")
+                                                    .unwrap(),
+                                            );
+                                    });
+                                }
+                            });
+                            if stage != lobby.stage {
+                                let _ = sync::block_on(lobby.set_stage(stage));
+                            }
+                        });
+                        strip.cell(|ui| {
+                            if lobby.remote_settings.game_info.is_some() {
+                                ui.label(format!(
+                                    "{} / {}",
+                                    format_stage_choice(
                                         &config.language,
-                                        &format!(
-                                            "game-{}.match-type-{}-{}",
-                                            game_info.family_and_variant.0,
-                                            lobby.remote_settings.match_type.0,
-                                            lobby.remote_settings.match_type.1,
-                                        ),
+                                        &battle_settings_labels,
+                                        lobby.remote_settings.stage.battle_settings
+                                    ),
+                                    format_stage_choice(
+                                        &config.language,
+                                        &background_labels,
+                                        lobby.remote_settings.stage.background
                                     )
-                                    .unwrap()
-                            } else {
-                                "".to_string()
+                                ));
+                            }
+                        });
+                    });
+            });
+
+            outer_strip.strip(|sb| {
+                sb.size(egui_extras::Size::remainder())
+                    .size(egui_extras::Size::exact(CELL_WIDTH))
+                    .size(egui_extras::Size::exact(CELL_WIDTH))
+                    .horizontal(|mut strip| {
+                        strip.cell(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.strong(i18n::LOCALES.lookup(&config.language, "play-details-best-of").unwrap());
+                                if lobby.local_selection.is_some()
+                                    && lobby.remote_settings.game_info.is_some()
+                                    && lobby.best_of != std::cmp::max(lobby.remote_settings.best_of, 1)
+                                {
+                                    gui::warning::show(
+                                        ui,
+                                        i18n::LOCALES
+                                            .lookup(&config.language, "lobby-issue-best-of-mismatch")
+                                            .unwrap(),
+                                    );
+                                }
                             });
                         });
+                        strip.cell(|ui| {
+                            let mut best_of = lobby.best_of;
+                            egui::ComboBox::new("start-best-of-combobox", "")
+                                .width(150.0)
+                                .selected_text(format_best_of(&config.language, best_of))
+                                .show_ui(ui, |ui| {
+                                    for choice in BEST_OF_CHOICES {
+                                        ui.selectable_value(
+                                            &mut best_of,
+                                            *choice,
+                                            format_best_of(&config.language, *choice),
+                                        );
+                                    }
+                                });
+                            if best_of != lobby.best_of {
+                                let _ = sync::block_on(lobby.set_best_of(best_of));
+                            }
+                        });
+                        strip.cell(|ui| {
+                            if lobby.remote_settings.game_info.is_some() {
+                                ui.label(format_best_of(&config.language, lobby.remote_settings.best_of));
+                            }
+                        });
                     });
             });
 
@@ -1429,11 +1690,21 @@ fn show_bottom_pane(
                         (None, None)
                     };
 
+                    // mGBA can only write back to raw saves, so progress made on a save in another container would be
+                    // silently lost.
+                    let single_player_blocked = link_code.is_empty()
+                        && selection
+                            .as_ref()
+                            .map(|selection| selection.save.container != save::container::Container::Raw)
+                            .unwrap_or(false);
+
                     let mut submitted = false;
                     if cancellation_token.is_none() {
                         if ui
                             .add_enabled(
-                                !error_window_open && (!link_code.is_empty() || selection.is_some()),
+                                !error_window_open
+                                    && !single_player_blocked
+                                    && (!link_code.is_empty() || selection.is_some()),
                                 egui::Button::new(egui::RichText::new(if link_code.is_empty() {
                                     format!("▶️ {}", i18n::LOCALES.lookup(&config.language, "play-play").unwrap())
                                 } else {
@@ -1445,6 +1716,15 @@ fn show_bottom_pane(
                             submitted = true;
                         }
 
+                        if single_player_blocked {
+                            gui::warning::show(
+                                ui,
+                                i18n::LOCALES
+                                    .lookup(&config.language, "play-save-container-unsupported")
+                                    .unwrap(),
+                            );
+                        }
+
                         if ui
                             .add_enabled(!error_window_open, egui::Button::new(egui::RichText::new("🎲")))
                             .on_hover_text(i18n::LOCALES.lookup(&config.language, "play-random").unwrap())
@@ -1585,8 +1865,12 @@ fn show_bottom_pane(
                                     egui_ctx.request_repaint();
                                 }
                             });
-                        } else if let Some(selection) = selection.as_ref() {
+                        } else if let Some(selection) = selection.as_ref().filter(|_| !single_player_blocked) {
                             let save_path = selection.save.path.clone();
+                            let backups_dir =
+                                save::backup::snapshots_dir(&config.backups_path(), &config.saves_path(), &save_path);
+                            let backup_retention = config.save_backup_retention.clone();
+                            let cheats_path = config.cheats_path();
                             let game = selection.game;
                             let rom = selection.rom.clone();
                             let patch = selection
@@ -1603,6 +1887,9 @@ fn show_bottom_pane(
                                         patch,
                                         &rom,
                                         &save_path,
+                                        &backups_dir,
+                                        backup_retention,
+                                        &cheats_path,
                                         emu_tps_counter,
                                     )
                                     .unwrap(),
@@ -1715,7 +2002,7 @@ pub fn show(
                                         let mut layouter = |ui: &egui::Ui, _: &str, _wrap_width: f32| {
                                             let mut layout_job = egui::text::LayoutJob::default();
                                             if let Some(selection) = selection.as_ref() {
-                                                let (family, variant) = selection.game.family_and_variant();
+                                                let (family, variant) = selection.game.strings_family_and_variant();
 
                                                 if warning.is_some() {
                                                     gui::warning::append_to_layout_job(ui, &mut layout_job);
@@ -1824,6 +2111,8 @@ pub fn show(
                     &mut *selection,
                     &config.language,
                     &config.saves_path(),
+                    &config.backups_path(),
+                    &config.save_backup_retention,
                     roms_scanner.clone(),
                     saves_scanner.clone(),
                     patches_scanner.clone(),
@@ -1900,21 +2189,27 @@ pub fn show(
                                         .map(|patch| {
                                             patch
                                                 .versions
-                                                .values()
-                                                .map(|vi| vi.netplay_compatibility.as_str())
+                                                .keys()
+                                                .flat_map(|v| {
+                                                    get_netplay_compatibility(
+                                                        selection.game,
+                                                        Some((patch_name.as_str(), v)),
+                                                        &patches,
+                                                    )
+                                                })
                                                 .collect()
                                         })
                                         .unwrap_or_else(|| vec![])
                                 } else {
-                                    vec![selection.game.family_and_variant().0]
+                                    vec![selection.game.family_and_variant().0.to_string()]
                                 };
 
-                            if let Some(nc) = get_netplay_compatibility(
+                            if let Some(nc) = get_netplay_compatibility_from_patch_info(
                                 remote_game,
-                                remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
+                                remote_gi.patch.as_ref(),
                                 &patches,
                             ) {
-                                if !local_netplay_compatibilities.contains(&nc.as_str()) {
+                                if !local_netplay_compatibilities.contains(&nc) {
                                     return Some(Warning::Incompatible);
                                 }
                             }
@@ -1988,9 +2283,9 @@ pub fn show(
                                             return None;
                                         };
 
-                                        if let Some(nc) = get_netplay_compatibility(
+                                        if let Some(nc) = get_netplay_compatibility_from_patch_info(
                                             remote_game,
-                                            remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
+                                            remote_gi.patch.as_ref(),
                                             &patches,
                                         ) {
                                             if nc != selection.game.family_and_variant().0 {
@@ -2065,18 +2360,24 @@ pub fn show(
                                             .map(|patch| {
                                                 patch
                                                     .versions
-                                                    .values()
-                                                    .map(|vi| vi.netplay_compatibility.as_str())
+                                                    .keys()
+                                                    .flat_map(|v| {
+                                                        get_netplay_compatibility(
+                                                            selection.game,
+                                                            Some((name.as_str(), v)),
+                                                            &patches,
+                                                        )
+                                                    })
                                                     .collect()
                                             })
                                             .unwrap_or_else(|| vec![]);
 
-                                        if let Some(nc) = get_netplay_compatibility(
+                                        if let Some(nc) = get_netplay_compatibility_from_patch_info(
                                             remote_game,
-                                            remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
+                                            remote_gi.patch.as_ref(),
                                             &patches,
                                         ) {
-                                            if !local_netplay_compatibilities.contains(&nc.as_str()) {
+                                            if !local_netplay_compatibilities.contains(&nc) {
                                                 return Some(Warning::Incompatible);
                                             }
                                         }
@@ -2132,13 +2433,16 @@ pub fn show(
                                             return;
                                         };
 
-                                        let rom = match patch::apply_patch_from_disk(
-                                            &rom,
-                                            selection.game,
-                                            &config.patches_path(),
-                                            &name,
-                                            &version,
-                                        ) {
+                                        let rom = match patch::resolve_chain(&patches, name, version)
+                                            .ok_or_else(|| anyhow::anyhow!("could not resolve base patches"))
+                                            .and_then(|chain| {
+                                                patch::apply_patch_chain_from_disk(
+                                                    &rom,
+                                                    selection.game,
+                                                    &config.patches_path(),
+                                                    &chain,
+                                                )
+                                            }) {
                                             Ok(r) => r,
                                             Err(e) => {
                                                 log::error!(
@@ -2221,9 +2525,9 @@ pub fn show(
                                         &patches,
                                     );
 
-                                    let remote_netplay_compatibility = get_netplay_compatibility(
+                                    let remote_netplay_compatibility = get_netplay_compatibility_from_patch_info(
                                         remote_game,
-                                        remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
+                                        remote_gi.patch.as_ref(),
                                         &patches,
                                     );
 
@@ -2316,11 +2620,12 @@ pub fn show(
                                                     &patches,
                                                 );
 
-                                                let remote_netplay_compatibility = get_netplay_compatibility(
-                                                    remote_game,
-                                                    remote_gi.patch.as_ref().map(|pi| (pi.name.as_str(), &pi.version)),
-                                                    &patches,
-                                                );
+                                                let remote_netplay_compatibility =
+                                                    get_netplay_compatibility_from_patch_info(
+                                                        remote_game,
+                                                        remote_gi.patch.as_ref(),
+                                                        &patches,
+                                                    );
 
                                                 if local_netplay_compatibility != remote_netplay_compatibility {
                                                     return Some(Warning::Incompatible);
@@ -2365,13 +2670,16 @@ pub fn show(
                                                     return;
                                                 };
 
-                                                let rom = match patch::apply_patch_from_disk(
-                                                    &rom,
-                                                    selection.game,
-                                                    &config.patches_path(),
-                                                    &patch_name,
-                                                    &version,
-                                                ) {
+                                                let rom = match patch::resolve_chain(&patches, &patch_name, version)
+                                                    .ok_or_else(|| anyhow::anyhow!("could not resolve base patches"))
+                                                    .and_then(|chain| {
+                                                        patch::apply_patch_chain_from_disk(
+                                                            &rom,
+                                                            selection.game,
+                                                            &config.patches_path(),
+                                                            &chain,
+                                                        )
+                                                    }) {
                                                     Ok(r) => r,
                                                     Err(e) => {
                                                         log::error!(
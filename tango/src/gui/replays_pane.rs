@@ -201,7 +201,10 @@ pub fn show(
                                             i18n::LOCALES
                                                 .lookup(
                                                     language,
-                                                    &format!("game-{}.short", local_game.family_and_variant().0),
+                                                    &format!(
+                                                        "game-{}.short",
+                                                        local_game.strings_family_and_variant().0
+                                                    ),
                                                 )
                                                 .unwrap()
                                                 .into(),
@@ -270,13 +273,11 @@ pub fn show(
 
                                 let (rom_code, revision) = local_game.rom_code_and_revision();
 
-                                local_rom = match patch::apply_patch_from_disk(
-                                    &local_rom,
-                                    local_game,
-                                    patches_path,
-                                    &patch_info.name,
-                                    &version,
-                                ) {
+                                local_rom = match replay_patch_chain(&patches, patch_info)
+                                    .ok_or_else(|| anyhow::anyhow!("could not resolve base patches"))
+                                    .and_then(|chain| {
+                                        patch::apply_patch_chain_from_disk(&local_rom, local_game, patches_path, &chain)
+                                    }) {
                                     Ok(r) => r,
                                     Err(e) => {
                                         log::error!(
@@ -313,21 +314,13 @@ pub fn show(
                                 (|| {
                                     let mut rom = rom.clone();
                                     if let Some(patch_info) = remote_game_info.patch.as_ref() {
-                                        let version = if let Ok(version) = semver::Version::parse(&patch_info.version) {
-                                            version
-                                        } else {
-                                            return None;
-                                        };
-
                                         let (rom_code, revision) = remote_game.rom_code_and_revision();
 
-                                        rom = match patch::apply_patch_from_disk(
-                                            &rom,
-                                            remote_game,
-                                            patches_path,
-                                            &patch_info.name,
-                                            &version,
-                                        ) {
+                                        rom = match replay_patch_chain(&patches, patch_info)
+                                            .ok_or_else(|| anyhow::anyhow!("could not resolve base patches"))
+                                            .and_then(|chain| {
+                                                patch::apply_patch_chain_from_disk(&rom, remote_game, patches_path, &chain)
+                                            }) {
                                             Ok(r) => r,
                                             Err(e) => {
                                                 log::error!(
@@ -392,6 +385,7 @@ pub fn show(
                                 let rom = selection.local_rom.clone();
                                 let emu_tps_counter = emu_tps_counter.clone();
                                 let replay = selection.replay.clone();
+                                let session = session.clone();
 
                                 move || {
                                     *session.lock() = Some(
@@ -410,6 +404,58 @@ pub fn show(
                             });
                         }
 
+                        if let Some(remote_rom) = selection.remote_rom.as_ref() {
+                            if ui
+                                .add_enabled(
+                                    selection.replay.has_match_type(),
+                                    egui::Button::new(format!(
+                                        "🥊 {}",
+                                        i18n::LOCALES.lookup(language, "replays-practice").unwrap()
+                                    )),
+                                )
+                                .on_hover_text(i18n::LOCALES.lookup(language, "replays-practice.description").unwrap())
+                                .on_disabled_hover_text(
+                                    i18n::LOCALES.lookup(language, "replays-practice.unsupported").unwrap(),
+                                )
+                                .clicked()
+                            {
+                                tokio::task::spawn_blocking({
+                                    let egui_ctx = ui.ctx().clone();
+                                    let audio_binder = audio_binder.clone();
+                                    let game = selection.game;
+                                    let patch = selection
+                                        .patch
+                                        .as_ref()
+                                        .map(|(name, version, _)| (name.clone(), version.clone()));
+                                    let rom = selection.local_rom.clone();
+                                    let remote_rom = remote_rom.clone();
+                                    let emu_tps_counter = emu_tps_counter.clone();
+                                    let replay = selection.replay.clone();
+                                    let session = session.clone();
+
+                                    move || {
+                                        match session::Session::new_practice(
+                                            audio_binder,
+                                            game,
+                                            patch,
+                                            &rom,
+                                            &remote_rom,
+                                            emu_tps_counter,
+                                            &replay,
+                                        ) {
+                                            Ok(s) => {
+                                                *session.lock() = Some(s);
+                                            }
+                                            Err(e) => {
+                                                log::error!("failed to start practice: {:?}", e);
+                                            }
+                                        }
+                                        egui_ctx.request_repaint();
+                                    }
+                                });
+                            }
+                        }
+
                         if ui
                             .button(format!(
                                 "💾 {}",
@@ -470,3 +516,24 @@ pub fn show(
             });
     });
 }
+
+/// Returns the patches to apply for one side of a replay: exactly the chain the replay was recorded with, rather than
+/// whichever base versions are the newest installed now.
+fn replay_patch_chain(
+    patches: &std::collections::BTreeMap<String, patch::Patch>,
+    patch_info: &replay::metadata::game_info::Patch,
+) -> Option<Vec<(String, semver::Version)>> {
+    let chain = patch_info
+        .bases
+        .iter()
+        .chain(std::iter::once(patch_info))
+        .map(|p| Some((p.name.clone(), semver::Version::parse(&p.version).ok()?)))
+        .collect::<Option<Vec<_>>>()?;
+    if !chain
+        .iter()
+        .all(|(name, version)| patches.get(name).map(|p| p.versions.contains_key(version)).unwrap_or(false))
+    {
+        return None;
+    }
+    Some(chain)
+}
@@ -1,31 +1,401 @@
+use chrono_locale::LocaleDate;
 use fluent_templates::Loader;
 
 use crate::{game, gui, i18n, net, patch, rom, save};
 
+struct History {
+    save_path: std::path::PathBuf,
+    dir: std::path::PathBuf,
+    snapshots: Vec<(save::backup::Snapshot, Option<Box<dyn save::Save + Send + Sync>>)>,
+    assets: Option<Box<dyn rom::Assets + Send + Sync>>,
+    selected: Option<usize>,
+    error: Option<String>,
+}
+
+impl History {
+    fn load(
+        game: &'static (dyn game::Game + Send + Sync),
+        rom: &[u8],
+        save_path: &std::path::Path,
+        dir: std::path::PathBuf,
+    ) -> Self {
+        let snapshots = save::backup::list(&dir)
+            .into_iter()
+            .map(|snapshot| {
+                let save = save::read_raw(&snapshot.path)
+                    .and_then(|(_, raw)| game.parse_save(&raw))
+                    .map_err(|e| {
+                        log::warn!("failed to parse snapshot {}: {:?}", snapshot.path.display(), e);
+                        e
+                    })
+                    .ok();
+                (snapshot, save)
+            })
+            .collect::<Vec<_>>();
+
+        let assets = snapshots
+            .iter()
+            .flat_map(|(_, save)| save.as_ref())
+            .next()
+            .and_then(|save| game.load_rom_assets(rom, save.as_raw_wram(), &Default::default()).ok());
+
+        Self {
+            save_path: save_path.to_path_buf(),
+            dir,
+            snapshots,
+            assets,
+            selected: None,
+            error: None,
+        }
+    }
+}
+
+/// A region conversion that drops progress, waiting for the player to confirm it.
+struct PendingConversion {
+    counterpart: &'static (dyn game::Game + Send + Sync),
+    save: Vec<u8>,
+    path: std::path::PathBuf,
+    losses: Vec<save::RegionConversionLoss>,
+}
+
 pub struct State {
     selection: Option<(&'static (dyn game::Game + Send + Sync), Option<std::path::PathBuf>)>,
+    history: Option<History>,
+    pending_conversion: Option<PendingConversion>,
+    error: Option<String>,
 }
 
 impl State {
     pub fn new(selection: Option<(&'static (dyn game::Game + Send + Sync), Option<std::path::PathBuf>)>) -> Self {
-        Self { selection }
+        Self {
+            selection,
+            history: None,
+            pending_conversion: None,
+            error: None,
+        }
     }
 }
 
+/// Writes a save converted for `counterpart` to `path`, returning the error to show if it fails.
+fn convert_region(
+    language: &unic_langid::LanguageIdentifier,
+    counterpart: &'static (dyn game::Game + Send + Sync),
+    save: &[u8],
+    path: &std::path::Path,
+    saves_path: &std::path::Path,
+    saves_scanner: save::Scanner,
+    egui_ctx: egui::Context,
+) -> Option<String> {
+    if let Err(e) = counterpart
+        .convert_save_from_region_counterpart(save)
+        .and_then(|converted| {
+            // Never clobber a save that is already there, e.g. one converted earlier and played since.
+            let mut f = std::fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+            std::io::Write::write_all(&mut f, &converted.to_vec())?;
+            Ok(())
+        })
+    {
+        log::error!("failed to convert save: {:?}", e);
+        return Some(
+            i18n::LOCALES
+                .lookup_with_args(
+                    language,
+                    "select-save.convert-region-failed",
+                    &std::collections::HashMap::from([("error", format!("{}: {}", path.display(), e).into())]),
+                )
+                .unwrap(),
+        );
+    }
+
+    tokio::task::spawn_blocking({
+        let saves_path = saves_path.to_path_buf();
+        move || {
+            saves_scanner.rescan(move || Some(save::scan_saves(&saves_path)));
+            egui_ctx.request_repaint();
+        }
+    });
+    None
+}
+
+fn show_conversion_confirmation(
+    ctx: &egui::Context,
+    language: &unic_langid::LanguageIdentifier,
+    pending_conversion: &PendingConversion,
+) -> Option<bool> {
+    let mut confirmed = None;
+    let (family, variant) = pending_conversion.counterpart.strings_family_and_variant();
+    egui::Window::new(
+        i18n::LOCALES
+            .lookup(language, "select-save.convert-region-confirm")
+            .unwrap(),
+    )
+    .id(egui::Id::new("select-save-convert-region-confirm"))
+    .collapsible(false)
+    .resizable(false)
+    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+    .show(ctx, |ui| {
+        ui.label(
+            i18n::LOCALES
+                .lookup_with_args(
+                    language,
+                    "select-save.convert-region-confirm-description",
+                    &std::collections::HashMap::from([(
+                        "game_name",
+                        i18n::LOCALES
+                            .lookup(language, &format!("game-{}.variant-{}", family, variant))
+                            .unwrap()
+                            .into(),
+                    )]),
+                )
+                .unwrap(),
+        );
+        for loss in pending_conversion.losses.iter() {
+            let (key, count) = match loss {
+                save::RegionConversionLoss::Modcards(count) => ("select-save.convert-region-loss-modcards", count),
+                save::RegionConversionLoss::UnmappedBytes(count) => ("select-save.convert-region-loss-unmapped", count),
+            };
+            ui.label(format!(
+                "• {}",
+                i18n::LOCALES
+                    .lookup_with_args(
+                        language,
+                        key,
+                        &std::collections::HashMap::from([("count", (*count).into())]),
+                    )
+                    .unwrap()
+            ));
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .button(i18n::LOCALES.lookup(language, "play-cancel").unwrap())
+                .clicked()
+            {
+                confirmed = Some(false);
+            }
+            if ui
+                .button(
+                    i18n::LOCALES
+                        .lookup(language, "select-save.convert-region-proceed")
+                        .unwrap(),
+                )
+                .clicked()
+            {
+                confirmed = Some(true);
+            }
+        });
+    });
+    confirmed
+}
+
+fn show_snapshot_preview(
+    ui: &mut egui::Ui,
+    language: &unic_langid::LanguageIdentifier,
+    save: &Box<dyn save::Save + Send + Sync>,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) {
+    let assets = if let Some(assets) = assets {
+        assets
+    } else {
+        return;
+    };
+
+    if let Some(chips_view) = save.view_chips() {
+        ui.heading(i18n::LOCALES.lookup(language, "save-tab-folder").unwrap());
+        let folder_index = chips_view.equipped_folder_index();
+        for chip in (0..30).flat_map(|i| chips_view.chip(folder_index, i)) {
+            ui.label(format!(
+                "{} {}",
+                assets
+                    .chip(chip.id)
+                    .map(|info| info.name())
+                    .unwrap_or_else(|| "???".to_string()),
+                chip.code
+            ));
+        }
+    }
+
+    if let Some(navicust_view) = save.view_navicust() {
+        ui.heading(i18n::LOCALES.lookup(language, "save-tab-navicust").unwrap());
+        for part in (0..navicust_view.count()).flat_map(|i| navicust_view.navicust_part(i)) {
+            ui.label(
+                assets
+                    .navicust_part(part.id, part.variant)
+                    .map(|info| info.name())
+                    .unwrap_or_else(|| "???".to_string()),
+            );
+        }
+    }
+}
+
+fn show_history(
+    ui: &mut egui::Ui,
+    history: &mut History,
+    selection: &mut Option<gui::Selection>,
+    language: &unic_langid::LanguageIdentifier,
+    saves_path: &std::path::Path,
+    saves_scanner: save::Scanner,
+    backup_retention: &save::backup::Retention,
+) -> bool {
+    let mut close = false;
+
+    ui.group(|ui| {
+        ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+            if ui
+                .selectable_label(
+                    false,
+                    format!(
+                        "⬅️ {}",
+                        i18n::LOCALES
+                            .lookup(language, "select-save.return-to-saves-list")
+                            .unwrap()
+                    ),
+                )
+                .clicked()
+            {
+                close = true;
+            }
+
+            ui.label(
+                history
+                    .save_path
+                    .strip_prefix(saves_path)
+                    .unwrap_or(history.save_path.as_path())
+                    .display()
+                    .to_string(),
+            );
+
+            if let Some(error) = history.error.as_ref() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            if history.snapshots.is_empty() {
+                ui.label(i18n::LOCALES.lookup(language, "select-save.no-snapshots").unwrap());
+                return;
+            }
+
+            ui.columns(2, |columns| {
+                egui::ScrollArea::vertical()
+                    .id_source("save-select-history-snapshots")
+                    .auto_shrink([false, false])
+                    .show(&mut columns[0], |ui| {
+                        ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                            for (i, (snapshot, save)) in history.snapshots.iter().enumerate() {
+                                if ui
+                                    .add_enabled(
+                                        save.is_some(),
+                                        egui::SelectableLabel::new(
+                                            history.selected == Some(i),
+                                            chrono::DateTime::<chrono::Local>::from(snapshot.time)
+                                                .formatl("%c", &language.to_string())
+                                                .to_string(),
+                                        ),
+                                    )
+                                    .clicked()
+                                {
+                                    history.selected = Some(i);
+                                }
+                            }
+                        });
+                    });
+
+                let ui = &mut columns[1];
+                let (snapshot, save) =
+                    if let Some((snapshot, Some(save))) = history.selected.and_then(|i| history.snapshots.get(i)) {
+                        (snapshot.clone(), save)
+                    } else {
+                        return;
+                    };
+
+                if ui
+                    .button(format!(
+                        "♻️ {}",
+                        i18n::LOCALES.lookup(language, "select-save.restore-snapshot").unwrap()
+                    ))
+                    .clicked()
+                {
+                    match save::backup::restore(&history.dir, &snapshot, &history.save_path, backup_retention) {
+                        Ok(()) => {
+                            history.error = None;
+                            if let Some(selection) = selection.as_mut() {
+                                if selection.save.path == history.save_path {
+                                    if let Err(e) = selection.reload_save() {
+                                        log::error!("failed to reload save: {:?}", e);
+                                    }
+                                }
+                            }
+                            tokio::task::spawn_blocking({
+                                let saves_scanner = saves_scanner.clone();
+                                let saves_path = saves_path.to_path_buf();
+                                let egui_ctx = ui.ctx().clone();
+                                move || {
+                                    saves_scanner.rescan(move || Some(save::scan_saves(&saves_path)));
+                                    egui_ctx.request_repaint();
+                                }
+                            });
+                            close = true;
+                        }
+                        Err(e) => {
+                            log::error!("failed to restore snapshot: {:?}", e);
+                            history.error = Some(
+                                i18n::LOCALES
+                                    .lookup_with_args(
+                                        language,
+                                        "select-save.restore-snapshot-failed",
+                                        &std::collections::HashMap::from([("error", format!("{}", e).into())]),
+                                    )
+                                    .unwrap(),
+                            );
+                        }
+                    }
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_source("save-select-history-preview")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        show_snapshot_preview(ui, language, save, history.assets.as_ref());
+                    });
+            });
+        });
+    });
+
+    close
+}
+
 pub fn show(
     ui: &mut egui::Ui,
     show: &mut Option<State>,
     selection: &mut Option<gui::Selection>,
     language: &unic_langid::LanguageIdentifier,
     saves_path: &std::path::Path,
+    backups_path: &std::path::Path,
+    backup_retention: &save::backup::Retention,
     roms_scanner: rom::Scanner,
     saves_scanner: save::Scanner,
     patches_scanner: patch::Scanner,
     remote_settings: Option<&net::protocol::Settings>,
 ) {
+    if let Some(history) = show.as_mut().unwrap().history.as_mut() {
+        if show_history(
+            ui,
+            history,
+            selection,
+            language,
+            saves_path,
+            saves_scanner.clone(),
+            backup_retention,
+        ) {
+            show.as_mut().unwrap().history = None;
+        }
+        return;
+    }
+
     let roms = roms_scanner.read();
     let saves = saves_scanner.read();
     let patches = patches_scanner.read();
+    let mut open_history = None;
+    let mut pending_conversion = None;
+    let mut error = None;
 
     ui.vertical(|ui| {
         let games = game::sorted_all_games(language);
@@ -47,7 +417,7 @@ pub fn show(
             }
 
             if let Some((game, _)) = show.as_mut().unwrap().selection {
-                let (family, variant) = game.family_and_variant();
+                let (family, variant) = game.strings_family_and_variant();
                 ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
                     ui.horizontal(|ui| {
                         ui.with_layout(
@@ -84,10 +454,14 @@ pub fn show(
                     }
                 }
 
+                if let Some(error) = show.as_ref().unwrap().error.as_ref() {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
                 egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
                     ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                         if let Some((game, _)) = show.as_ref().unwrap().selection.clone() {
-                            if let Some(saves) = saves.get(&game) {
+                            if let Some(saves) = saves.get(&game.base_game().unwrap_or(game)) {
                                 for save in saves {
                                     let selected = selection
                                         .as_ref()
@@ -113,7 +487,140 @@ pub fn show(
                                             },
                                         ),
                                     );
-                                    if ui.selectable_label(selected, layout_job).clicked() {
+                                    let resp = ui.selectable_label(selected, layout_job).context_menu(|ui| {
+                                        if let Some(counterpart) = game.region_counterpart() {
+                                            let (family, variant) = counterpart.strings_family_and_variant();
+                                            if ui
+                                                .button(
+                                                    i18n::LOCALES
+                                                        .lookup_with_args(
+                                                            language,
+                                                            "select-save.convert-region",
+                                                            &std::collections::HashMap::from([(
+                                                                "game_name",
+                                                                i18n::LOCALES
+                                                                    .lookup(
+                                                                        language,
+                                                                        &format!("game-{}.variant-{}", family, variant),
+                                                                    )
+                                                                    .unwrap()
+                                                                    .into(),
+                                                            )]),
+                                                        )
+                                                        .unwrap(),
+                                                )
+                                                .clicked()
+                                            {
+                                                ui.close_menu();
+                                                let path = save.path.with_file_name(format!(
+                                                    "{}_{}.sav",
+                                                    save.path
+                                                        .file_stem()
+                                                        .and_then(|stem| stem.to_str())
+                                                        .unwrap_or("save"),
+                                                    family
+                                                ));
+                                                let losses = save.save.region_conversion_losses();
+                                                if losses.is_empty() {
+                                                    error = convert_region(
+                                                        language,
+                                                        counterpart,
+                                                        &save.save.to_vec(),
+                                                        &path,
+                                                        saves_path,
+                                                        saves_scanner.clone(),
+                                                        ui.ctx().clone(),
+                                                    );
+                                                } else {
+                                                    pending_conversion = Some(PendingConversion {
+                                                        counterpart,
+                                                        save: save.save.to_vec(),
+                                                        path,
+                                                        losses,
+                                                    });
+                                                }
+                                            }
+                                            ui.separator();
+                                        }
+
+                                        let rom = if let Some(rom) = roms.get(&game) {
+                                            rom
+                                        } else {
+                                            return;
+                                        };
+
+                                        if ui
+                                            .button(i18n::LOCALES.lookup(language, "select-save.view-history").unwrap())
+                                            .clicked()
+                                        {
+                                            ui.close_menu();
+                                            open_history = Some(History::load(
+                                                game,
+                                                rom,
+                                                &save.path,
+                                                save::backup::snapshots_dir(backups_path, saves_path, &save.path),
+                                            ));
+                                        }
+                                        ui.separator();
+                                        for container in save::container::ALL {
+                                            if ui
+                                                .button(
+                                                    i18n::LOCALES
+                                                        .lookup(
+                                                            language,
+                                                            match container {
+                                                                save::container::Container::Raw => {
+                                                                    "select-save.export-raw"
+                                                                }
+                                                                save::container::Container::NoCashGba => {
+                                                                    "select-save.export-nocash-gba"
+                                                                }
+                                                                save::container::Container::SharkPort => {
+                                                                    "select-save.export-sharkport"
+                                                                }
+                                                            },
+                                                        )
+                                                        .unwrap(),
+                                                )
+                                                .clicked()
+                                            {
+                                                ui.close_menu();
+                                                if let Some(path) = rfd::FileDialog::new()
+                                                    .set_directory(save.path.parent().unwrap_or(saves_path))
+                                                    .set_file_name(&format!(
+                                                        "{}.{}",
+                                                        save.path
+                                                            .file_stem()
+                                                            .and_then(|stem| stem.to_str())
+                                                            .unwrap_or("save"),
+                                                        container.extension()
+                                                    ))
+                                                    .save_file()
+                                                {
+                                                    if let Err(e) =
+                                                        save::container::wrap(*container, &save.save.to_vec(), rom)
+                                                            .map_err(|e| anyhow::anyhow!(e))
+                                                            .and_then(|buf| Ok(std::fs::write(&path, buf)?))
+                                                    {
+                                                        log::error!("failed to export save: {:?}", e);
+                                                        error = Some(
+                                                            i18n::LOCALES
+                                                                .lookup_with_args(
+                                                                    language,
+                                                                    "select-save.export-failed",
+                                                                    &std::collections::HashMap::from([(
+                                                                        "error",
+                                                                        format!("{}: {}", path.display(), e).into(),
+                                                                    )]),
+                                                                )
+                                                                .unwrap(),
+                                                        );
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    });
+                                    if resp.clicked() {
                                         let (game, rom, patch) = if let Some(selection) = selection.take() {
                                             if selection.game == game {
                                                 (selection.game, selection.rom, selection.patch)
@@ -168,10 +675,16 @@ pub fn show(
                                         gui::play_pane::get_netplay_compatibility_from_game_info(remote_gi, &patches)
                                     {
                                         if &netplay_compatibility != family
-                                            && !patches.values().any(|metadata| {
-                                                metadata.versions.values().any(|version| {
+                                            && !patches.iter().any(|(name, metadata)| {
+                                                metadata.versions.iter().any(|(v, version)| {
                                                     version.supported_games.contains(game)
-                                                        && version.netplay_compatibility == netplay_compatibility
+                                                        && gui::play_pane::get_netplay_compatibility(
+                                                            *game,
+                                                            Some((name.as_str(), v)),
+                                                            &patches,
+                                                        )
+                                                        .as_ref()
+                                                            == Some(&netplay_compatibility)
                                                 })
                                             })
                                         {
@@ -187,7 +700,10 @@ pub fn show(
                                 }
                                 layout_job.append(
                                     &i18n::LOCALES
-                                        .lookup(language, &format!("game-{}.variant-{}", family, variant))
+                                        .lookup(language, &{
+                                            let (family, variant) = game.strings_family_and_variant();
+                                            format!("game-{}.variant-{}", family, variant)
+                                        })
                                         .unwrap(),
                                     0.0,
                                     egui::TextFormat::simple(
@@ -216,4 +732,37 @@ pub fn show(
             });
         });
     });
+
+    if let Some(history) = open_history {
+        show.as_mut().unwrap().history = Some(history);
+    }
+
+    if let Some(pending_conversion) = pending_conversion {
+        show.as_mut().unwrap().pending_conversion = Some(pending_conversion);
+    }
+
+    if let Some(pending) = show.as_ref().unwrap().pending_conversion.as_ref() {
+        match show_conversion_confirmation(ui.ctx(), language, pending) {
+            Some(true) => {
+                error = convert_region(
+                    language,
+                    pending.counterpart,
+                    &pending.save,
+                    &pending.path,
+                    saves_path,
+                    saves_scanner.clone(),
+                    ui.ctx().clone(),
+                );
+                show.as_mut().unwrap().pending_conversion = None;
+            }
+            Some(false) => {
+                show.as_mut().unwrap().pending_conversion = None;
+            }
+            None => {}
+        }
+    }
+
+    if let (Some(show), Some(error)) = (show.as_mut(), error) {
+        show.error = Some(error);
+    }
 }
@@ -3,6 +3,7 @@ mod folder_view;
 mod modcards_view;
 mod navi_view;
 mod navicust_view;
+mod stats_view;
 
 use fluent_templates::Loader;
 
@@ -15,6 +16,7 @@ enum Tab {
     Folder,
     Modcards,
     DarkAI,
+    Stats,
 }
 
 pub struct State {
@@ -24,6 +26,7 @@ pub struct State {
     folder_view: folder_view::State,
     modcards_view: modcards_view::State,
     dark_ai_view: dark_ai_view::State,
+    stats_view: stats_view::State,
 }
 
 impl State {
@@ -35,6 +38,7 @@ impl State {
             folder_view: folder_view::State::new(),
             modcards_view: modcards_view::State::new(),
             dark_ai_view: dark_ai_view::State::new(),
+            stats_view: stats_view::State::new(),
         }
     }
 }
@@ -56,6 +60,7 @@ pub fn show(
         let chips_view = save.view_chips();
         let modcards_view = save.view_modcards();
         let dark_ai_view = save.view_dark_ai();
+        let stats_view = save.view_stats();
 
         let mut available_tabs = vec![];
         if navi_view.is_some() {
@@ -73,6 +78,9 @@ pub fn show(
         if dark_ai_view.is_some() {
             available_tabs.push(Tab::DarkAI);
         }
+        if stats_view.is_some() {
+            available_tabs.push(Tab::Stats);
+        }
 
         ui.horizontal(|ui| {
             if streamer_mode
@@ -96,6 +104,7 @@ pub fn show(
                                     Tab::Folder => "save-tab-folder",
                                     Tab::Modcards => "save-tab-modcards",
                                     Tab::DarkAI => "save-tab-dark-ai",
+                                    Tab::Stats => "save-tab-stats",
                                 },
                             )
                             .unwrap(),
@@ -183,6 +192,20 @@ pub fn show(
                     );
                 }
             }
+            Some(Tab::Stats) => {
+                if let Some(stats_view) = stats_view {
+                    stats_view::show(
+                        ui,
+                        clipboard,
+                        font_families,
+                        lang,
+                        game_lang,
+                        &stats_view,
+                        assets,
+                        &mut state.stats_view,
+                    );
+                }
+            }
             None => {
                 ui.with_layout(
                     egui::Layout::centered_and_justified(egui::Direction::LeftToRight)
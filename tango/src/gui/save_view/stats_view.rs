@@ -0,0 +1,179 @@
+use fluent_templates::Loader;
+
+use crate::{gui, i18n, rom, save};
+
+pub struct State {
+    chip_icon_texture_cache: std::collections::HashMap<usize, egui::TextureHandle>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            chip_icon_texture_cache: std::collections::HashMap::new(),
+        }
+    }
+}
+
+pub fn show<'a>(
+    ui: &mut egui::Ui,
+    clipboard: &mut arboard::Clipboard,
+    font_families: &gui::FontFamilies,
+    lang: &unic_langid::LanguageIdentifier,
+    game_lang: &unic_langid::LanguageIdentifier,
+    stats_view: &Box<dyn save::StatsView<'a> + 'a>,
+    assets: &Box<dyn rom::Assets + Send + Sync>,
+    state: &mut State,
+) {
+    let library = (0..assets.num_chips())
+        .flat_map(|id| {
+            let info = assets.chip(id)?;
+            let counts = info
+                .codes()
+                .into_iter()
+                .enumerate()
+                .flat_map(|(variant, code)| {
+                    let count = stats_view.pack_count(id, variant)?;
+                    if count == 0 {
+                        return None;
+                    }
+                    Some((code, count))
+                })
+                .collect::<Vec<_>>();
+            if counts.is_empty() {
+                return None;
+            }
+            Some((id, info.name(), counts))
+        })
+        .collect::<Vec<_>>();
+
+    ui.horizontal(|ui| {
+        if ui
+            .button(format!(
+                "📋 {}",
+                i18n::LOCALES.lookup(lang, "copy-to-clipboard").unwrap(),
+            ))
+            .clicked()
+        {
+            let _ = clipboard.set_text(
+                library
+                    .iter()
+                    .map(|(_, name, counts)| {
+                        format!(
+                            "{}\t{}",
+                            name,
+                            counts
+                                .iter()
+                                .map(|(code, count)| format!("{}x{}", code, count))
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+    });
+
+    egui::Grid::new("stats-view-summary").num_columns(2).show(ui, |ui| {
+        if let (Some(hp), Some(max_hp)) = (stats_view.hp(), stats_view.max_hp()) {
+            ui.strong(i18n::LOCALES.lookup(lang, "save-stats-hp").unwrap());
+            ui.label(format!("{}/{}", hp, max_hp));
+            ui.end_row();
+        }
+
+        if let Some(zenny) = stats_view.zenny() {
+            ui.strong(i18n::LOCALES.lookup(lang, "save-stats-zenny").unwrap());
+            ui.label(format!("{}z", zenny));
+            ui.end_row();
+        }
+
+        if let Some(bugfrags) = stats_view.bugfrags() {
+            ui.strong(i18n::LOCALES.lookup(lang, "save-stats-bugfrags").unwrap());
+            ui.label(format!("{}", bugfrags));
+            ui.end_row();
+        }
+
+        ui.strong(i18n::LOCALES.lookup(lang, "save-stats-library").unwrap());
+        ui.label(
+            i18n::LOCALES
+                .lookup_with_args(
+                    lang,
+                    "save-stats-library.summary",
+                    &std::collections::HashMap::from([
+                        ("count", library.len().into()),
+                        (
+                            "total",
+                            library
+                                .iter()
+                                .map(|(_, _, counts)| counts.iter().map(|(_, count)| *count).sum::<usize>())
+                                .sum::<usize>()
+                                .into(),
+                        ),
+                    ]),
+                )
+                .unwrap(),
+        );
+        ui.end_row();
+    });
+
+    egui::ScrollArea::vertical()
+        .id_source("stats-view")
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            egui_extras::StripBuilder::new(ui)
+                .sizes(egui_extras::Size::exact(28.0), library.len())
+                .vertical(|mut outer_strip| {
+                    for (i, (id, name, counts)) in library.iter().enumerate() {
+                        outer_strip.cell(|ui| {
+                            let rect = ui.available_rect_before_wrap().expand(ui.spacing().item_spacing.y);
+                            if i % 2 == 0 {
+                                ui.painter().rect_filled(rect, 0.0, ui.visuals().faint_bg_color);
+                            }
+
+                            egui_extras::StripBuilder::new(ui)
+                                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                                .size(egui_extras::Size::exact(28.0))
+                                .size(egui_extras::Size::remainder())
+                                .size(egui_extras::Size::remainder())
+                                .horizontal(|mut strip| {
+                                    strip.cell(|ui| {
+                                        match state.chip_icon_texture_cache.entry(*id) {
+                                            std::collections::hash_map::Entry::Occupied(_) => {}
+                                            std::collections::hash_map::Entry::Vacant(e) => {
+                                                if let Some(image) = assets.chip(*id).map(|info| info.icon()) {
+                                                    e.insert(ui.ctx().load_texture(
+                                                        format!("chip icon {}", id),
+                                                        egui::ColorImage::from_rgba_unmultiplied(
+                                                            [14, 14],
+                                                            &image::imageops::crop_imm(&image, 1, 1, 14, 14).to_image(),
+                                                        ),
+                                                        egui::TextureFilter::Nearest,
+                                                    ));
+                                                }
+                                            }
+                                        }
+
+                                        if let Some(texture_handle) = state.chip_icon_texture_cache.get(id) {
+                                            ui.image(texture_handle.id(), egui::Vec2::new(28.0, 28.0));
+                                        }
+                                    });
+                                    strip.cell(|ui| {
+                                        ui.label(
+                                            egui::RichText::new(name).family(font_families.for_language(game_lang)),
+                                        );
+                                    });
+                                    strip.cell(|ui| {
+                                        ui.label(
+                                            counts
+                                                .iter()
+                                                .map(|(code, count)| format!("{}×{}", code, count))
+                                                .collect::<Vec<_>>()
+                                                .join(" "),
+                                        );
+                                    });
+                                });
+                        });
+                    }
+                });
+        });
+}
@@ -1,6 +1,6 @@
 use fluent_templates::Loader;
 
-use crate::{discord, gui, i18n, input, session, stats, sync, video};
+use crate::{battle, discord, gui, i18n, input, session, stats, sync, video};
 
 mod replay_controls_window;
 
@@ -9,6 +9,7 @@ pub struct State {
     opponent_save_view: gui::save_view::State,
     own_save_view: gui::save_view::State,
     debug_window: Option<gui::debug_window::State>,
+    cheats_window: Option<gui::cheats_window::State>,
 }
 
 impl State {
@@ -18,6 +19,7 @@ impl State {
             opponent_save_view: gui::save_view::State::new(),
             own_save_view: gui::save_view::State::new(),
             debug_window: None,
+            cheats_window: None,
         }
     }
 }
@@ -130,7 +132,7 @@ pub fn show(
 
     let game_info = session.game_info();
     match session.mode() {
-        session::Mode::SinglePlayer(_) => {
+        session::Mode::SinglePlayer(_) | session::Mode::Practice(_) => {
             discord_client.set_current_activity(Some(discord::make_single_player_activity(
                 session.start_time(),
                 language,
@@ -276,6 +278,7 @@ cpsr = {:08x}"#,
             session,
             show_debug,
             &mut state.debug_window,
+            &mut state.cheats_window,
             fps_counter.clone(),
             emu_tps_counter.clone(),
         );
@@ -304,11 +307,74 @@ cpsr = {:08x}"#,
             session,
             show_debug,
             &mut state.debug_window,
+            &mut state.cheats_window,
             fps_counter.clone(),
             emu_tps_counter.clone(),
         );
     }
     gui::debug_window::show(ctx, language, session, &mut state.debug_window);
+    gui::cheats_window::show(ctx, language, session, &mut state.cheats_window);
+    show_side_choice(ctx, language, session);
+}
+
+/// Between the games of a set, asks the loser of the last game which side to play on next, or tells the winner that
+/// the opponent is picking.
+fn show_side_choice(ctx: &egui::Context, language: &unic_langid::LanguageIdentifier, session: &session::Session) {
+    let pvp = if let session::Mode::PvP(pvp) = session.mode() {
+        pvp
+    } else {
+        return;
+    };
+
+    let match_ = if let Some(match_) = &*sync::block_on(pvp.match_.lock()) {
+        match_.clone()
+    } else {
+        return;
+    };
+
+    let (game, side_chooser) = {
+        let set_state = sync::block_on(match_.lock_set_state());
+        (set_state.game, set_state.side_chooser())
+    };
+    let side_chooser = if let Some(side_chooser) = side_chooser {
+        side_chooser
+    } else {
+        return;
+    };
+
+    egui::Window::new("")
+        .id(egui::Id::new("side-choice-window"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .resizable(false)
+        .title_bar(false)
+        .show(ctx, |ui| {
+            ui.vertical_centered_justified(|ui| match side_chooser {
+                battle::SideChooser::Local => {
+                    ui.label(
+                        i18n::LOCALES
+                            .lookup_with_args(
+                                language,
+                                "set-side-choice-prompt",
+                                &std::collections::HashMap::from([("game", game.to_string().into())]),
+                            )
+                            .unwrap(),
+                    );
+                    for side in 0..2 {
+                        if ui
+                            .button(egui::RichText::new(format!("P{}", side + 1)).heading())
+                            .clicked()
+                        {
+                            if let Err(e) = sync::block_on(match_.choose_side(side)) {
+                                log::error!("failed to pick side: {:?}", e);
+                            }
+                        }
+                    }
+                }
+                battle::SideChooser::Remote => {
+                    ui.label(i18n::LOCALES.lookup(language, "set-side-choice-waiting").unwrap());
+                }
+            });
+        });
 }
 
 fn show_status_bar(
@@ -317,38 +383,49 @@ fn show_status_bar(
     session: &session::Session,
     show_debug: bool,
     debug_window: &mut Option<gui::debug_window::State>,
+    cheats_window: &mut Option<gui::cheats_window::State>,
     fps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
     emu_tps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
 ) {
     egui::TopBottomPanel::bottom("session-status-bar").show(ctx, |ui| {
         ui.horizontal(|ui| {
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                let (tps_adjustment, latency, round_info) = (|| {
+                let (tps_adjustment, latency, set_info, round_info) = (|| {
                     let pvp = if let session::Mode::PvP(pvp) = session.mode() {
                         pvp
                     } else {
-                        return (0.0, None, None);
+                        return (0.0, None, None, None);
                     };
 
                     let match_ = sync::block_on(pvp.match_.lock());
                     let match_ = if let Some(match_) = &*match_ {
                         match_
                     } else {
-                        return (0.0, None, None);
+                        return (0.0, None, None, None);
                     };
 
                     let latency = sync::block_on(match_.latency());
 
+                    let set_info = {
+                        let set_state = sync::block_on(match_.lock_set_state());
+                        if set_state.best_of > 1 {
+                            Some((set_state.best_of, set_state.local_score, set_state.remote_score))
+                        } else {
+                            None
+                        }
+                    };
+
                     let round_state = sync::block_on(match_.lock_round_state());
                     let round = if let Some(round) = round_state.round.as_ref() {
                         round
                     } else {
-                        return (0.0, Some(latency), None);
+                        return (0.0, Some(latency), set_info, None);
                     };
 
                     (
                         round.tps_adjustment(),
                         Some(latency),
+                        set_info,
                         Some((
                             round.local_queue_length(),
                             round.remote_queue_length(),
@@ -375,6 +452,22 @@ fn show_status_bar(
                     ui.add(egui::Separator::default().vertical());
                 }
 
+                if let session::Mode::SinglePlayer(_) = session.mode() {
+                    let cheats_window_open = cheats_window.is_some();
+                    if ui
+                        .selectable_label(cheats_window_open, "🧪")
+                        .on_hover_text(i18n::LOCALES.lookup(language, "cheats").unwrap())
+                        .clicked()
+                    {
+                        *cheats_window = if cheats_window.is_some() {
+                            None
+                        } else {
+                            Some(gui::cheats_window::State::new())
+                        };
+                    }
+                    ui.add(egui::Separator::default().vertical());
+                }
+
                 ui.monospace(format!(
                     "fps {:7.2}",
                     1.0 / fps_counter.lock().mean_duration().as_secs_f32()
@@ -408,6 +501,11 @@ fn show_status_bar(
                     ui.monospace(format!("P{}", local_player_index + 1));
                 }
 
+                if let Some((best_of, local_score, remote_score)) = set_info {
+                    ui.add(egui::Separator::default().vertical());
+                    ui.monospace(format!("bo{} {}-{}", best_of, local_score, remote_score));
+                }
+
                 ui.add(egui::Separator::default().vertical());
             });
         });
@@ -205,6 +205,26 @@ fn show_general_tab(ui: &mut egui::Ui, config: &mut config::Config, font_familie
                 );
                 ui.end_row();
             }
+
+            {
+                ui.strong(
+                    i18n::LOCALES
+                        .lookup(&config.language, "settings-save-backup-max-count")
+                        .unwrap(),
+                );
+                ui.add(egui::DragValue::new(&mut config.save_backup_retention.max_count).clamp_range(0..=1000));
+                ui.end_row();
+            }
+
+            {
+                ui.strong(
+                    i18n::LOCALES
+                        .lookup(&config.language, "settings-save-backup-max-age-days")
+                        .unwrap(),
+                );
+                ui.add(egui::DragValue::new(&mut config.save_backup_retention.max_age_days).clamp_range(0..=3650));
+                ui.end_row();
+            }
         });
 }
 
@@ -588,6 +608,22 @@ fn show_patches_tab(ui: &mut egui::Ui, config: &mut config::Config) {
             );
             ui.end_row();
 
+            ui.strong(
+                i18n::LOCALES
+                    .lookup(&config.language, "settings-require-signed-patch-index")
+                    .unwrap(),
+            );
+            let repo = config.patch_repo_url().to_string();
+            let mut require_signature = config.signed_patch_repos.contains(&repo);
+            if ui.checkbox(&mut require_signature, "").changed() {
+                if require_signature {
+                    config.signed_patch_repos.push(repo);
+                } else {
+                    config.signed_patch_repos.retain(|r| r != &repo);
+                }
+            }
+            ui.end_row();
+
             ui.strong(
                 i18n::LOCALES
                     .lookup(&config.language, "settings-enable-patch-autoupdate")
@@ -0,0 +1,427 @@
+use crate::{battle, config, game, save, session, shadow};
+
+/// Points every hook family passes through on the way from boot to a link battle, in order, along with the ROM offset
+/// that is trapped at each.
+const STAGES: &[(&str, &str)] = &[
+    ("skip logo", "start_screen_jump_table_entry"),
+    ("title", "start_screen_sram_unmask_ret"),
+    ("game load", "game_load_ret"),
+    ("comm menu", "comm_menu_init_ret"),
+    ("battle start", "round_start_ret"),
+];
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Boot each game in the ROMs directory with its real hooks and report which traps fired on the way. Each game is
+    /// run twice: as a primary core with no match attached, which should stop at the comm menu, and as a shadow core,
+    /// which should get into a link battle against itself. Each game needs a save in the saves directory.
+    CheckHooks {
+        /// Directory to look for ROMs in. Defaults to the ROMs directory in the data path.
+        #[arg(long)]
+        roms_path: Option<std::path::PathBuf>,
+
+        /// Directory to look for saves in. Defaults to the saves directory in the data path.
+        #[arg(long)]
+        saves_path: Option<std::path::PathBuf>,
+
+        /// Number of frames each game may take to reach the battle.
+        #[arg(long, default_value_t = 60 * 60 * 5)]
+        max_frames: u32,
+    },
+}
+
+pub fn main(config: config::Config, command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::CheckHooks {
+            roms_path,
+            saves_path,
+            max_frames,
+        } => cmd_check_hooks(config, roms_path, saves_path, max_frames),
+    }
+}
+
+/// Which core a run sets up, i.e. which trap list is installed alongside the common traps.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Side {
+    /// The primary core as it is at the start of a session, before a match is attached. Its traps run for real but find
+    /// no match, so it is expected to boot to the comm menu and stay there.
+    Primary,
+
+    /// The shadow core, which plays a link battle against itself.
+    Shadow,
+}
+
+impl Side {
+    fn name(&self) -> &'static str {
+        match self {
+            Side::Primary => "primary",
+            Side::Shadow => "shadow",
+        }
+    }
+
+    /// The stages this side is expected to reach.
+    fn stages(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Side::Primary => &STAGES[..STAGES.len() - 1],
+            Side::Shadow => STAGES,
+        }
+    }
+}
+
+struct Trap {
+    names: Vec<String>,
+    sources: Vec<&'static str>,
+
+    /// The order this trap first fired in relative to other traps, and on which frame.
+    first_fired: Option<(usize, u32)>,
+    count: usize,
+}
+
+struct Report {
+    side: Side,
+    traps: std::collections::BTreeMap<u32, Trap>,
+    finished_frame: Option<u32>,
+    error: Option<anyhow::Error>,
+}
+
+fn run(game: &'static (dyn game::Game + Send + Sync), side: Side, rom: &[u8], save: &[u8], max_frames: u32) -> Report {
+    let hooks = game.hooks();
+
+    let mut traps = std::collections::BTreeMap::<u32, Trap>::new();
+    if let Some(rom_offsets) = hooks.offsets().get("rom").and_then(|v| v.as_table()) {
+        for (name, addr) in rom_offsets {
+            if let Some(addr) = addr.as_integer() {
+                traps
+                    .entry(addr as u32)
+                    .or_insert_with(|| Trap {
+                        names: vec![],
+                        sources: vec![],
+                        first_fired: None,
+                        count: 0,
+                    })
+                    .names
+                    .push(name.clone());
+            }
+        }
+    }
+
+    let shadow_state = shadow::State::new(
+        (0, 0),
+        Default::default(),
+        true,
+        rand_pcg::Mcg128Xsl64::new(0),
+        battle::BattleResult::Win,
+    );
+
+    let mut installed = hooks
+        .common_traps()
+        .into_iter()
+        .map(|(addr, f)| ("common", addr, f))
+        .collect::<Vec<_>>();
+    match side {
+        Side::Primary => {
+            installed.extend(
+                hooks
+                    .primary_traps(
+                        std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0)),
+                        std::sync::Arc::new(tokio::sync::Mutex::new(None)),
+                        session::CompletionToken::new(std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false))),
+                    )
+                    .into_iter()
+                    .map(|(addr, f)| ("primary", addr, f)),
+            );
+        }
+        Side::Shadow => {
+            installed.extend(
+                hooks
+                    .shadow_traps(shadow_state.clone())
+                    .into_iter()
+                    .map(|(addr, f)| ("shadow", addr, f)),
+            );
+        }
+    }
+    for (source, addr, _) in installed.iter() {
+        traps
+            .entry(*addr)
+            .or_insert_with(|| Trap {
+                names: vec![],
+                sources: vec![],
+                first_fired: None,
+                count: 0,
+            })
+            .sources
+            .push(*source);
+    }
+
+    // The primary side never gets a match, so it is done once the last stage it can reach has fired.
+    let last_stage_addr = side.stages().last().and_then(|(_, offset_name)| {
+        traps
+            .iter()
+            .find(|(_, trap)| trap.names.iter().any(|name| name == offset_name))
+            .map(|(addr, _)| *addr)
+    });
+
+    let fired = std::rc::Rc::new(std::cell::RefCell::new(Vec::<(u32, u32)>::new()));
+    let frame = std::rc::Rc::new(std::cell::Cell::new(0u32));
+
+    let mut report = Report {
+        side,
+        traps,
+        finished_frame: None,
+        error: None,
+    };
+
+    let result = (|| -> Result<(), anyhow::Error> {
+        let mut core = mgba::core::Core::new_gba("tango")?;
+        core.as_mut().load_rom(mgba::vfile::VFile::open_memory(rom))?;
+        core.as_mut().load_save(mgba::vfile::VFile::open_memory(save))?;
+        hooks.patch(core.as_mut());
+
+        core.set_traps(
+            installed
+                .into_iter()
+                .map(|(_, addr, f)| {
+                    let fired = fired.clone();
+                    let frame = frame.clone();
+                    (
+                        addr,
+                        Box::new(move |core: mgba::core::CoreMutRef<'_>| {
+                            fired.borrow_mut().push((addr, frame.get()));
+                            f(core);
+                        }) as Box<dyn Fn(mgba::core::CoreMutRef<'_>)>,
+                    )
+                })
+                .collect(),
+        );
+        core.as_mut().reset();
+
+        while frame.get() < max_frames {
+            core.as_mut().run_frame();
+            frame.set(frame.get() + 1);
+
+            if let Some(err) = shadow_state.take_error() {
+                return Err(err);
+            }
+
+            let finished = match side {
+                Side::Primary => fired.borrow().iter().any(|(addr, _)| Some(*addr) == last_stage_addr),
+                Side::Shadow => shadow_state
+                    .lock_round_state()
+                    .round
+                    .as_ref()
+                    .map(|round| round.has_first_committed_state())
+                    .unwrap_or(false),
+            };
+            if finished {
+                report.finished_frame = Some(frame.get());
+                break;
+            }
+        }
+        Ok(())
+    })();
+    report.error = result.err();
+
+    for (i, (addr, frame)) in fired.borrow().iter().enumerate() {
+        if let Some(trap) = report.traps.get_mut(addr) {
+            trap.count += 1;
+            if trap.first_fired.is_none() {
+                trap.first_fired = Some((i, *frame));
+            }
+        }
+    }
+
+    report
+}
+
+/// Checks that every stage the report's side should reach was reached, in order. Returns the problems found.
+fn check_stages(report: &Report) -> Vec<String> {
+    let mut problems = vec![];
+    let mut last: Option<(&str, usize)> = None;
+    for (stage, offset_name) in report.side.stages() {
+        let first_fired = report
+            .traps
+            .values()
+            .find(|trap| trap.names.iter().any(|name| name == offset_name))
+            .and_then(|trap| trap.first_fired);
+        let (order, _) = match first_fired {
+            Some(first_fired) => first_fired,
+            None => {
+                problems.push(format!("{} ({}) was not reached", stage, offset_name));
+                continue;
+            }
+        };
+        if let Some((last_stage, last_order)) = last {
+            if order < last_order {
+                problems.push(format!("{} was reached before {}", stage, last_stage));
+            }
+        }
+        last = Some((stage, order));
+    }
+    if report.side == Side::Shadow && report.finished_frame.is_none() {
+        problems.push("no battle state was committed".to_string());
+    }
+    problems
+}
+
+fn print_report(report: &Report) {
+    let mut traps = report.traps.iter().collect::<Vec<_>>();
+    traps.sort_by_key(|(addr, trap)| (trap.first_fired.map(|(i, _)| i).unwrap_or(usize::MAX), **addr));
+    for (addr, trap) in traps {
+        let name = if trap.names.is_empty() {
+            "(unnamed)".to_string()
+        } else {
+            trap.names.join(", ")
+        };
+        let sources = if trap.sources.is_empty() {
+            "not trapped".to_string()
+        } else {
+            trap.sources.join("+")
+        };
+        if let Some((_, frame)) = trap.first_fired {
+            eprintln!(
+                "    {:08x} {} [{}]: fired {} times, first on frame {}",
+                addr, name, sources, trap.count, frame
+            );
+        } else {
+            eprintln!("    {:08x} {} [{}]: not fired", addr, name, sources);
+        }
+    }
+}
+
+fn cmd_check_hooks(
+    config: config::Config,
+    roms_path: Option<std::path::PathBuf>,
+    saves_path: Option<std::path::PathBuf>,
+    max_frames: u32,
+) -> Result<(), anyhow::Error> {
+    let roms = game::scan_roms(&roms_path.unwrap_or_else(|| config.roms_path()));
+    let saves = save::scan_saves(&saves_path.unwrap_or_else(|| config.saves_path()));
+
+    let mut games = roms.keys().copied().collect::<Vec<_>>();
+    games.sort_by_key(|game| game.family_and_variant());
+
+    let mut failed = 0;
+    for game in games {
+        let (rom_code, revision) = game.rom_code_and_revision();
+        eprintln!(
+            "{:?} ({}_{:02}):",
+            game.family_and_variant(),
+            String::from_utf8_lossy(rom_code),
+            revision
+        );
+
+        let scanned_save = match saves.get(&game).and_then(|saves| saves.first()) {
+            Some(scanned_save) => scanned_save,
+            None => {
+                eprintln!("  skipped: no save found");
+                continue;
+            }
+        };
+        eprintln!("  using save {}", scanned_save.path.display());
+
+        let save = scanned_save.save.to_vec();
+        let mut game_failed = false;
+        for side in [Side::Primary, Side::Shadow] {
+            eprintln!("  {}:", side.name());
+            let report = run(game, side, &roms[&game], &save, max_frames);
+            print_report(&report);
+
+            let mut problems = check_stages(&report);
+            if let Some(err) = report.error.as_ref() {
+                problems.push(format!("error: {:?}", err));
+            }
+            if problems.is_empty() {
+                eprintln!("    ok: finished on frame {}", report.finished_frame.unwrap());
+            } else {
+                for problem in problems {
+                    eprintln!("    FAILED: {}", problem);
+                }
+                game_failed = true;
+            }
+        }
+        if game_failed {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} game(s) failed", failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(side: Side, fired: &[(&str, usize)], finished_frame: Option<u32>) -> Report {
+        let mut traps = std::collections::BTreeMap::new();
+        for (i, (_, offset_name)) in STAGES.iter().enumerate() {
+            traps.insert(
+                i as u32,
+                Trap {
+                    names: vec![offset_name.to_string()],
+                    sources: vec!["common"],
+                    first_fired: fired
+                        .iter()
+                        .find(|(name, _)| name == offset_name)
+                        .map(|(_, order)| (*order, *order as u32)),
+                    count: 1,
+                },
+            );
+        }
+        Report {
+            side,
+            traps,
+            finished_frame,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_check_stages_ok() {
+        let fired = STAGES
+            .iter()
+            .enumerate()
+            .map(|(i, (_, offset_name))| (*offset_name, i))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            check_stages(&report(Side::Shadow, &fired, Some(10))),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_check_stages_primary_stops_at_comm_menu() {
+        let fired = STAGES[..STAGES.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(i, (_, offset_name))| (*offset_name, i))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            check_stages(&report(Side::Primary, &fired, Some(10))),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check_stages(&report(Side::Shadow, &fired, None)),
+            vec![
+                "battle start (round_start_ret) was not reached".to_string(),
+                "no battle state was committed".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_stages_out_of_order() {
+        let fired = [
+            ("start_screen_jump_table_entry", 0),
+            ("start_screen_sram_unmask_ret", 2),
+            ("game_load_ret", 1),
+            ("comm_menu_init_ret", 3),
+        ];
+        assert_eq!(
+            check_stages(&report(Side::Primary, &fired, Some(10))),
+            vec!["game load was reached before title".to_string()]
+        );
+    }
+}
@@ -7,23 +7,29 @@ extern crate lazy_static;
 
 mod audio;
 mod battle;
+mod cheats;
 mod config;
 mod discord;
 mod filesync;
 mod game;
 mod graphics;
 mod gui;
+mod hooktool;
 mod i18n;
 mod input;
 mod lockstep;
 mod net;
+mod offsettool;
 mod patch;
+mod patchtool;
 mod randomcode;
 mod replay;
 mod replayer;
 mod replaytool;
 mod rom;
+mod romtool;
 mod save;
+mod savetool;
 mod scanner;
 mod session;
 mod shadow;
@@ -37,12 +43,33 @@ use fluent_templates::Loader;
 
 const TANGO_CHILD_ENV_VAR: &str = "TANGO_CHILD";
 
+#[derive(clap::Subcommand)]
+enum Command {
+    #[command(flatten)]
+    Replay(replaytool::Command),
+
+    #[command(flatten)]
+    Save(savetool::Command),
+
+    #[command(flatten)]
+    Rom(romtool::Command),
+
+    #[command(flatten)]
+    Patch(patchtool::Command),
+
+    #[command(flatten)]
+    Offset(offsettool::Command),
+
+    #[command(flatten)]
+    Hook(hooktool::Command),
+}
+
 #[derive(clap::Parser)]
 struct Args {
     replay_path: Option<std::path::PathBuf>,
 
     #[command(subcommand)]
-    replaytool_command: Option<replaytool::Command>,
+    command: Option<Command>,
 }
 
 enum UserEvent {
@@ -55,9 +82,35 @@ fn main() -> Result<(), anyhow::Error> {
     let config = config::Config::load_or_create()?;
     config.ensure_dirs()?;
 
+    let definition_errors = game::load_definitions(&config.games_path());
+
     let args = Args::parse();
-    if let (Some(path), Some(command)) = (args.replay_path, args.replaytool_command) {
-        return replaytool::main(config, path, command);
+    if args.command.is_some() {
+        // Command line tools run without a logger.
+        for (path, e) in definition_errors.iter() {
+            eprintln!("failed to load game definition {}: {:?}", path.display(), e);
+        }
+    }
+    match (args.replay_path, args.command) {
+        (Some(path), Some(Command::Replay(command))) => {
+            return replaytool::main(config, path, command);
+        }
+        (_, Some(Command::Save(command))) => {
+            return savetool::main(config, command);
+        }
+        (_, Some(Command::Rom(command))) => {
+            return romtool::main(config, command);
+        }
+        (_, Some(Command::Patch(command))) => {
+            return patchtool::main(config, command);
+        }
+        (_, Some(Command::Offset(command))) => {
+            return offsettool::main(command);
+        }
+        (_, Some(Command::Hook(command))) => {
+            return hooktool::main(config, command);
+        }
+        _ => {}
     }
 
     env_logger::Builder::from_default_env()
@@ -135,6 +188,10 @@ fn main() -> Result<(), anyhow::Error> {
 }
 
 fn child_main(mut config: config::Config) -> Result<(), anyhow::Error> {
+    for (path, e) in game::definition_errors() {
+        log::error!("failed to load game definition {}: {}", path.display(), e);
+    }
+
     let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
     let _enter_guard = rt.enter();
 
@@ -117,6 +117,11 @@ impl Sender {
         }))
         .await
     }
+
+    pub async fn send_loser_side(&mut self, game: u8, side: u8) -> std::io::Result<()> {
+        self.send_packet(&protocol::Packet::LoserSide(protocol::LoserSide { game, side }))
+            .await
+    }
 }
 
 pub struct Receiver {
@@ -1,6 +1,6 @@
 use bincode::Options;
 
-pub const VERSION: u8 = 0x30;
+pub const VERSION: u8 = 0x34;
 
 lazy_static! {
     static ref BINCODE_OPTIONS: bincode::config::WithOtherLimit<
@@ -31,6 +31,7 @@ pub enum Packet {
 
     // In match.
     Input(Input),
+    LoserSide(LoserSide),
 }
 
 impl Packet {
@@ -75,6 +76,20 @@ pub struct Pong {
 pub struct PatchInfo {
     pub name: String,
     pub version: semver::Version,
+
+    /// Patches this patch is applied on top of, starting from the bottom-most one.
+    pub bases: Vec<(String, semver::Version)>,
+}
+
+impl PatchInfo {
+    /// Returns every patch that has to be applied, in order, ending with this one.
+    pub fn chain(&self) -> Vec<(String, semver::Version)> {
+        self.bases
+            .iter()
+            .cloned()
+            .chain(std::iter::once((self.name.clone(), self.version.clone())))
+            .collect()
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
@@ -83,10 +98,21 @@ pub struct GameInfo {
     pub patch: Option<PatchInfo>,
 }
 
+/// Battle settings (stage/terrain) and background to use instead of random ones. Each is an index into the choices the
+/// game's hooks offer for the match type, see `game::Hooks::num_stage_choices`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StageSelection {
+    pub battle_settings: Option<u16>,
+    pub background: Option<u16>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
 pub struct Settings {
     pub nickname: String,
     pub match_type: (u8, u8),
+    pub stage: StageSelection,
+    /// Number of games in the set. 0 and 1 both mean a single game.
+    pub best_of: u8,
     pub game_info: Option<GameInfo>,
     pub available_games: Vec<(String, u8)>,
     pub available_patches: Vec<(String, Vec<semver::Version>)>,
@@ -101,6 +127,14 @@ pub struct Input {
     pub joyflags: u16,
 }
 
+/// Sent by the loser of a game in a set to pick the side (0 for P1, 1 for P2) they play on in the next game.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct LoserSide {
+    /// The game that was just lost.
+    pub game: u8,
+    pub side: u8,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct StartMatch {}
 
@@ -0,0 +1,480 @@
+use crate::game;
+
+const ROM_START: u32 = 0x08000000;
+
+/// Bytes taken before and after an address to build signatures from, from most to least specific.
+const WINDOWS: &[usize] = &[64, 32, 16];
+
+/// Signatures with fewer unmasked bytes than this match too much to be useful.
+const MIN_UNMASKED: usize = 12;
+
+/// Maximum number of literal pool references to an EWRAM address to look up in the target ROM.
+const MAX_REFERENCES: usize = 8;
+
+/// How far below an unreferenced EWRAM address to look for a referenced one to infer its shift from.
+const MAX_SHIFT_DISTANCE: u32 = 0x1000;
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Propose hook offsets for a target ROM by scanning it for signatures built around the offsets of a supported
+    /// reference ROM. The proposed offsets are printed in the format of a game definition's [offsets] tables.
+    FindOffsets {
+        /// Path to a ROM of a supported game, e.g. the US release of the same game.
+        reference_rom_path: std::path::PathBuf,
+
+        /// Path to the ROM to find offsets for.
+        target_rom_path: std::path::PathBuf,
+
+        /// Path to write the proposed offsets to. Defaults to stdout.
+        #[arg(long)]
+        output_path: Option<std::path::PathBuf>,
+    },
+}
+
+pub fn main(command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::FindOffsets {
+            reference_rom_path,
+            target_rom_path,
+            output_path,
+        } => cmd_find_offsets(reference_rom_path, target_rom_path, output_path),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Confidence {
+    /// The widest signature matched exactly once.
+    High,
+    /// Only a narrower signature matched exactly once, or all literal references agreed but there was only one.
+    Medium,
+    /// Only the narrowest signature matched, literal references disagreed, or the address was inferred from a nearby
+    /// one.
+    Low,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Finding {
+    Found(u32, Confidence),
+    Ambiguous(Vec<u32>),
+    NotFound(&'static str),
+}
+
+struct Signature {
+    start: usize,
+    bytes: Vec<u8>,
+    mask: Vec<bool>,
+}
+
+fn looks_like_pointer(word: u32) -> bool {
+    matches!(word >> 24, 0x02 | 0x03 | 0x08 | 0x09)
+}
+
+impl Signature {
+    /// Builds a signature from rom[start..end], masking out bytes that are likely to differ between revisions even if
+    /// the surrounding code is the same: pointers in literal pools and Thumb BL targets.
+    fn new(rom: &[u8], start: usize, end: usize) -> Self {
+        let bytes = rom[start..end].to_vec();
+        let mut mask = vec![true; bytes.len()];
+
+        for offset in (start..end).filter(|offset| offset % 4 == 0) {
+            if offset + 4 > end {
+                break;
+            }
+            let word = u32::from_le_bytes(rom[offset..offset + 4].try_into().unwrap());
+            if looks_like_pointer(word) {
+                mask[offset - start..offset - start + 4].fill(false);
+            }
+        }
+
+        for offset in (start..end).filter(|offset| offset % 2 == 0) {
+            if offset + 4 > end {
+                break;
+            }
+            let hi = u16::from_le_bytes(rom[offset..offset + 2].try_into().unwrap());
+            let lo = u16::from_le_bytes(rom[offset + 2..offset + 4].try_into().unwrap());
+            if hi & 0xf800 == 0xf000 && (lo & 0xf800 == 0xf800 || lo & 0xf800 == 0xe800) {
+                mask[offset - start..offset - start + 4].fill(false);
+            }
+        }
+
+        Self { start, bytes, mask }
+    }
+
+    fn around(rom: &[u8], offset: usize, window: usize) -> Self {
+        Self::new(
+            rom,
+            offset.saturating_sub(window),
+            std::cmp::min(offset + window, rom.len()),
+        )
+    }
+
+    fn unmasked(&self) -> usize {
+        self.mask.iter().filter(|m| **m).count()
+    }
+
+    /// Finds where this signature matches in the haystack, keeping the alignment of the signature in the reference
+    /// ROM. Returns the offsets in the haystack corresponding to the start of the signature.
+    fn find(&self, haystack: &[u8], align: usize) -> Vec<usize> {
+        // Search for the longest unmasked run first, then check the rest of the signature.
+        let (mut anchor_start, mut anchor_len) = (0, 0);
+        let mut run_start = 0;
+        for i in 0..=self.mask.len() {
+            if i < self.mask.len() && self.mask[i] {
+                continue;
+            }
+            if i - run_start > anchor_len {
+                anchor_start = run_start;
+                anchor_len = i - run_start;
+            }
+            run_start = i + 1;
+        }
+        if anchor_len == 0 {
+            return vec![];
+        }
+        let anchor = &self.bytes[anchor_start..anchor_start + anchor_len];
+
+        let mut found = vec![];
+        for i in anchor_start..(haystack.len() + anchor_start + 1).saturating_sub(self.bytes.len()) {
+            if haystack[i] != anchor[0] || &haystack[i..i + anchor_len] != anchor {
+                continue;
+            }
+            let start = i - anchor_start;
+            if start % align != self.start % align {
+                continue;
+            }
+            if self
+                .bytes
+                .iter()
+                .zip(self.mask.iter())
+                .zip(haystack[start..start + self.bytes.len()].iter())
+                .all(|((b, m), h)| !*m || b == h)
+            {
+                found.push(start);
+            }
+        }
+        found
+    }
+}
+
+/// Finds a ROM address (e.g. a trap address) by matching the code around it.
+fn find_rom_address(reference: &[u8], target: &[u8], addr: u32) -> Finding {
+    let offset = match addr.checked_sub(ROM_START) {
+        Some(offset) if (offset as usize) < reference.len() => offset as usize,
+        _ => {
+            return Finding::NotFound("not a ROM address");
+        }
+    };
+
+    for (i, window) in WINDOWS.iter().enumerate() {
+        let signature = Signature::around(reference, offset, *window);
+        if signature.unmasked() < MIN_UNMASKED {
+            continue;
+        }
+        let found = signature.find(target, 2);
+        let delta = offset - signature.start;
+        match found.len() {
+            0 => {}
+            1 => {
+                return Finding::Found(
+                    (found[0] + delta) as u32 + ROM_START,
+                    match i {
+                        0 => Confidence::High,
+                        1 => Confidence::Medium,
+                        _ => Confidence::Low,
+                    },
+                );
+            }
+            _ => {
+                return Finding::Ambiguous(found.into_iter().map(|f| (f + delta) as u32 + ROM_START).collect());
+            }
+        }
+    }
+
+    Finding::NotFound("no signature matched")
+}
+
+/// Finds an address referenced from literal pools (e.g. an EWRAM address) by matching the code and data around each
+/// reference to it and reading the corresponding literal in the target ROM.
+fn find_literal(reference: &[u8], target: &[u8], addr: u32) -> Finding {
+    let references = reference
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(_, word)| u32::from_le_bytes((*word).try_into().unwrap()) == addr)
+        .map(|(i, _)| i * 4)
+        .take(MAX_REFERENCES)
+        .collect::<Vec<_>>();
+    if references.is_empty() {
+        return Finding::NotFound("not referenced from any literal pool");
+    }
+
+    let mut votes = std::collections::BTreeMap::<u32, usize>::new();
+    for offset in references {
+        for window in WINDOWS {
+            let signature = Signature::around(reference, offset, *window);
+            if signature.unmasked() < MIN_UNMASKED {
+                continue;
+            }
+            let found = signature.find(target, 4);
+            if found.len() != 1 {
+                continue;
+            }
+            let literal_offset = found[0] + (offset - signature.start);
+            let literal = match target.get(literal_offset..literal_offset + 4) {
+                Some(literal) => u32::from_le_bytes(literal.try_into().unwrap()),
+                None => {
+                    continue;
+                }
+            };
+            *votes.entry(literal).or_default() += 1;
+            break;
+        }
+    }
+
+    let total = votes.values().sum::<usize>();
+    let (value, count) = match votes.iter().max_by_key(|(_, count)| **count) {
+        Some((value, count)) => (*value, *count),
+        None => {
+            return Finding::NotFound("no reference matched");
+        }
+    };
+    if votes.len() > 1 && count * 2 <= total {
+        return Finding::Ambiguous(votes.into_keys().collect());
+    }
+    Finding::Found(
+        value,
+        if votes.len() > 1 {
+            Confidence::Low
+        } else if count > 1 {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        },
+    )
+}
+
+/// Finds an address that isn't referenced directly (e.g. a field of a struct) by finding the closest referenced
+/// address below it and assuming both moved by the same amount.
+fn find_shifted(reference: &[u8], target: &[u8], addr: u32) -> Finding {
+    let base = match reference
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .filter(|word| *word <= addr && addr - *word <= MAX_SHIFT_DISTANCE)
+        .max()
+    {
+        Some(base) => base,
+        None => {
+            return Finding::NotFound("no nearby address is referenced from any literal pool");
+        }
+    };
+
+    match find_literal(reference, target, base) {
+        Finding::Found(target_base, _) => Finding::Found(target_base.wrapping_add(addr - base), Confidence::Low),
+        Finding::Ambiguous(_) | Finding::NotFound(_) => Finding::NotFound("nearby address was not found"),
+    }
+}
+
+fn find_ewram_address(reference: &[u8], target: &[u8], addr: u32) -> Finding {
+    match find_literal(reference, target, addr) {
+        Finding::NotFound(_) => find_shifted(reference, target, addr),
+        finding => finding,
+    }
+}
+
+fn cmd_find_offsets(
+    reference_rom_path: std::path::PathBuf,
+    target_rom_path: std::path::PathBuf,
+    output_path: Option<std::path::PathBuf>,
+) -> Result<(), anyhow::Error> {
+    let reference = std::fs::read(&reference_rom_path)?;
+    let target = std::fs::read(&target_rom_path)?;
+
+    let game = game::detect(&reference)?;
+    eprintln!("reference rom is {:?}", game.family_and_variant());
+
+    let offsets = game.hooks().offsets();
+
+    let mut output = String::new();
+    let (mut found, mut unresolved) = (0, 0);
+    for (section, find) in [
+        ("rom", find_rom_address as fn(&[u8], &[u8], u32) -> Finding),
+        ("ewram", find_ewram_address),
+    ] {
+        let table = offsets
+            .get(section)
+            .and_then(|v| v.as_table())
+            .ok_or_else(|| anyhow::anyhow!("offsets have no {} table", section))?;
+
+        output.push_str(&format!("[offsets.{}]\n", section));
+        for (name, value) in table {
+            let addr = value
+                .as_integer()
+                .ok_or_else(|| anyhow::anyhow!("{}.{} is not an integer", section, name))?
+                as u32;
+            match find(&reference, &target, addr) {
+                Finding::Found(target_addr, confidence) => {
+                    eprintln!(
+                        "{}.{}: {:08x} -> {:08x} ({:?} confidence)",
+                        section, name, addr, target_addr, confidence
+                    );
+                    output.push_str(&format!("{} = 0x{:08x}\n", name, target_addr));
+                    found += 1;
+                }
+                Finding::Ambiguous(candidates) => {
+                    eprintln!(
+                        "{}.{}: {:08x} is ambiguous, candidates: {}",
+                        section,
+                        name,
+                        addr,
+                        candidates
+                            .iter()
+                            .map(|c| format!("{:08x}", c))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    output.push_str(&format!("# {} = ???\n", name));
+                    unresolved += 1;
+                }
+                Finding::NotFound(reason) => {
+                    eprintln!("{}.{}: {:08x} not found: {}", section, name, addr, reason);
+                    output.push_str(&format!("# {} = ???\n", name));
+                    unresolved += 1;
+                }
+            }
+        }
+        output.push('\n');
+    }
+
+    eprintln!("found {} offsets, {} need to be filled in by hand", found, unresolved);
+
+    if let Some(output_path) = output_path {
+        std::fs::write(&output_path, output)?;
+    } else {
+        print!("{}", output);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFERENCED: u32 = 0x02001234;
+
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(seed);
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    fn write_u32(rom: &mut [u8], offset: usize, value: u32) {
+        rom[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// A reference ROM with REFERENCED in the literal pools at the given offsets, and a target ROM with the same code
+    /// moved by 0x10 bytes and the given values in those literal pools instead.
+    fn roms(references: &[(usize, u32)]) -> (Vec<u8>, Vec<u8>) {
+        let mut reference = noise(1, 0x400);
+        let mut target = noise(2, 0x10);
+        target.extend_from_slice(&reference);
+        for (offset, value) in references {
+            write_u32(&mut reference, *offset, REFERENCED);
+            write_u32(&mut target, offset + 0x10, *value);
+        }
+        (reference, target)
+    }
+
+    fn signature_rom() -> Vec<u8> {
+        let mut rom = (0x10..0x20).collect::<Vec<u8>>();
+        // A pointer in a literal pool.
+        write_u32(&mut rom, 4, 0x08001234);
+        // A Thumb BL.
+        rom[10..14].copy_from_slice(&[0x00, 0xf0, 0x00, 0xf8]);
+        rom
+    }
+
+    #[test]
+    fn test_signature_new_masks_pointers_and_branches() {
+        let signature = Signature::new(&signature_rom(), 0, 16);
+        assert_eq!(
+            signature.mask,
+            vec![
+                true, true, true, true, false, false, false, false, true, true, false, false, false, false, true, true
+            ]
+        );
+        assert_eq!(signature.unmasked(), 8);
+    }
+
+    #[test]
+    fn test_signature_find() {
+        let rom = signature_rom();
+        let signature = Signature::new(&rom, 0, 16);
+
+        let mut moved = rom.clone();
+        write_u32(&mut moved, 4, 0x08005678);
+        moved[10..14].copy_from_slice(&[0x01, 0xf0, 0x02, 0xf8]);
+        let mut haystack = vec![0xaa; 6];
+        haystack.extend_from_slice(&moved);
+        haystack.extend_from_slice(&[0xbb; 4]);
+
+        // Masked bytes may differ.
+        assert_eq!(signature.find(&haystack, 2), vec![6]);
+        // The signature started word aligned in the reference, so it must be word aligned in the target too.
+        assert_eq!(signature.find(&haystack, 4), Vec::<usize>::new());
+
+        haystack[6 + 8] ^= 0xff;
+        assert_eq!(signature.find(&haystack, 2), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_signature_find_at_end_of_haystack() {
+        let rom = signature_rom();
+        let signature = Signature::new(&rom, 0, 16);
+        assert_eq!(signature.find(&rom, 2), vec![0]);
+        assert_eq!(signature.find(&rom[..15], 2), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_find_literal_agreeing_references() {
+        let (reference, target) = roms(&[(0x100, 0x02005678), (0x300, 0x02005678)]);
+        assert_eq!(
+            find_literal(&reference, &target, REFERENCED),
+            Finding::Found(0x02005678, Confidence::High)
+        );
+    }
+
+    #[test]
+    fn test_find_literal_single_reference() {
+        let (reference, target) = roms(&[(0x100, 0x02005678)]);
+        assert_eq!(
+            find_literal(&reference, &target, REFERENCED),
+            Finding::Found(0x02005678, Confidence::Medium)
+        );
+    }
+
+    #[test]
+    fn test_find_literal_majority() {
+        let (reference, target) = roms(&[(0x100, 0x02005678), (0x200, 0x02009999), (0x300, 0x02005678)]);
+        assert_eq!(
+            find_literal(&reference, &target, REFERENCED),
+            Finding::Found(0x02005678, Confidence::Low)
+        );
+    }
+
+    #[test]
+    fn test_find_literal_tie_is_ambiguous() {
+        let (reference, target) = roms(&[(0x100, 0x02005678), (0x300, 0x02009999)]);
+        assert_eq!(
+            find_literal(&reference, &target, REFERENCED),
+            Finding::Ambiguous(vec![0x02005678, 0x02009999])
+        );
+    }
+
+    #[test]
+    fn test_find_literal_not_referenced() {
+        let (reference, target) = roms(&[]);
+        assert!(matches!(
+            find_literal(&reference, &target, REFERENCED),
+            Finding::NotFound(_)
+        ));
+    }
+}
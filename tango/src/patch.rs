@@ -1,4 +1,6 @@
 pub mod bps;
+pub mod ips;
+pub mod ups;
 
 use futures::StreamExt;
 use itertools::Itertools;
@@ -25,6 +27,13 @@ struct VersionMetadata {
     #[serde(default)]
     pub rom_overrides: rom::Overrides,
     pub netplay_compatibility: String,
+    pub base: Option<BaseMetadata>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct BaseMetadata {
+    pub name: String,
+    pub version: semver::VersionReq,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +41,10 @@ pub struct Version {
     pub rom_overrides: rom::Overrides,
     pub netplay_compatibility: String,
     pub supported_games: std::collections::HashSet<&'static (dyn game::Game + Send + Sync)>,
+
+    /// The patch and version this version is applied on top of, if any. Resolved to the newest installed version
+    /// that satisfies the version requirement in info.toml and whose own bases resolve.
+    pub base: Option<(String, semver::Version)>,
 }
 
 #[derive(Debug)]
@@ -46,29 +59,127 @@ pub struct Patch {
 }
 
 lazy_static! {
-    static ref PATCH_FILENAME_REGEX: regex::Regex = regex::Regex::new(r"^(\S{4})_(\d{2}).bps$").unwrap();
+    static ref PATCH_FILENAME_REGEX: regex::Regex = regex::Regex::new(r"^(\S{4})_(\d{2})\.(bps|ups|ips)$").unwrap();
+}
+
+/// Where patches are synced from. Besides HTTP(S) repositories, a repository can be a local directory or a zip archive
+/// with the same layout, for when there is no internet connection.
+enum Source {
+    Http(String),
+    Directory(std::path::PathBuf),
+    Zip(std::path::PathBuf),
+}
+
+impl Source {
+    fn parse(repo: &str) -> Result<Self, anyhow::Error> {
+        if repo.starts_with("http://") || repo.starts_with("https://") {
+            return Ok(Source::Http(repo.to_string()));
+        }
+
+        let path = match url::Url::parse(repo) {
+            Ok(url) if url.scheme() == "file" => url
+                .to_file_path()
+                .map_err(|_| anyhow::anyhow!("invalid file url: {}", repo))?,
+            _ => std::path::PathBuf::from(repo),
+        };
+
+        let metadata = std::fs::metadata(&path)?;
+        Ok(if metadata.is_dir() {
+            Source::Directory(path)
+        } else {
+            Source::Zip(path)
+        })
+    }
+}
+
+/// Which publishers a repository's index must be signed by. The signature is a raw ed25519 signature of index.json,
+/// stored next to it as index.json.sig.
+#[derive(Clone)]
+pub struct IndexPolicy {
+    pub trusted_keys: Vec<[u8; 32]>,
+    pub require_signature: bool,
+}
+
+impl IndexPolicy {
+    pub fn from_config(config: &config::Config, repo: &str) -> Self {
+        Self {
+            trusted_keys: config.trusted_patch_publisher_keys.iter().map(|key| key.0).collect(),
+            require_signature: config.signed_patch_repos.iter().any(|r| r == repo),
+        }
+    }
+
+    fn parse_index(&self, raw_index: &[u8], signature: Option<&[u8]>) -> Result<filesync::Entries, anyhow::Error> {
+        match signature {
+            Some(signature) if !self.trusted_keys.is_empty() => {
+                let signature = ed25519_dalek::Signature::from_slice(signature)?;
+                if !self.trusted_keys.iter().any(|key| {
+                    ed25519_dalek::VerifyingKey::from_bytes(key)
+                        .map(|key| key.verify_strict(raw_index, &signature).is_ok())
+                        .unwrap_or(false)
+                }) {
+                    anyhow::bail!("index signature does not match any trusted publisher key");
+                }
+            }
+            _ if self.require_signature => {
+                anyhow::bail!("index is not signed by a trusted publisher key");
+            }
+            _ => {}
+        }
+        Ok(serde_json::from_slice(raw_index)?)
+    }
 }
 
-pub async fn update(url: &String, root: &std::path::Path) -> Result<(), anyhow::Error> {
+pub async fn update(url: &String, root: &std::path::Path, policy: &IndexPolicy) -> Result<(), anyhow::Error> {
     std::fs::create_dir_all(root)?;
 
+    match Source::parse(url)? {
+        Source::Http(url) => update_from_http(&url, root, policy).await,
+        Source::Directory(path) => update_from_directory(&path, root, policy).await,
+        Source::Zip(path) => update_from_zip(&path, root, policy).await,
+    }
+}
+
+async fn update_from_http(url: &String, root: &std::path::Path, policy: &IndexPolicy) -> Result<(), anyhow::Error> {
     let client = reqwest::Client::new();
-    let entries = tokio::time::timeout(
+    let (raw_index, signature) = tokio::time::timeout(
         // 30 second timeout to fetch JSON.
         std::time::Duration::from_secs(30),
         (|| async {
-            Ok::<_, anyhow::Error>(
+            let raw_index = client
+                .get(format!("{}/index.json", url))
+                .header("User-Agent", "tango")
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            // Only a signature that was actually fetched counts: anything else is treated as unsigned, which the policy
+            // rejects if the repository requires signatures.
+            let signature = match async {
                 client
-                    .get(format!("{}/index.json", url))
+                    .get(format!("{}/index.json.sig", url))
                     .header("User-Agent", "tango")
                     .send()
                     .await?
-                    .json::<filesync::Entries>()
-                    .await?,
-            )
+                    .error_for_status()?
+                    .bytes()
+                    .await
+            }
+            .await
+            {
+                Ok(signature) => Some(signature),
+                Err(e) => {
+                    if e.status() != Some(reqwest::StatusCode::NOT_FOUND) {
+                        log::warn!("failed to fetch index signature: {:?}", e);
+                    }
+                    None
+                }
+            };
+            Ok::<_, anyhow::Error>((raw_index, signature))
         })(),
     )
     .await??;
+    let entries = policy.parse_index(&raw_index, signature.as_deref())?;
 
     let root = root.to_path_buf();
     filesync::sync(
@@ -119,8 +230,107 @@ pub async fn update(url: &String, root: &std::path::Path) -> Result<(), anyhow::
     Ok(())
 }
 
+async fn update_from_directory(
+    path: &std::path::Path,
+    root: &std::path::Path,
+    policy: &IndexPolicy,
+) -> Result<(), anyhow::Error> {
+    let raw_index = tokio::fs::read(path.join("index.json")).await?;
+    let signature = match tokio::fs::read(path.join("index.json.sig")).await {
+        Ok(signature) => Some(signature),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("failed to read index signature: {:?}", e);
+            }
+            None
+        }
+    };
+    let entries = policy.parse_index(&raw_index, signature.as_deref())?;
+
+    let root = root.to_path_buf();
+    filesync::sync(
+        &root,
+        &entries,
+        {
+            let source = path.to_path_buf();
+            let root = root.clone();
+            move |path| {
+                let source = source.clone();
+                let root = root.clone();
+                Box::pin(async move {
+                    tokio::fs::copy(source.join(path), root.join(path)).await?;
+                    log::info!("filesynced: {}", path.display());
+                    Ok(())
+                })
+            }
+        },
+        4,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn update_from_zip(
+    path: &std::path::Path,
+    root: &std::path::Path,
+    policy: &IndexPolicy,
+) -> Result<(), anyhow::Error> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut raw_index = vec![];
+    std::io::Read::read_to_end(&mut archive.by_name("index.json")?, &mut raw_index)?;
+    let signature = match archive
+        .by_name("index.json.sig")
+        .map_err(anyhow::Error::from)
+        .and_then(|mut f| {
+            let mut signature = vec![];
+            std::io::Read::read_to_end(&mut f, &mut signature)?;
+            Ok(signature)
+        }) {
+        Ok(signature) => Some(signature),
+        Err(e) => {
+            if !matches!(e.downcast_ref(), Some(zip::result::ZipError::FileNotFound)) {
+                log::warn!("failed to read index signature: {:?}", e);
+            }
+            None
+        }
+    };
+    let entries = policy.parse_index(&raw_index, signature.as_deref())?;
+    let archive = std::sync::Arc::new(parking_lot::Mutex::new(archive));
+
+    let root = root.to_path_buf();
+    filesync::sync(
+        &root,
+        &entries,
+        {
+            let root = root.clone();
+            move |path| {
+                let archive = archive.clone();
+                let root = root.clone();
+                Box::pin(async move {
+                    let mut buf = vec![];
+                    {
+                        let mut archive = archive.lock();
+                        let mut f = archive
+                            .by_name(&path.components().map(|v| v.as_os_str().to_string_lossy()).join("/"))
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                        std::io::Read::read_to_end(&mut f, &mut buf)?;
+                    }
+                    tokio::fs::write(root.join(path), buf).await?;
+                    log::info!("filesynced: {}", path.display());
+                    Ok(())
+                })
+            }
+        },
+        // Reads from the archive are serialized anyway.
+        1,
+    )
+    .await?;
+    Ok(())
+}
+
 pub fn scan(path: &std::path::Path) -> Result<std::collections::BTreeMap<String, Patch>, std::io::Error> {
     let mut patches = std::collections::BTreeMap::new();
+    let mut base_reqs = std::collections::HashMap::new();
     for entry in std::fs::read_dir(path)? {
         let entry = match entry {
             Ok(entry) => entry,
@@ -228,12 +438,17 @@ pub fn scan(path: &std::path::Path) -> Result<std::collections::BTreeMap<String,
                 supported_games.insert(game);
             }
 
+            if let Some(base) = version.base {
+                base_reqs.insert((name.clone(), sv.clone()), base);
+            }
+
             versions.insert(
                 sv,
                 Version {
                     rom_overrides: version.rom_overrides,
                     netplay_compatibility: version.netplay_compatibility,
                     supported_games,
+                    base: None,
                 },
             );
         }
@@ -269,9 +484,112 @@ pub fn scan(path: &std::path::Path) -> Result<std::collections::BTreeMap<String,
             },
         );
     }
+
+    resolve_bases(&mut patches, base_reqs);
     Ok(patches)
 }
 
+/// Resolves the base of every stacked version to the newest installed version that satisfies its requirement and can
+/// itself be resolved, falling back to older matching versions otherwise. Versions without a usable base or which end
+/// up depending on themselves are dropped, and the supported games of the rest are narrowed to the ones their whole
+/// chain supports.
+fn resolve_bases(
+    patches: &mut std::collections::BTreeMap<String, Patch>,
+    base_reqs: std::collections::HashMap<(String, semver::Version), BaseMetadata>,
+) {
+    // Dropping a version may leave versions stacked on top of it without a base, so repeat until nothing is dropped.
+    loop {
+        let mut dropped = vec![];
+        for ((name, version), base) in base_reqs.iter() {
+            if !patches[name].versions.contains_key(version) {
+                continue;
+            }
+            let resolved = if let Some(resolved) = patches.get(&base.name).and_then(|p| {
+                p.versions
+                    .keys()
+                    .filter(|v| base.version.matches(v))
+                    .max()
+                    .map(|v| (base.name.clone(), v.clone()))
+            }) {
+                resolved
+            } else {
+                log::warn!(
+                    "{} v{}: no installed version of {} matches {}",
+                    name,
+                    version,
+                    base.name,
+                    base.version
+                );
+                dropped.push((name.clone(), version.clone()));
+                continue;
+            };
+            patches.get_mut(name).unwrap().versions.get_mut(version).unwrap().base = Some(resolved);
+        }
+
+        for (name, version) in base_reqs.keys() {
+            if !patches[name].versions.contains_key(version) || dropped.contains(&(name.clone(), version.clone())) {
+                continue;
+            }
+            if resolve_chain(patches, name, version).is_none() {
+                log::warn!("{} v{}: could not resolve base patches", name, version);
+                dropped.push((name.clone(), version.clone()));
+            }
+        }
+
+        if dropped.is_empty() {
+            break;
+        }
+
+        for (name, version) in dropped {
+            patches.get_mut(&name).unwrap().versions.remove(&version);
+        }
+    }
+
+    for (name, version) in base_reqs.keys() {
+        let chain = if let Some(chain) = resolve_chain(patches, name, version) {
+            chain
+        } else {
+            continue;
+        };
+        let supported_games = chain
+            .iter()
+            .map(|(name, version)| patches[name].versions[version].supported_games.clone())
+            .reduce(|acc, games| acc.intersection(&games).cloned().collect())
+            .unwrap_or_default();
+        patches
+            .get_mut(name)
+            .unwrap()
+            .versions
+            .get_mut(version)
+            .unwrap()
+            .supported_games = supported_games;
+    }
+}
+
+/// Returns every patch that has to be applied to get the given patch version, starting from the bottom-most base and
+/// ending with the version itself. Returns `None` if a base is missing or the chain loops back on itself.
+pub fn resolve_chain(
+    patches: &std::collections::BTreeMap<String, Patch>,
+    name: &str,
+    version: &semver::Version,
+) -> Option<Vec<(String, semver::Version)>> {
+    let mut chain = vec![(name.to_string(), version.clone())];
+    loop {
+        let (name, version) = chain.last().unwrap();
+        let base = if let Some(base) = patches.get(name)?.versions.get(version)?.base.as_ref() {
+            base
+        } else {
+            break;
+        };
+        if chain.contains(base) {
+            return None;
+        }
+        chain.push(base.clone());
+    }
+    chain.reverse();
+    Some(chain)
+}
+
 pub type Scanner = scanner::Scanner<std::collections::BTreeMap<String, Patch>>;
 
 pub struct Autoupdater {
@@ -302,22 +620,19 @@ impl Autoupdater {
             let patches_scanner = self.patches_scanner.clone();
             async move {
                 'l: loop {
-                    let (repo_url, patches_path) = {
+                    let (repo_url, patches_path, index_policy) = {
                         let config = config.read();
                         (
-                            if !config.patch_repo.is_empty() {
-                                config.patch_repo.clone()
-                            } else {
-                                config::DEFAULT_PATCH_REPO.to_owned()
-                            },
+                            config.patch_repo_url().to_owned(),
                             config.patches_path().to_path_buf(),
+                            IndexPolicy::from_config(&config, config.patch_repo_url()),
                         )
                     };
 
                     let patches_scanner = patches_scanner.clone();
                     let _ = tokio::task::spawn_blocking(move || {
                         patches_scanner.rescan(move || {
-                            if let Err(e) = sync::block_on(update(&repo_url, &patches_path)) {
+                            if let Err(e) = sync::block_on(update(&repo_url, &patches_path, &index_policy)) {
                                 log::error!("failed to update patches: {:?}", e);
                             }
                             scan(&patches_path).ok()
@@ -364,15 +679,209 @@ pub fn apply_patch_from_disk(
     }
 
     let (rom_code, revision) = game.rom_code_and_revision();
-    let raw = std::fs::read(
-        patches_path
-            .join(&patch_name)
-            .join(format!("v{}", patch_version))
-            .join(format!(
-                "{}_{:02}.bps",
-                std::str::from_utf8(rom_code).unwrap(),
-                revision
-            )),
-    )?;
-    Ok(bps::apply(rom, &raw)?)
+    let version_path = patches_path.join(&patch_name).join(format!("v{}", patch_version));
+    let stem = format!("{}_{:02}", std::str::from_utf8(rom_code).unwrap(), revision);
+
+    // If a version ships the same patch in more than one format, prefer the ones with checksums.
+    for ext in ["bps", "ups", "ips"] {
+        let raw = match std::fs::read(version_path.join(format!("{}.{}", stem, ext))) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                continue;
+            }
+            Err(e) => {
+                return Err(e.into());
+            }
+        };
+        return Ok(match ext {
+            "bps" => bps::apply(rom, &raw)?,
+            "ups" => ups::apply(rom, &raw)?,
+            "ips" => ips::apply(rom, &raw)?,
+            _ => unreachable!(),
+        });
+    }
+
+    anyhow::bail!("no patch file found for {}", stem);
+}
+
+/// Applies a chain of patches, as returned by `resolve_chain`, in order.
+pub fn apply_patch_chain_from_disk(
+    rom: &[u8],
+    game: &'static (dyn game::Game + Send + Sync),
+    patches_path: &std::path::Path,
+    chain: &[(String, semver::Version)],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let mut rom = rom.to_vec();
+    for (patch_name, patch_version) in chain {
+        rom = apply_patch_from_disk(&rom, game, patches_path, patch_name, patch_version)?;
+    }
+    Ok(rom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret_key: &[u8; 32], message: &[u8]) -> ([u8; 32], Vec<u8>) {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(secret_key);
+        let signature = ed25519_dalek::Signer::sign(&signing_key, message);
+        (signing_key.verifying_key().to_bytes(), signature.to_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_sign_rfc8032() {
+        // Test 1 from RFC 8032 section 7.1, so keys and signatures stay compatible across ed25519 library upgrades.
+        let secret_key = [
+            0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4, 0x44, 0x49,
+            0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60,
+        ];
+        let (public_key, signature) = sign(&secret_key, b"");
+        assert_eq!(
+            public_key,
+            [
+                0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64, 0x07, 0x3a, 0x0e,
+                0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68, 0xf7, 0x07, 0x51, 0x1a,
+            ]
+        );
+        assert_eq!(
+            signature,
+            vec![
+                0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e, 0x82, 0x8a, 0x84,
+                0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65, 0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8,
+                0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e, 0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5,
+                0xf0, 0x59, 0x5b, 0xbe, 0x24, 0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let raw_index = br#"{"a": {"b_00.bps": "0000000000000000000000000000000000000000000000000000000000000000"}}"#;
+        let (trusted_key, signature) = sign(&[1; 32], raw_index);
+        let (_, untrusted_signature) = sign(&[2; 32], raw_index);
+
+        for require_signature in [false, true] {
+            let policy = IndexPolicy {
+                trusted_keys: vec![trusted_key],
+                require_signature,
+            };
+            assert!(policy.parse_index(raw_index, Some(&signature)).is_ok());
+            assert!(policy.parse_index(raw_index, Some(&untrusted_signature)).is_err());
+            assert!(policy.parse_index(raw_index, Some(b"garbage")).is_err());
+            assert_eq!(policy.parse_index(raw_index, None).is_ok(), !require_signature);
+
+            let mut tampered_index = raw_index.to_vec();
+            tampered_index[2] = b'c';
+            assert!(policy.parse_index(&tampered_index, Some(&signature)).is_err());
+        }
+
+        // A signature can't be checked without trusted keys, so it doesn't satisfy a repo that requires one.
+        let policy = IndexPolicy {
+            trusted_keys: vec![],
+            require_signature: true,
+        };
+        assert!(policy.parse_index(raw_index, Some(&signature)).is_err());
+    }
+
+    /// Patch name, version, and the name and version requirement of its base.
+    type VersionSpec<'a> = (&'a str, &'a str, Option<(&'a str, &'a str)>);
+
+    fn resolve(versions: &[VersionSpec]) -> std::collections::BTreeMap<String, Patch> {
+        let mut patches = std::collections::BTreeMap::new();
+        let mut base_reqs = std::collections::HashMap::new();
+        for (name, version, base) in versions {
+            let version = semver::Version::parse(version).unwrap();
+            patches
+                .entry(name.to_string())
+                .or_insert_with(|| Patch {
+                    path: std::path::PathBuf::from(name),
+                    title: name.to_string(),
+                    authors: vec![],
+                    license: None,
+                    source: None,
+                    readme: None,
+                    versions: std::collections::HashMap::new(),
+                })
+                .versions
+                .insert(
+                    version.clone(),
+                    Version {
+                        rom_overrides: Default::default(),
+                        netplay_compatibility: name.to_string(),
+                        supported_games: Default::default(),
+                        base: None,
+                    },
+                );
+            if let Some((base_name, base_req)) = base {
+                base_reqs.insert(
+                    (name.to_string(), version),
+                    BaseMetadata {
+                        name: base_name.to_string(),
+                        version: semver::VersionReq::parse(base_req).unwrap(),
+                    },
+                );
+            }
+        }
+        resolve_bases(&mut patches, base_reqs);
+        patches
+    }
+
+    fn chain(patches: &std::collections::BTreeMap<String, Patch>, name: &str, version: &str) -> Option<Vec<String>> {
+        Some(
+            resolve_chain(patches, name, &semver::Version::parse(version).unwrap())?
+                .into_iter()
+                .map(|(name, version)| format!("{} v{}", name, version))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_resolve_bases_newest() {
+        let patches = resolve(&[
+            ("base", "1.0.0", None),
+            ("base", "1.1.0", None),
+            ("base", "2.0.0", None),
+            ("top", "1.0.0", Some(("base", "^1"))),
+        ]);
+        assert_eq!(
+            chain(&patches, "top", "1.0.0"),
+            Some(vec!["base v1.1.0".to_string(), "top v1.0.0".to_string()])
+        );
+        assert_eq!(chain(&patches, "base", "2.0.0"), Some(vec!["base v2.0.0".to_string()]));
+    }
+
+    #[test]
+    fn test_resolve_bases_falls_back_to_older_base() {
+        let patches = resolve(&[
+            ("base", "1.0.0", None),
+            // Stacked on a patch that isn't installed, so it gets dropped.
+            ("base", "1.1.0", Some(("missing", "*"))),
+            ("middle", "1.0.0", Some(("base", "^1"))),
+            ("top", "1.0.0", Some(("middle", "^1"))),
+        ]);
+        assert_eq!(chain(&patches, "base", "1.1.0"), None);
+        assert_eq!(
+            chain(&patches, "top", "1.0.0"),
+            Some(vec![
+                "base v1.0.0".to_string(),
+                "middle v1.0.0".to_string(),
+                "top v1.0.0".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_resolve_bases_drops_unresolvable() {
+        let patches = resolve(&[
+            ("base", "1.0.0", None),
+            ("no_match", "1.0.0", Some(("base", "^2"))),
+            ("on_no_match", "1.0.0", Some(("no_match", "^1"))),
+            ("loop_a", "1.0.0", Some(("loop_b", "^1"))),
+            ("loop_b", "1.0.0", Some(("loop_a", "^1"))),
+        ]);
+        for name in ["no_match", "on_no_match", "loop_a", "loop_b"] {
+            assert!(patches[name].versions.is_empty(), "{}", name);
+        }
+        assert_eq!(chain(&patches, "base", "1.0.0"), Some(vec!["base v1.0.0".to_string()]));
+    }
 }
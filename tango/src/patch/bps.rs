@@ -29,7 +29,7 @@ pub enum Error {
     InvalidPatchChecksum(u32),
 }
 
-fn read_vlq(buf: &mut impl std::io::Read) -> Option<usize> {
+pub fn read_vlq(buf: &mut impl std::io::Read) -> Option<usize> {
     // uint64 data = 0, shift = 1;
     let mut data = 0;
     let mut shift = 1;
@@ -60,6 +60,9 @@ fn read_signed_vlq(buf: &mut impl std::io::Read) -> Option<isize> {
 }
 
 pub fn apply(src: &[u8], mut patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.len() < 4 + 12 {
+        return Err(Error::UnexpectedPatchEOF);
+    }
     let actual_patch_checksum = crc32fast::hash(&patch[..patch.len() - 4]);
 
     // string "BPS1"
@@ -166,3 +169,268 @@ pub fn apply(src: &[u8], mut patch: &[u8]) -> Result<Vec<u8>, Error> {
 
     Ok(tgt)
 }
+
+pub fn write_vlq(out: &mut Vec<u8>, mut data: usize) {
+    loop {
+        let x = (data & 0x7f) as u8;
+        data >>= 7;
+        if data == 0 {
+            out.push(0x80 | x);
+            break;
+        }
+        out.push(x);
+        data -= 1;
+    }
+}
+
+fn write_signed_vlq(out: &mut Vec<u8>, v: isize) {
+    write_vlq(out, (v.unsigned_abs() << 1) | if v < 0 { 1 } else { 0 });
+}
+
+/// Chains of positions in a buffer that share the same 4-byte prefix, for finding copy candidates.
+struct MatchIndex {
+    heads: Vec<u32>,
+    prev: Vec<u32>,
+}
+
+const MATCH_INDEX_BITS: usize = 20;
+const MATCH_INDEX_NONE: u32 = u32::MAX;
+const MIN_MATCH_LEN: usize = 4;
+const MAX_MATCH_CANDIDATES: usize = 32;
+
+impl MatchIndex {
+    fn new(len: usize) -> Self {
+        Self {
+            heads: vec![MATCH_INDEX_NONE; 1 << MATCH_INDEX_BITS],
+            prev: vec![MATCH_INDEX_NONE; len],
+        }
+    }
+
+    fn bucket(buf: &[u8], pos: usize) -> Option<usize> {
+        let key = buf.get(pos..pos + MIN_MATCH_LEN)?;
+        Some((u32::from_le_bytes(key.try_into().unwrap()).wrapping_mul(2654435761) >> (32 - MATCH_INDEX_BITS)) as usize)
+    }
+
+    fn insert(&mut self, buf: &[u8], pos: usize) {
+        if let Some(bucket) = Self::bucket(buf, pos) {
+            self.prev[pos] = self.heads[bucket];
+            self.heads[bucket] = pos as u32;
+        }
+    }
+
+    /// Finds the longest match for `needle[pos..]` among the indexed positions of `haystack`.
+    fn longest_match(&self, haystack: &[u8], needle: &[u8], pos: usize) -> (usize, usize) {
+        let mut best = (0, 0);
+        let mut candidate = match Self::bucket(needle, pos) {
+            Some(bucket) => self.heads[bucket],
+            None => {
+                return best;
+            }
+        };
+        for _ in 0..MAX_MATCH_CANDIDATES {
+            if candidate == MATCH_INDEX_NONE {
+                break;
+            }
+            let start = candidate as usize;
+            let len = haystack[start..]
+                .iter()
+                .zip(needle[pos..].iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if len > best.1 {
+                best = (start, len);
+            }
+            candidate = self.prev[start];
+        }
+        best
+    }
+}
+
+/// Creates a BPS patch that turns `src` into `tgt`.
+pub fn diff(src: &[u8], tgt: &[u8], metadata: &[u8]) -> Vec<u8> {
+    let mut patch = vec![];
+
+    // string "BPS1"
+    patch.extend_from_slice(b"BPS1");
+
+    // number source-size, target-size, metadata-size
+    write_vlq(&mut patch, src.len());
+    write_vlq(&mut patch, tgt.len());
+    write_vlq(&mut patch, metadata.len());
+
+    // string metadata[metadata-size]
+    patch.extend_from_slice(metadata);
+
+    let mut src_index = MatchIndex::new(src.len());
+    for pos in 0..src.len() {
+        src_index.insert(src, pos);
+    }
+    let mut tgt_index = MatchIndex::new(tgt.len());
+
+    let mut src_rel_offset = 0isize;
+    let mut tgt_rel_offset = 0isize;
+    let mut literal_start = None;
+
+    let flush_literal = |patch: &mut Vec<u8>, literal_start: &mut Option<usize>, end: usize| {
+        if let Some(start) = literal_start.take() {
+            // target read
+            write_vlq(patch, ((end - start - 1) << 2) | 1);
+            patch.extend_from_slice(&tgt[start..end]);
+        }
+    };
+
+    let mut tgt_offset = 0;
+    while tgt_offset < tgt.len() {
+        // source read
+        let source_read_len = src
+            .get(tgt_offset..)
+            .unwrap_or_default()
+            .iter()
+            .zip(tgt[tgt_offset..].iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (source_copy_start, source_copy_len) = src_index.longest_match(src, tgt, tgt_offset);
+        let (target_copy_start, target_copy_len) = tgt_index.longest_match(tgt, tgt, tgt_offset);
+
+        let len = source_read_len.max(source_copy_len).max(target_copy_len);
+        if len < MIN_MATCH_LEN {
+            if literal_start.is_none() {
+                literal_start = Some(tgt_offset);
+            }
+            tgt_index.insert(tgt, tgt_offset);
+            tgt_offset += 1;
+            continue;
+        }
+
+        flush_literal(&mut patch, &mut literal_start, tgt_offset);
+        if len == source_read_len {
+            // source read
+            write_vlq(&mut patch, (len - 1) << 2);
+        } else if len == source_copy_len {
+            // source copy
+            write_vlq(&mut patch, ((len - 1) << 2) | 2);
+            write_signed_vlq(&mut patch, source_copy_start as isize - src_rel_offset);
+            src_rel_offset = (source_copy_start + len) as isize;
+        } else {
+            // target copy
+            write_vlq(&mut patch, ((len - 1) << 2) | 3);
+            write_signed_vlq(&mut patch, target_copy_start as isize - tgt_rel_offset);
+            tgt_rel_offset = (target_copy_start + len) as isize;
+        }
+
+        for pos in tgt_offset..tgt_offset + len {
+            tgt_index.insert(tgt, pos);
+        }
+        tgt_offset += len;
+    }
+    flush_literal(&mut patch, &mut literal_start, tgt_offset);
+
+    // uint32 source-checksum, target-checksum
+    patch.extend_from_slice(&crc32fast::hash(src).to_le_bytes());
+    patch.extend_from_slice(&crc32fast::hash(tgt).to_le_bytes());
+
+    // uint32 patch-checksum
+    let patch_checksum = crc32fast::hash(&patch);
+    patch.extend_from_slice(&patch_checksum.to_le_bytes());
+
+    patch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(seed: u32, len: usize) -> Vec<u8> {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vlq_round_trip() {
+        for v in [0, 1, 0x7f, 0x80, 0x81, 0x407f, 0x4080, 0xffffff, usize::MAX >> 8] {
+            let mut buf = vec![];
+            write_vlq(&mut buf, v);
+            assert_eq!(read_vlq(&mut &buf[..]), Some(v));
+        }
+        for v in [0, 1, -1, 0x40, -0x40, 0x1234, -0x1234] {
+            let mut buf = vec![];
+            write_signed_vlq(&mut buf, v);
+            assert_eq!(read_signed_vlq(&mut &buf[..]), Some(v));
+        }
+    }
+
+    #[test]
+    fn test_diff_apply() {
+        let src = noise(1, 0x800);
+
+        let mut edited = src.clone();
+        edited[0x100..0x140].copy_from_slice(&noise(2, 0x40));
+
+        // Source blocks moved around, with insertions and deletions in between.
+        let mut shuffled = src[0x400..0x600].to_vec();
+        shuffled.extend_from_slice(&noise(3, 0x33));
+        shuffled.extend_from_slice(&src[0x10..0x200]);
+        shuffled.extend_from_slice(&src[0x700..]);
+
+        // Repeated target data exercises target copies.
+        let mut repeated = noise(4, 0x20).repeat(0x10);
+        repeated.extend_from_slice(&src[..0x80]);
+
+        let mut grown = src.clone();
+        grown.extend_from_slice(&noise(5, 0x300));
+
+        for tgt in [
+            src.clone(),
+            edited,
+            shuffled,
+            repeated,
+            grown,
+            src[..0x123].to_vec(),
+            vec![],
+        ] {
+            assert_eq!(apply(&src, &diff(&src, &tgt, b"")).unwrap(), tgt);
+        }
+        assert_eq!(apply(&[], &diff(&[], &src, b"")).unwrap(), src);
+    }
+
+    #[test]
+    fn test_diff_metadata() {
+        let src = noise(1, 0x100);
+        let tgt = noise(2, 0x100);
+        let patch = diff(&src, &tgt, b"<metadata/>");
+        assert_eq!(apply(&src, &patch).unwrap(), tgt);
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let src = noise(1, 0x100);
+        let mut tgt = src.clone();
+        tgt[0x42] ^= 0xff;
+        let patch = diff(&src, &tgt, b"");
+
+        assert!(matches!(apply(&src, b""), Err(Error::UnexpectedPatchEOF)));
+        assert!(matches!(apply(&src, &patch[..8]), Err(Error::UnexpectedPatchEOF)));
+
+        let mut other_src = src.clone();
+        other_src[0] ^= 1;
+        assert!(matches!(
+            apply(&other_src, &patch),
+            Err(Error::InvalidSourceChecksum(_))
+        ));
+
+        let mut corrupted = patch.clone();
+        corrupted[4] ^= 1;
+        assert!(matches!(apply(&src, &corrupted), Err(Error::InvalidPatchChecksum(_))));
+
+        let mut bad_header = patch;
+        bad_header[..4].copy_from_slice(b"UPS1");
+        assert!(matches!(apply(&src, &bad_header), Err(Error::InvalidHeader)));
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::Read;
+
+use byteorder::ReadBytesExt;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid format")]
+    InvalidHeader,
+
+    #[error("unexpected patch eof")]
+    UnexpectedPatchEOF,
+}
+
+const EOF_MARKER: usize = 0x454f46;
+
+pub fn apply(src: &[u8], mut patch: &[u8]) -> Result<Vec<u8>, Error> {
+    // string "PATCH"
+    let mut header = [0u8; 5];
+    patch.read_exact(&mut header).map_err(|_| Error::UnexpectedPatchEOF)?;
+    if &header != b"PATCH" {
+        return Err(Error::InvalidHeader);
+    }
+
+    let mut tgt = src.to_vec();
+
+    loop {
+        // uint24 offset, or "EOF"
+        let offset = patch
+            .read_u24::<byteorder::BigEndian>()
+            .map_err(|_| Error::UnexpectedPatchEOF)? as usize;
+        if offset == EOF_MARKER {
+            break;
+        }
+
+        // uint16 size
+        let size = patch
+            .read_u16::<byteorder::BigEndian>()
+            .map_err(|_| Error::UnexpectedPatchEOF)? as usize;
+
+        let data = if size == 0 {
+            // RLE record: uint16 run length, uint8 value
+            let len = patch
+                .read_u16::<byteorder::BigEndian>()
+                .map_err(|_| Error::UnexpectedPatchEOF)? as usize;
+            let v = patch.read_u8().map_err(|_| Error::UnexpectedPatchEOF)?;
+            vec![v; len]
+        } else {
+            let mut buf = vec![0u8; size];
+            patch.read_exact(&mut buf).map_err(|_| Error::UnexpectedPatchEOF)?;
+            buf
+        };
+
+        // Records may write past the end of the source, growing the target.
+        if tgt.len() < offset + data.len() {
+            tgt.resize(offset + data.len(), 0);
+        }
+        tgt[offset..offset + data.len()].copy_from_slice(&data);
+    }
+
+    // Optional uint24 truncation size, as written by Lunar IPS.
+    if let Ok(truncate) = patch.read_u24::<byteorder::BigEndian>() {
+        tgt.truncate(truncate as usize);
+    }
+
+    Ok(tgt)
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+
+    fn record(patch: &mut Vec<u8>, offset: usize, data: &[u8]) {
+        patch.write_u24::<byteorder::BigEndian>(offset as u32).unwrap();
+        patch.write_u16::<byteorder::BigEndian>(data.len() as u16).unwrap();
+        patch.extend_from_slice(data);
+    }
+
+    fn rle_record(patch: &mut Vec<u8>, offset: usize, len: usize, v: u8) {
+        patch.write_u24::<byteorder::BigEndian>(offset as u32).unwrap();
+        patch.write_u16::<byteorder::BigEndian>(0).unwrap();
+        patch.write_u16::<byteorder::BigEndian>(len as u16).unwrap();
+        patch.push(v);
+    }
+
+    #[test]
+    fn test_apply() {
+        let src = (0..0x20).collect::<Vec<u8>>();
+
+        let mut patch = b"PATCH".to_vec();
+        record(&mut patch, 0x02, &[0xaa, 0xbb, 0xcc]);
+        rle_record(&mut patch, 0x10, 4, 0xee);
+        // Writes past the end of the source grow the target.
+        record(&mut patch, 0x1f, &[0x11, 0x22]);
+        patch.write_u24::<byteorder::BigEndian>(EOF_MARKER as u32).unwrap();
+
+        let mut expected = src.clone();
+        expected[0x02..0x05].copy_from_slice(&[0xaa, 0xbb, 0xcc]);
+        expected[0x10..0x14].fill(0xee);
+        expected[0x1f] = 0x11;
+        expected.push(0x22);
+        assert_eq!(apply(&src, &patch).unwrap(), expected);
+
+        // Lunar IPS truncation extension.
+        patch.write_u24::<byteorder::BigEndian>(0x08).unwrap();
+        assert_eq!(apply(&src, &patch).unwrap(), expected[..0x08]);
+    }
+
+    #[test]
+    fn test_apply_empty() {
+        let src = vec![1, 2, 3];
+        let mut patch = b"PATCH".to_vec();
+        patch.write_u24::<byteorder::BigEndian>(EOF_MARKER as u32).unwrap();
+        assert_eq!(apply(&src, &patch).unwrap(), src);
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        assert!(matches!(apply(&[], b"PATC"), Err(Error::UnexpectedPatchEOF)));
+        assert!(matches!(apply(&[], b"PATCX\x45\x4f\x46"), Err(Error::InvalidHeader)));
+
+        // Missing EOF marker.
+        let mut patch = b"PATCH".to_vec();
+        record(&mut patch, 0, &[1]);
+        assert!(matches!(apply(&[0], &patch), Err(Error::UnexpectedPatchEOF)));
+
+        // Record shorter than its size.
+        let mut patch = b"PATCH".to_vec();
+        record(&mut patch, 0, &[1, 2, 3]);
+        patch.truncate(patch.len() - 1);
+        assert!(matches!(apply(&[0], &patch), Err(Error::UnexpectedPatchEOF)));
+    }
+}
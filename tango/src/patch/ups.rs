@@ -0,0 +1,185 @@
+use std::io::Read;
+
+use byteorder::ReadBytesExt;
+
+use super::bps::read_vlq;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid format")]
+    InvalidHeader,
+
+    #[error("unexpected patch eof")]
+    UnexpectedPatchEOF,
+
+    #[error("invalid length, expected {0}")]
+    InvalidLength(usize),
+
+    #[error("invalid source checksum, expected {0}")]
+    InvalidSourceChecksum(u32),
+
+    #[error("invalid target checksum, expected {0}")]
+    InvalidTargetChecksum(u32),
+
+    #[error("invalid patch checksum, expected {0}")]
+    InvalidPatchChecksum(u32),
+}
+
+pub fn apply(src: &[u8], mut patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.len() < 4 + 12 {
+        return Err(Error::UnexpectedPatchEOF);
+    }
+    let actual_patch_checksum = crc32fast::hash(&patch[..patch.len() - 4]);
+
+    // string "UPS1"
+    let mut header = [0u8; 4];
+    patch.read_exact(&mut header).map_err(|_| Error::UnexpectedPatchEOF)?;
+    if &header != b"UPS1" {
+        return Err(Error::InvalidHeader);
+    }
+
+    // (trailer)
+    let mut footer = &patch[patch.len() - 12..];
+
+    // uint32 source-checksum
+    let source_checksum = footer.read_u32::<byteorder::LittleEndian>().unwrap();
+    if source_checksum != crc32fast::hash(src) {
+        return Err(Error::InvalidSourceChecksum(source_checksum));
+    }
+
+    // uint32 target-checksum
+    let target_checksum = footer.read_u32::<byteorder::LittleEndian>().unwrap();
+
+    // uint32 patch-checksum
+    let patch_checksum = footer.read_u32::<byteorder::LittleEndian>().unwrap();
+    if patch_checksum != actual_patch_checksum {
+        return Err(Error::InvalidPatchChecksum(patch_checksum));
+    }
+
+    // number source-size
+    let source_size = read_vlq(&mut patch).ok_or(Error::UnexpectedPatchEOF)?;
+    if source_size != src.len() {
+        return Err(Error::InvalidLength(source_size));
+    }
+
+    // number target-size
+    let target_size = read_vlq(&mut patch).ok_or(Error::UnexpectedPatchEOF)?;
+    let mut tgt = src.to_vec();
+    tgt.resize(target_size, 0);
+
+    patch = &patch[..patch.len() - 12];
+
+    // repeat {
+    let mut offset = 0;
+    while !patch.is_empty() {
+        // number relative-offset
+        offset += read_vlq(&mut patch).ok_or(Error::UnexpectedPatchEOF)?;
+
+        // xor bytes, terminated by a zero byte
+        loop {
+            let x = patch.read_u8().map_err(|_| Error::UnexpectedPatchEOF)?;
+            if x == 0 {
+                offset += 1;
+                break;
+            }
+            // Patches that shrink the file still carry the bytes past the end of the target, so that they can be
+            // applied in reverse.
+            if let Some(v) = tgt.get_mut(offset) {
+                *v ^= x;
+            }
+            offset += 1;
+        }
+    }
+    // }
+
+    if target_checksum != crc32fast::hash(&tgt) {
+        return Err(Error::InvalidTargetChecksum(target_checksum));
+    }
+
+    Ok(tgt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bps::write_vlq;
+    use super::*;
+
+    /// Creates a UPS patch, which XORs the source against the target.
+    fn diff(src: &[u8], tgt: &[u8]) -> Vec<u8> {
+        let mut patch = b"UPS1".to_vec();
+        write_vlq(&mut patch, src.len());
+        write_vlq(&mut patch, tgt.len());
+
+        let len = src.len().max(tgt.len());
+        let xor = |i: usize| src.get(i).copied().unwrap_or(0) ^ tgt.get(i).copied().unwrap_or(0);
+        let mut last = 0;
+        let mut i = 0;
+        while i < len {
+            if xor(i) == 0 {
+                i += 1;
+                continue;
+            }
+            write_vlq(&mut patch, i - last);
+            while i < len && xor(i) != 0 {
+                patch.push(xor(i));
+                i += 1;
+            }
+            patch.push(0);
+            i += 1;
+            last = i;
+        }
+
+        patch.extend_from_slice(&crc32fast::hash(src).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(tgt).to_le_bytes());
+        let patch_checksum = crc32fast::hash(&patch);
+        patch.extend_from_slice(&patch_checksum.to_le_bytes());
+        patch
+    }
+
+    fn src() -> Vec<u8> {
+        (0..0x400).map(|i| (i * 13 + i / 0x100) as u8).collect()
+    }
+
+    #[test]
+    fn test_apply() {
+        let src = src();
+
+        let mut edited = src.clone();
+        edited[0x10..0x20].fill(0xff);
+        edited[0x3ff] ^= 0x01;
+
+        let mut grown = src.clone();
+        grown.extend_from_slice(&[0xaa; 0x80]);
+
+        let shrunk = src[..0x123].to_vec();
+
+        for tgt in [src.clone(), edited, grown, shrunk, vec![]] {
+            assert_eq!(apply(&src, &diff(&src, &tgt)).unwrap(), tgt);
+        }
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let src = src();
+        let mut tgt = src.clone();
+        tgt[0x42] = 0;
+        let patch = diff(&src, &tgt);
+
+        assert!(matches!(apply(&src, &patch[..8]), Err(Error::UnexpectedPatchEOF)));
+
+        let mut other_src = src.clone();
+        other_src[0] ^= 1;
+        assert!(matches!(
+            apply(&other_src, &patch),
+            Err(Error::InvalidSourceChecksum(_))
+        ));
+
+        let mut corrupted = patch.clone();
+        corrupted[4] ^= 1;
+        assert!(matches!(apply(&src, &corrupted), Err(Error::InvalidPatchChecksum(_))));
+
+        let mut bad_header = patch.clone();
+        bad_header[..4].copy_from_slice(b"BPS1");
+        assert!(matches!(apply(&src, &bad_header), Err(Error::InvalidHeader)));
+    }
+}
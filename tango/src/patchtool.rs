@@ -0,0 +1,323 @@
+use crate::{config, game, patch};
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Create a BPS patch from a modified ROM and add it to a patch directory, creating or updating its info.toml.
+    CreatePatch {
+        modified_rom_path: std::path::PathBuf,
+
+        /// Name of the patch directory.
+        #[arg(long)]
+        name: String,
+
+        /// Version to publish the patch as.
+        #[arg(long)]
+        version: semver::Version,
+
+        /// Path to the unmodified ROM. Defaults to the ROM in the ROMs directory with the same game code and revision
+        /// as the modified ROM.
+        #[arg(long)]
+        source_rom_path: Option<std::path::PathBuf>,
+
+        /// Directory to look for ROMs in. Defaults to the ROMs directory in the data path.
+        #[arg(long)]
+        roms_path: Option<std::path::PathBuf>,
+
+        /// Title of the patch. Only used if info.toml does not already have one. Defaults to the name of the patch.
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Author of the patch, e.g. "Name <email@example.com>". May be given more than once. Only used if info.toml
+        /// does not already list any authors.
+        #[arg(long = "author")]
+        authors: Vec<String>,
+
+        /// Netplay compatibility string for this version. Defaults to the name of the patch.
+        #[arg(long)]
+        netplay_compatibility: Option<String>,
+
+        /// Directory to write the patch to. Defaults to the patches directory in the data path.
+        #[arg(long)]
+        patches_path: Option<std::path::PathBuf>,
+
+        /// Name of a patch this patch is applied on top of. The modified ROM is diffed against the source ROM with the
+        /// base patch applied.
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Versions of the base patch this patch can be applied on top of.
+        #[arg(long, requires = "base", default_value = "*")]
+        base_version: semver::VersionReq,
+    },
+
+    /// Generate a key for signing patch repository indexes. The secret key is written as hex to the given path and the
+    /// public key, to add to trusted_patch_publisher_keys, is printed.
+    GenerateSigningKey { secret_key_path: std::path::PathBuf },
+
+    /// Sign a patch repository's index.json, writing the signature to index.json.sig next to it.
+    SignIndex {
+        index_path: std::path::PathBuf,
+
+        /// Path to a secret key made by generate-signing-key.
+        #[arg(long)]
+        secret_key_path: std::path::PathBuf,
+    },
+}
+
+pub fn main(config: config::Config, command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::CreatePatch {
+            modified_rom_path,
+            name,
+            version,
+            source_rom_path,
+            roms_path,
+            title,
+            authors,
+            netplay_compatibility,
+            patches_path,
+            base,
+            base_version,
+        } => cmd_create_patch(
+            config,
+            modified_rom_path,
+            name,
+            version,
+            source_rom_path,
+            roms_path,
+            title,
+            authors,
+            netplay_compatibility,
+            patches_path,
+            base.map(|base| (base, base_version)),
+        ),
+        Command::GenerateSigningKey { secret_key_path } => cmd_generate_signing_key(secret_key_path),
+        Command::SignIndex {
+            index_path,
+            secret_key_path,
+        } => cmd_sign_index(index_path, secret_key_path),
+    }
+}
+
+fn to_hex(buf: &[u8]) -> String {
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let s = s.trim();
+    if s.len() % 2 != 0 {
+        anyhow::bail!("odd number of hex digits");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            Ok(u8::from_str_radix(
+                s.get(i..i + 2).ok_or_else(|| anyhow::anyhow!("invalid hex"))?,
+                16,
+            )?)
+        })
+        .collect()
+}
+
+fn update_info(
+    info: &mut toml::value::Table,
+    name: &str,
+    version: &semver::Version,
+    title: Option<String>,
+    authors: Vec<String>,
+    netplay_compatibility: Option<String>,
+    base: Option<&(String, semver::VersionReq)>,
+) -> Result<(), anyhow::Error> {
+    let patch_info = info
+        .entry("patch")
+        .or_insert_with(|| toml::Value::Table(Default::default()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("patch in info.toml is not a table"))?;
+    patch_info
+        .entry("title")
+        .or_insert_with(|| toml::Value::String(title.unwrap_or_else(|| name.to_string())));
+    patch_info
+        .entry("authors")
+        .or_insert_with(|| toml::Value::Array(authors.into_iter().map(toml::Value::String).collect()));
+
+    let versions = info
+        .entry("versions")
+        .or_insert_with(|| toml::Value::Table(Default::default()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("versions in info.toml is not a table"))?;
+    let version_info = versions
+        .entry(version.to_string())
+        .or_insert_with(|| toml::Value::Table(Default::default()))
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("version {} in info.toml is not a table", version))?;
+    if let Some(netplay_compatibility) = netplay_compatibility {
+        version_info.insert(
+            "netplay_compatibility".to_string(),
+            toml::Value::String(netplay_compatibility),
+        );
+    } else {
+        version_info
+            .entry("netplay_compatibility")
+            .or_insert_with(|| toml::Value::String(name.to_string()));
+    }
+    if let Some((base_name, base_version)) = base {
+        version_info.insert(
+            "base".to_string(),
+            toml::Value::Table(toml::value::Table::from_iter([
+                ("name".to_string(), toml::Value::String(base_name.clone())),
+                ("version".to_string(), toml::Value::String(base_version.to_string())),
+            ])),
+        );
+    }
+    Ok(())
+}
+
+fn cmd_create_patch(
+    config: config::Config,
+    modified_rom_path: std::path::PathBuf,
+    name: String,
+    version: semver::Version,
+    source_rom_path: Option<std::path::PathBuf>,
+    roms_path: Option<std::path::PathBuf>,
+    title: Option<String>,
+    authors: Vec<String>,
+    netplay_compatibility: Option<String>,
+    patches_path: Option<std::path::PathBuf>,
+    base: Option<(String, semver::VersionReq)>,
+) -> Result<(), anyhow::Error> {
+    if std::path::Path::new(&name).components().count() != 1 {
+        anyhow::bail!("patch name must be a single path component");
+    }
+
+    let modified_rom = std::fs::read(&modified_rom_path)?;
+    let rom_code: &[u8; 4] = modified_rom
+        .get(0xac..0xac + 4)
+        .ok_or_else(|| anyhow::anyhow!("modified rom is too short"))?
+        .try_into()?;
+    let rom_revision = *modified_rom
+        .get(0xbc)
+        .ok_or_else(|| anyhow::anyhow!("modified rom is too short"))?;
+    let game = game::find_by_rom_info(rom_code, rom_revision).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unsupported game: {}_{:02}",
+            String::from_utf8_lossy(rom_code),
+            rom_revision
+        )
+    })?;
+
+    let source_rom = if let Some(source_rom_path) = source_rom_path {
+        let source_rom = std::fs::read(&source_rom_path)?;
+        let source_game = game::detect(&source_rom)?;
+        if source_game != game {
+            anyhow::bail!(
+                "source rom is {:?} but modified rom is {:?}",
+                source_game.family_and_variant(),
+                game.family_and_variant()
+            );
+        }
+        source_rom
+    } else {
+        game::scan_roms(&roms_path.unwrap_or_else(|| config.roms_path()))
+            .remove(&game)
+            .ok_or_else(|| anyhow::anyhow!("no rom found for {:?}", game.family_and_variant()))?
+    };
+
+    let patches_path = patches_path.unwrap_or_else(|| config.patches_path());
+    let source_rom = if let Some((base_name, base_version)) = base.as_ref() {
+        let patches = patch::scan(&patches_path)?;
+        let version = patches
+            .get(base_name)
+            .and_then(|p| p.versions.keys().filter(|v| base_version.matches(v)).max())
+            .ok_or_else(|| anyhow::anyhow!("no installed version of {} matches {}", base_name, base_version))?;
+        let chain = patch::resolve_chain(&patches, base_name, version)
+            .ok_or_else(|| anyhow::anyhow!("could not resolve base patches of {} v{}", base_name, version))?;
+        eprintln!("diffing against {} v{}", base_name, version);
+        patch::apply_patch_chain_from_disk(&source_rom, game, &patches_path, &chain)?
+    } else {
+        source_rom
+    };
+
+    let patch_path = patches_path.join(&name);
+    let version_path = patch_path.join(format!("v{}", version));
+    let (rom_code, revision) = game.rom_code_and_revision();
+    let bps_path = version_path.join(format!(
+        "{}_{:02}.bps",
+        std::str::from_utf8(rom_code).unwrap(),
+        revision
+    ));
+    if bps_path.exists() {
+        anyhow::bail!("{} already exists", bps_path.display());
+    }
+
+    let info_path = patch_path.join("info.toml");
+    let mut info = match std::fs::read(&info_path) {
+        Ok(raw) => toml::from_slice::<toml::value::Table>(&raw)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+    update_info(
+        &mut info,
+        &name,
+        &version,
+        title,
+        authors,
+        netplay_compatibility,
+        base.as_ref(),
+    )?;
+
+    let raw_patch = patch::bps::diff(&source_rom, &modified_rom, &[]);
+    if patch::bps::apply(&source_rom, &raw_patch)? != modified_rom {
+        anyhow::bail!("created patch does not reproduce the modified rom");
+    }
+
+    std::fs::create_dir_all(&version_path)?;
+    std::fs::write(&bps_path, &raw_patch)?;
+    std::fs::write(&info_path, toml::to_string_pretty(&toml::Value::Table(info))?)?;
+
+    eprintln!("wrote {} ({} bytes)", bps_path.display(), raw_patch.len());
+    Ok(())
+}
+
+fn cmd_generate_signing_key(secret_key_path: std::path::PathBuf) -> Result<(), anyhow::Error> {
+    // Only the owner may read the secret key.
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut f = match options.open(&secret_key_path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            anyhow::bail!("{} already exists", secret_key_path.display());
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+    std::io::Write::write_all(&mut f, to_hex(signing_key.as_bytes()).as_bytes())?;
+
+    eprintln!("wrote secret key to {}", secret_key_path.display());
+    println!("{}", to_hex(signing_key.verifying_key().as_bytes()));
+    Ok(())
+}
+
+fn cmd_sign_index(index_path: std::path::PathBuf, secret_key_path: std::path::PathBuf) -> Result<(), anyhow::Error> {
+    let signing_key = ed25519_dalek::SigningKey::try_from(&from_hex(&std::fs::read_to_string(&secret_key_path)?)?[..])?;
+    let public_key = signing_key.verifying_key();
+
+    let raw_index = std::fs::read(&index_path)?;
+    let signature = ed25519_dalek::Signer::sign(&signing_key, &raw_index);
+    let mut signature_path = index_path.into_os_string();
+    signature_path.push(".sig");
+    std::fs::write(&signature_path, signature.to_bytes())?;
+
+    eprintln!(
+        "wrote {} (public key {})",
+        std::path::Path::new(&signature_path).display(),
+        to_hex(public_key.as_bytes())
+    );
+    Ok(())
+}
@@ -25,6 +25,7 @@ const VERSION: u8 = 0x11;
 
 #[derive(Clone)]
 pub struct Replay {
+    pub version: u8,
     pub is_complete: bool,
     pub metadata: Metadata,
     pub local_player_index: u8,
@@ -47,6 +48,11 @@ fn decode_metadata(version: u8, raw: &[u8]) -> Result<Metadata, std::io::Error>
 }
 
 pub fn read_metadata(r: &mut impl std::io::Read) -> Result<(usize, Metadata), std::io::Error> {
+    let (_, num_inputs, metadata) = read_versioned_metadata(r)?;
+    Ok((num_inputs, metadata))
+}
+
+fn read_versioned_metadata(r: &mut impl std::io::Read) -> Result<(u8, usize, Metadata), std::io::Error> {
     let mut header = [0u8; 4];
     r.read_exact(&mut header)?;
     if &header != HEADER {
@@ -58,13 +64,62 @@ pub fn read_metadata(r: &mut impl std::io::Read) -> Result<(usize, Metadata), st
     let metadata_len = r.read_u32::<byteorder::LittleEndian>()?;
     let mut raw = vec![0u8; metadata_len as usize];
     r.read_exact(&mut raw[..])?;
-    Ok((num_inputs, decode_metadata(version, &raw)?))
+    Ok((version, num_inputs, decode_metadata(version, &raw)?))
+}
+
+fn rewrite_metadata_buf(buf: &[u8], f: impl FnOnce(&mut Metadata)) -> Result<Vec<u8>, std::io::Error> {
+    if buf.get(HEADER.len()) != Some(&VERSION) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "can only rewrite replays of the current version",
+        ));
+    }
+    let mut r = buf;
+    let (num_inputs, mut metadata) = read_metadata(&mut r)?;
+    f(&mut metadata);
+
+    let mut w = Vec::with_capacity(buf.len());
+    w.write_all(HEADER)?;
+    w.write_u8(VERSION)?;
+    w.write_u32::<byteorder::LittleEndian>(num_inputs as u32)?;
+    let raw_metadata = metadata.encode_to_vec();
+    w.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)?;
+    w.write_all(&raw_metadata[..])?;
+    w.write_all(r)?;
+    Ok(w)
+}
+
+/// Replaces the metadata of a replay file, keeping the rest of it as is. The new file is written next to the old one
+/// and renamed over it, so readers never see a partially written replay.
+pub fn rewrite_metadata(path: &std::path::Path, f: impl FnOnce(&mut Metadata)) -> Result<(), std::io::Error> {
+    let buf = rewrite_metadata_buf(&std::fs::read(path)?, f)?;
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = std::path::PathBuf::from(tmp_path);
+    if let Err(e) = std::fs::write(&tmp_path, buf).and_then(|_| std::fs::rename(&tmp_path, path)) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
 }
 
 impl Replay {
+    /// Whether the metadata records the match type. Version 0x10 replays don't, so they report match type 0 whatever
+    /// was actually played.
+    pub fn has_match_type(&self) -> bool {
+        self.version >= 0x11
+    }
+
     #[allow(dead_code)]
     pub fn into_remote(mut self) -> Self {
         std::mem::swap(&mut self.metadata.local_side, &mut self.metadata.remote_side);
+        if let Some(set) = self.metadata.set.as_mut() {
+            std::mem::swap(&mut set.local_score, &mut set.remote_score);
+            if let Some(outcome) = set.outcome.as_mut() {
+                std::mem::swap(&mut outcome.local_score, &mut outcome.remote_score);
+            }
+        }
         self.local_player_index = 1 - self.local_player_index;
         std::mem::swap(&mut self.local_state, &mut self.remote_state);
         for ip in self.input_pairs.iter_mut() {
@@ -74,7 +129,7 @@ impl Replay {
     }
 
     pub fn decode(mut r: impl std::io::Read) -> std::io::Result<Self> {
-        let (num_inputs, metadata) = read_metadata(&mut r)?;
+        let (version, num_inputs, metadata) = read_versioned_metadata(&mut r)?;
 
         let mut zr = zstd::stream::read::Decoder::new(r)?;
 
@@ -142,6 +197,7 @@ impl Replay {
         }
 
         Ok(Self {
+            version,
             is_complete: num_inputs > 0 && num_inputs as usize == input_pairs.len(),
             metadata,
             local_player_index,
@@ -236,3 +292,98 @@ impl Drop for Writer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay_buf(metadata: &Metadata, num_inputs: u32, rest: &[u8]) -> Vec<u8> {
+        let mut w = vec![];
+        w.write_all(HEADER).unwrap();
+        w.write_u8(VERSION).unwrap();
+        w.write_u32::<byteorder::LittleEndian>(num_inputs).unwrap();
+        let raw_metadata = metadata.encode_to_vec();
+        w.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)
+            .unwrap();
+        w.write_all(&raw_metadata[..]).unwrap();
+        w.write_all(rest).unwrap();
+        w
+    }
+
+    #[test]
+    fn test_rewrite_metadata_buf() {
+        let metadata = Metadata {
+            link_code: "abc".to_string(),
+            round: 2,
+            set: Some(metadata::Set {
+                best_of: 3,
+                game: 2,
+                local_score: 1,
+                remote_score: 0,
+                outcome: None,
+            }),
+            ..Default::default()
+        };
+        let buf = replay_buf(&metadata, 123, b"inputs go here");
+
+        let rewritten = rewrite_metadata_buf(&buf, |metadata| {
+            metadata.set.as_mut().unwrap().outcome = Some(metadata::set::Outcome {
+                local_score: 2,
+                remote_score: 0,
+            });
+        })
+        .unwrap();
+
+        let mut expected_metadata = metadata.clone();
+        expected_metadata.set.as_mut().unwrap().outcome = Some(metadata::set::Outcome {
+            local_score: 2,
+            remote_score: 0,
+        });
+        assert_eq!(rewritten, replay_buf(&expected_metadata, 123, b"inputs go here"));
+
+        let mut r = &rewritten[..];
+        assert_eq!(read_metadata(&mut r).unwrap(), (123, expected_metadata));
+        assert_eq!(r, b"inputs go here");
+    }
+
+    #[test]
+    fn test_rewrite_metadata_buf_rejects_other_versions() {
+        let mut buf = replay_buf(&Default::default(), 0, b"");
+        buf[HEADER.len()] = 0x10;
+        assert_eq!(
+            rewrite_metadata_buf(&buf, |_| {}).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_rewrite_metadata() {
+        let path = std::env::temp_dir().join(format!(
+            "tango-test-rewrite-metadata-{}.tangoreplay",
+            std::process::id()
+        ));
+        std::fs::write(&path, replay_buf(&Default::default(), 1, b"rest")).unwrap();
+
+        rewrite_metadata(&path, |metadata| {
+            metadata.round = 5;
+        })
+        .unwrap();
+
+        let buf = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            buf,
+            replay_buf(
+                &Metadata {
+                    round: 5,
+                    ..Default::default()
+                },
+                1,
+                b"rest"
+            )
+        );
+        let mut tmp_path = path.into_os_string();
+        tmp_path.push(".tmp");
+        assert!(!std::path::Path::new(&tmp_path).exists());
+    }
+}
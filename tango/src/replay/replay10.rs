@@ -46,6 +46,7 @@ fn convert_side(v10: &super::protos::replay10::metadata::Side) -> Result<super::
                     patch: gi.patch.as_ref().map(|patch| super::metadata::game_info::Patch {
                         name: patch.name.clone(),
                         version: patch.version.clone(),
+                        bases: vec![],
                     }),
                 })
             })
@@ -70,5 +71,6 @@ pub fn decode_metadata(raw: &[u8]) -> Result<super::Metadata, std::io::Error> {
         round: 0,      // Impossible to tell.
         match_type: 0, // Impossible to tell.
         match_subtype: 0,
+        set: None,
     })
 }
@@ -7,6 +7,9 @@ pub struct InnerState {
     current_tick: u32,
     local_player_index: u8,
     input_pairs: std::collections::VecDeque<lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>>,
+    input_source: Option<
+        Box<dyn FnMut(u32) -> Option<lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>> + Sync + Send>,
+    >,
     output_pairs: Vec<lockstep::Pair<lockstep::Input, lockstep::Input>>,
     apply_shadow_input: Box<
         dyn FnMut(lockstep::Pair<lockstep::Input, lockstep::PartialInput>) -> anyhow::Result<Vec<u8>> + Sync + Send,
@@ -70,6 +73,10 @@ impl InnerState {
         self.committed_state.take()
     }
 
+    pub fn committed_state(&self) -> Option<&battle::CommittedState> {
+        self.committed_state.as_ref()
+    }
+
     pub fn dirty_tick(&self) -> u32 {
         self.dirty_tick
     }
@@ -89,11 +96,24 @@ impl InnerState {
         });
     }
 
-    pub fn peek_input_pair(&self) -> Option<&lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>> {
+    fn fill_input_pairs(&mut self) {
+        if !self.input_pairs.is_empty() {
+            return;
+        }
+        if let Some(input_source) = self.input_source.as_mut() {
+            if let Some(ip) = input_source(self.current_tick) {
+                self.input_pairs.push_back(ip);
+            }
+        }
+    }
+
+    pub fn peek_input_pair(&mut self) -> Option<&lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>> {
+        self.fill_input_pairs();
         self.input_pairs.front()
     }
 
     pub fn pop_input_pair(&mut self) -> Option<lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>> {
+        self.fill_input_pairs();
         self.input_pairs.pop_front()
     }
 
@@ -162,6 +182,10 @@ impl InnerState {
 
     pub fn increment_current_tick(&mut self) {
         self.current_tick += 1;
+        // A live replayer never rolls back, so every tick it reaches is committed.
+        if self.input_source.is_some() {
+            self.commit_tick = self.current_tick;
+        }
     }
 }
 
@@ -235,6 +259,7 @@ impl State {
                     },
                 })
                 .collect(),
+            input_source: None,
             apply_shadow_input: Box::new({
                 let mut iq = input_pairs.into_iter().collect::<std::collections::VecDeque<_>>();
                 move |_| {
@@ -260,6 +285,45 @@ impl State {
         }))))
     }
 
+    /// Creates a replayer state that is fed input as the game asks for it instead of from a recording: `input_source`
+    /// is called with the current tick whenever no input pair is queued, and `apply_shadow_input` produces the remote
+    /// packet for each tick. The committed state follows the current tick.
+    pub fn new_live(
+        match_type: (u8, u8),
+        local_player_index: u8,
+        first_local_packet: Vec<u8>,
+        input_source: Box<
+            dyn FnMut(u32) -> Option<lockstep::Pair<lockstep::PartialInput, lockstep::PartialInput>> + Sync + Send,
+        >,
+        apply_shadow_input: Box<
+            dyn FnMut(lockstep::Pair<lockstep::Input, lockstep::PartialInput>) -> anyhow::Result<Vec<u8>> + Sync + Send,
+        >,
+        on_round_ended: Box<dyn FnOnce() + Send>,
+    ) -> State {
+        State(std::sync::Arc::new(parking_lot::Mutex::new(Some(InnerState {
+            disable_bgm: false,
+            current_tick: 0,
+            local_player_index,
+            input_pairs: std::collections::VecDeque::new(),
+            input_source: Some(input_source),
+            apply_shadow_input,
+            match_type,
+            output_pairs: vec![],
+            local_packet: Some(lockstep::Packet {
+                tick: 0,
+                packet: first_local_packet,
+            }),
+            commit_tick: 0,
+            committed_state: None,
+            dirty_tick: 0,
+            dirty_state: None,
+            round_result: None,
+            phase: RoundPhase::InProgress,
+            error: None,
+            on_round_ended: Some(on_round_ended),
+        }))))
+    }
+
     pub fn lock_inner(&self) -> parking_lot::MappedMutexGuard<'_, InnerState> {
         parking_lot::MutexGuard::map(self.0.lock(), |s| s.as_mut().unwrap())
     }
@@ -313,6 +377,7 @@ impl Fastforwarder {
             current_tick,
             local_player_index: self.local_player_index,
             input_pairs: input_pairs.into_iter().collect(),
+            input_source: None,
             output_pairs: vec![],
             apply_shadow_input,
             match_type: self.match_type,
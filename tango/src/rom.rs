@@ -1,3 +1,4 @@
+pub mod graphics;
 pub mod text;
 
 use byteorder::{ByteOrder, ReadBytesExt};
@@ -133,6 +134,19 @@ pub trait Assets {
     fn num_navis(&self) -> usize {
         0
     }
+    fn graphics_presets(&self) -> Result<Vec<graphics::Preset>, anyhow::Error> {
+        Ok(vec![])
+    }
+    fn text_parse_options(&self) -> &text::ParseOptions;
+    fn text_archives(&self) -> Vec<TextArchive> {
+        vec![]
+    }
+}
+
+pub struct TextArchive {
+    pub name: String,
+    pub address: u32,
+    pub entries: Vec<Vec<text::Part>>,
 }
 
 pub fn bgr555_to_rgba(c: u16) -> image::Rgba<u8> {
@@ -204,6 +218,19 @@ pub fn read_merged_tiles(raw: &[u8], cols: usize) -> Option<PalettedImage> {
     Some(merge_tiles(&tiles, cols))
 }
 
+/// Largest input the LZ77 and RLE headers can hold the size of, which is a 24-bit field.
+const MAX_COMPRESS_LEN: usize = 0xffffff;
+
+fn compress_header(kind: u8, len: usize) -> std::io::Result<[u8; 4]> {
+    if len > MAX_COMPRESS_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("input is too large to compress: {} bytes", len),
+        ));
+    }
+    Ok((kind as u32 | ((len as u32) << 8)).to_le_bytes())
+}
+
 pub fn unlz77(mut r: &[u8]) -> std::io::Result<Vec<u8>> {
     let mut out = vec![];
 
@@ -231,6 +258,12 @@ pub fn unlz77(mut r: &[u8]) -> std::io::Result<Vec<u8>> {
 
             let m = info >> 12;
             let offset = info & 0x0fff;
+            if offset as usize >= out.len() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "back-reference is out of bounds",
+                ));
+            }
 
             for _ in 0..(m + 3) {
                 out.push(out[out.len() - offset as usize - 1]);
@@ -242,6 +275,126 @@ pub fn unlz77(mut r: &[u8]) -> std::io::Result<Vec<u8>> {
     Ok(out)
 }
 
+/// Compresses data in the GBA BIOS LZ77 format, as read by [`unlz77`].
+///
+/// Back-references never point at the immediately preceding byte, so the output is also safe to decompress with the
+/// BIOS's VRAM (16-bit write) routine.
+///
+/// Matches are found by trying every displacement in the 4 KiB window, which costs up to `0x1000 * 0x12` byte
+/// comparisons per input byte. The search stops as soon as it finds a match of the longest encodable length, so
+/// repetitive data compresses quickly, but a few megabytes of noise can take seconds. This is only used offline by
+/// romtool, where that's fine.
+pub fn lz77(buf: &[u8]) -> std::io::Result<Vec<u8>> {
+    const MIN_DISP: usize = 2;
+    const MAX_DISP: usize = 0x1000;
+    const MIN_LEN: usize = 3;
+    const MAX_LEN: usize = 0x12;
+
+    let mut out = vec![];
+    out.extend_from_slice(&compress_header(0x10, buf.len())?);
+
+    let mut i = 0;
+    while i < buf.len() {
+        let flags_index = out.len();
+        out.push(0);
+
+        for bit in 0..8 {
+            if i >= buf.len() {
+                break;
+            }
+
+            let max_len = MAX_LEN.min(buf.len() - i);
+            let (mut best_disp, mut best_len) = (0, 0);
+            for disp in MIN_DISP..=MAX_DISP.min(i) {
+                let len = (0..max_len).take_while(|&j| buf[i + j] == buf[i + j - disp]).count();
+                if len > best_len {
+                    (best_disp, best_len) = (disp, len);
+                    if len == max_len {
+                        break;
+                    }
+                }
+            }
+
+            if best_len < MIN_LEN {
+                out.push(buf[i]);
+                i += 1;
+                continue;
+            }
+
+            out[flags_index] |= 0x80 >> bit;
+            let info = (((best_len - MIN_LEN) as u16) << 12) | (best_disp - 1) as u16;
+            // Big endian, to match unlz77.
+            out.extend_from_slice(&info.to_be_bytes());
+            i += best_len;
+        }
+    }
+
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+    Ok(out)
+}
+
+pub fn unrle(mut r: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = vec![];
+
+    let header = r.read_u32::<byteorder::LittleEndian>()?;
+    if (header & 0xff) != 0x30 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid header"));
+    }
+
+    let n = (header >> 8) as usize;
+    while out.len() < n {
+        let flag = r.read_u8()?;
+        if flag & 0x80 != 0 {
+            let v = r.read_u8()?;
+            out.extend(std::iter::repeat(v).take((flag & 0x7f) as usize + 3));
+        } else {
+            for _ in 0..(flag & 0x7f) as usize + 1 {
+                out.push(r.read_u8()?);
+            }
+        }
+    }
+
+    out.truncate(n);
+    Ok(out)
+}
+
+/// Compresses data in the GBA BIOS run-length format, as read by [`unrle`].
+pub fn rle(buf: &[u8]) -> std::io::Result<Vec<u8>> {
+    const MIN_RUN: usize = 3;
+    const MAX_RUN: usize = 0x82;
+    const MAX_LITERALS: usize = 0x80;
+
+    let mut out = vec![];
+    out.extend_from_slice(&compress_header(0x30, buf.len())?);
+
+    let run_len_at = |i: usize| buf[i..].iter().take(MAX_RUN).take_while(|v| **v == buf[i]).count();
+
+    let mut i = 0;
+    while i < buf.len() {
+        let run_len = run_len_at(i);
+        if run_len >= MIN_RUN {
+            out.push(0x80 | (run_len - MIN_RUN) as u8);
+            out.push(buf[i]);
+            i += run_len;
+            continue;
+        }
+
+        let start = i;
+        while i < buf.len() && i - start < MAX_LITERALS && run_len_at(i) < MIN_RUN {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&buf[start..i]);
+    }
+
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+    Ok(out)
+}
+
 pub struct MemoryMapper {
     rom: Vec<u8>,
     wram: Vec<u8>,
@@ -257,23 +410,98 @@ impl MemoryMapper {
         }
     }
 
+    /// Gets the memory starting at the given address. Addresses with the high bit set point at LZ77 compressed data
+    /// in ROM, which is decompressed on first use.
+    ///
+    /// Panics if the address can't be mapped: use this only with addresses that are known to be good, and
+    /// [`MemoryMapper::try_get`] with addresses read out of the ROM or save.
     pub fn get<'a>(&'a self, start: u32) -> std::borrow::Cow<'a, [u8]> {
-        if start >= 0x02000000 && start < 0x04000000 {
-            std::borrow::Cow::Borrowed(&self.wram[(start & !0x02000000) as usize..])
-        } else if start >= 0x08000000 && start < 0x0a000000 {
-            std::borrow::Cow::Borrowed(&self.rom[(start & !0x08000000) as usize..])
-        } else if start >= 0x88000000 && start <= 0x8a000000 {
-            std::borrow::Cow::Owned(
-                self.unlz77_cache
-                    .lock()
-                    .entry(start)
-                    .or_insert_with(|| unlz77(&self.rom[(start & !0x88000000) as usize..]).unwrap()[4..].to_vec())
-                    .clone(),
-            )
+        self.try_get(start).expect("could not get slice")
+    }
+
+    /// Like [`MemoryMapper::get`], but returns an error if the address is out of range or its data can't be
+    /// decompressed.
+    pub fn try_get<'a>(&'a self, start: u32) -> Result<std::borrow::Cow<'a, [u8]>, anyhow::Error> {
+        fn slice(buf: &[u8], offset: u32, start: u32) -> Result<&[u8], anyhow::Error> {
+            buf.get(offset as usize..)
+                .ok_or_else(|| anyhow::anyhow!("address {:08x} is out of range", start))
+        }
+
+        if (0x02000000..0x04000000).contains(&start) {
+            Ok(std::borrow::Cow::Borrowed(slice(
+                &self.wram,
+                start & !0x02000000,
+                start,
+            )?))
+        } else if (0x08000000..0x0a000000).contains(&start) {
+            Ok(std::borrow::Cow::Borrowed(slice(
+                &self.rom,
+                start & !0x08000000,
+                start,
+            )?))
+        } else if (0x88000000..=0x8a000000).contains(&start) {
+            let mut unlz77_cache = self.unlz77_cache.lock();
+            if let Some(buf) = unlz77_cache.get(&start) {
+                return Ok(std::borrow::Cow::Owned(buf.clone()));
+            }
+            let buf = unlz77(slice(&self.rom, start & !0x88000000, start)?)
+                .map_err(|e| anyhow::anyhow!("failed to decompress data at {:08x}: {}", start, e))?;
+            let buf = buf
+                .get(4..)
+                .ok_or_else(|| anyhow::anyhow!("compressed data at {:08x} is too short", start))?
+                .to_vec();
+            unlz77_cache.insert(start, buf.clone());
+            Ok(std::borrow::Cow::Owned(buf))
         } else {
-            panic!("could not get slice")
+            Err(anyhow::anyhow!("address {:08x} can't be mapped", start))
         }
     }
+
+    /// Reads the address stored at a pointer.
+    pub fn read_pointer(&self, pointer: u32) -> Result<u32, anyhow::Error> {
+        let buf = self.try_get(pointer)?;
+        let buf = buf
+            .get(..4)
+            .ok_or_else(|| anyhow::anyhow!("pointer at {:08x} runs past the end of memory", pointer))?;
+        Ok(byteorder::LittleEndian::read_u32(buf))
+    }
+}
+
+/// Reads the text archive that the pointer at the given address points to.
+pub fn read_text_archive(
+    mapper: &MemoryMapper,
+    options: &text::ParseOptions,
+    name: &str,
+    pointer: u32,
+) -> Result<TextArchive, anyhow::Error> {
+    let address = mapper.read_pointer(pointer)?;
+    let entries = text::parse_archive(&mapper.try_get(address)?, options)
+        .map_err(|e| anyhow::anyhow!("failed to parse text archive at {:08x}: {}", address, e))?;
+    Ok(TextArchive {
+        name: name.to_string(),
+        address,
+        entries,
+    })
+}
+
+/// Reads the text archives that the given named pointers point to, skipping any that can't be read.
+pub fn read_text_archives(
+    mapper: &MemoryMapper,
+    options: &text::ParseOptions,
+    pointers: Vec<(String, u32)>,
+) -> Vec<TextArchive> {
+    pointers
+        .into_iter()
+        .flat_map(
+            |(name, pointer)| match read_text_archive(mapper, options, &name, pointer) {
+                Ok(archive) => Some(archive),
+                Err(e) => {
+                    log::warn!("failed to read text archive {}: {}", name, e);
+                    None
+                }
+            },
+        )
+        .collect()
 }
 
 pub type Scanner = scanner::Scanner<std::collections::HashMap<&'static (dyn game::Game + Send + Sync), Vec<u8>>>;
@@ -352,3 +580,214 @@ pub struct Overrides {
     pub modcard56s: Option<Vec<Modcard56Override>>,
     pub modcard56_effects: Option<Vec<Modcard56EffectOverride>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> Vec<Vec<u8>> {
+        let mut x = 0x2545f491u32;
+        let noise = (0..0x1000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect::<Vec<_>>();
+        vec![
+            vec![],
+            vec![0x42],
+            vec![1, 2],
+            vec![0; 3],
+            vec![0xaa; 0x1000],
+            (0..0x1000).map(|i| (i % 7) as u8).collect(),
+            noise.clone(),
+            noise
+                .chunks(0x100)
+                .enumerate()
+                .flat_map(|(i, chunk)| {
+                    let mut v = chunk.to_vec();
+                    v.extend(std::iter::repeat(i as u8).take(i * 3));
+                    v
+                })
+                .collect(),
+        ]
+    }
+
+    #[test]
+    fn test_lz77_round_trip() {
+        for input in inputs() {
+            let compressed = lz77(&input).unwrap();
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(unlz77(&compressed).unwrap(), input, "len {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_rle_round_trip() {
+        for input in inputs() {
+            let compressed = rle(&input).unwrap();
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(unrle(&compressed).unwrap(), input, "len {}", input.len());
+        }
+    }
+
+    /// Random inputs built to hit the encoders' edge cases: noise, runs longer than the LZ77 window and the RLE run
+    /// limit, small alphabets, and repeats of earlier data both inside and beyond the window.
+    fn random_inputs() -> Vec<Vec<u8>> {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(0x2545f491);
+        (0..64)
+            .map(|_| {
+                let mut input = vec![];
+                let target_len = rng.gen_range(0..0x4000);
+                while input.len() < target_len {
+                    match rng.gen_range(0..5) {
+                        0 => {
+                            let n = rng.gen_range(1..0x100);
+                            input.extend((0..n).map(|_| rng.gen::<u8>()));
+                        }
+                        1 => {
+                            let n = rng.gen_range(1..0x2000);
+                            input.extend(std::iter::repeat(rng.gen::<u8>()).take(n));
+                        }
+                        2 => {
+                            let n = rng.gen_range(1..0x200);
+                            input.extend((0..n).map(|_| rng.gen_range(0..3u8)));
+                        }
+                        _ => {
+                            if input.is_empty() {
+                                continue;
+                            }
+                            let start = rng.gen_range(0..input.len());
+                            let n = rng.gen_range(1..=(input.len() - start).min(0x40));
+                            input.extend_from_within(start..start + n);
+                        }
+                    }
+                }
+                input
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lz77_random_round_trip() {
+        for input in random_inputs() {
+            let compressed = lz77(&input).unwrap();
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(unlz77(&compressed).unwrap(), input, "len {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_lz77_never_refers_to_previous_byte() {
+        for input in random_inputs() {
+            let compressed = lz77(&input).unwrap();
+            let mut r = &compressed[4..];
+            let mut len = 0;
+            while len < input.len() {
+                let flags = r.read_u8().unwrap();
+                for bit in 0..8 {
+                    if len >= input.len() {
+                        break;
+                    }
+                    if flags & (0x80 >> bit) == 0 {
+                        r.read_u8().unwrap();
+                        len += 1;
+                        continue;
+                    }
+                    let info = r.read_u16::<byteorder::BigEndian>().unwrap();
+                    assert_ne!(info & 0x0fff, 0);
+                    len += (info >> 12) as usize + 3;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rle_random_round_trip() {
+        for input in random_inputs() {
+            let compressed = rle(&input).unwrap();
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(unrle(&compressed).unwrap(), input, "len {}", input.len());
+        }
+    }
+
+    #[test]
+    fn test_compress_too_large() {
+        let input = vec![0; MAX_COMPRESS_LEN + 1];
+        assert_eq!(lz77(&input).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(rle(&input).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_memory_mapper_try_get() {
+        let mut rom = vec![0; 0x10];
+        let compressed = lz77(b"\x01\x02\x03\x04hello").unwrap();
+        assert_eq!(compressed.len(), 0x10);
+        rom.extend(&compressed);
+        rom.extend(&compressed[..8]);
+        let mapper = MemoryMapper::new(rom, vec![0; 0x10]);
+
+        assert_eq!(mapper.try_get(0x02000008).unwrap().len(), 0x08);
+        assert_eq!(&mapper.try_get(0x88000010).unwrap()[..], b"hello");
+        assert_eq!(mapper.try_get(0x08000028).unwrap().len(), 0);
+        assert!(mapper.try_get(0x02000011).is_err());
+        assert!(mapper.try_get(0x08000029).is_err());
+        assert!(mapper.try_get(0x00000000).is_err());
+        // Truncated compressed data.
+        assert!(mapper.try_get(0x88000020).is_err());
+        // Not compressed data at all.
+        assert!(mapper.try_get(0x88000000).is_err());
+        assert!(mapper.read_pointer(0x08000026).is_err());
+    }
+
+    #[test]
+    fn test_unlz77_bad_back_reference() {
+        // Copies 3 bytes from 1 byte back before anything has been written.
+        assert_eq!(
+            unlz77(&[0x10, 0x03, 0x00, 0x00, 0x80, 0x00, 0x00]).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_read_text_archive_bad_pointers() {
+        let options = text::ParseOptions {
+            charset: vec!["a".to_string()],
+            extension_ops: 0xe4..=0xe4,
+            eof_op: 0xe6,
+            newline_op: 0xe9,
+            commands: std::collections::HashMap::new(),
+        };
+
+        let mut rom = vec![0; 0x20];
+        rom[0x00..0x04].copy_from_slice(&0x08000010u32.to_le_bytes());
+        rom[0x04..0x08].copy_from_slice(&0x08100000u32.to_le_bytes());
+        rom[0x08..0x0c].copy_from_slice(&0x12345678u32.to_le_bytes());
+        rom[0x10..0x14].copy_from_slice(&[0x02, 0x00, 0x00, 0xe6]);
+        let mapper = MemoryMapper::new(rom, vec![]);
+
+        let archive = read_text_archive(&mapper, &options, "good", 0x08000000).unwrap();
+        assert_eq!(archive.address, 0x08000010);
+        assert_eq!(archive.entries.len(), 1);
+        assert!(read_text_archive(&mapper, &options, "out_of_range", 0x08000004).is_err());
+        assert!(read_text_archive(&mapper, &options, "unmapped", 0x08000008).is_err());
+        assert!(read_text_archive(&mapper, &options, "truncated_pointer", 0x0800001e).is_err());
+
+        let archives = read_text_archives(
+            &mapper,
+            &options,
+            vec![
+                ("good".to_string(), 0x08000000),
+                ("out_of_range".to_string(), 0x08000004),
+            ],
+        );
+        assert_eq!(
+            archives.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+            vec!["good"]
+        );
+    }
+}
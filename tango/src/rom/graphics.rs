@@ -0,0 +1,247 @@
+use byteorder::ByteOrder;
+
+use crate::rom;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bpp {
+    Four,
+    Eight,
+}
+
+impl Bpp {
+    pub fn tile_bytes(&self) -> usize {
+        match self {
+            Bpp::Four => rom::TILE_WIDTH * rom::TILE_HEIGHT / 2,
+            Bpp::Eight => rom::TILE_WIDTH * rom::TILE_HEIGHT,
+        }
+    }
+
+    pub fn num_colors(&self) -> usize {
+        match self {
+            Bpp::Four => 16,
+            Bpp::Eight => 256,
+        }
+    }
+}
+
+/// A run of tiles in memory, along with how to lay them out and color them.
+#[derive(Clone, Debug)]
+pub struct Sheet {
+    pub address: u32,
+    pub compressed: bool,
+    pub num_tiles: usize,
+    pub cols: usize,
+    pub bpp: Bpp,
+
+    /// Address of the palette. If not given, the sheet is rendered in grayscale.
+    pub palette_address: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub name: String,
+    pub sheet: Sheet,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("sheet has no tiles")]
+    Empty,
+
+    #[error("could not read {0} bytes of tile data")]
+    ShortRead(usize),
+
+    #[error("decompression failed: {0}")]
+    Decompress(#[from] std::io::Error),
+}
+
+fn read_colors(raw: &[u8]) -> Vec<image::Rgba<u8>> {
+    [image::Rgba([0, 0, 0, 0])]
+        .into_iter()
+        .chain(
+            raw.chunks(2)
+                .skip(1)
+                .map(|c| rom::bgr555_to_rgba(byteorder::LittleEndian::read_u16(c))),
+        )
+        .collect()
+}
+
+fn grayscale(bpp: Bpp) -> Vec<image::Rgba<u8>> {
+    let n = bpp.num_colors();
+    [image::Rgba([0, 0, 0, 0])]
+        .into_iter()
+        .chain((1..n).map(|i| {
+            let v = (i * 0xff / (n - 1)) as u8;
+            image::Rgba([v, v, v, 0xff])
+        }))
+        .collect()
+}
+
+/// Renders a sheet, reading memory through the given function. The function is passed an address and a length and
+/// may return fewer bytes than asked for if the read runs off the end of memory.
+pub fn render(sheet: &Sheet, read: &mut dyn FnMut(u32, usize) -> Vec<u8>) -> Result<image::RgbaImage, Error> {
+    if sheet.num_tiles == 0 || sheet.cols == 0 {
+        return Err(Error::Empty);
+    }
+
+    let len = sheet.num_tiles * sheet.bpp.tile_bytes();
+    let raw = if sheet.compressed {
+        let header = read(sheet.address, 4);
+        let size = header
+            .get(..4)
+            .map(|header| (byteorder::LittleEndian::read_u32(header) >> 8) as usize)
+            .unwrap_or(0);
+        // Worst case, LZ77 needs one flag byte per eight literals.
+        rom::unlz77(&read(sheet.address, 4 + size + (size + 7) / 8))?
+    } else {
+        read(sheet.address, len)
+    };
+    if raw.len() < len {
+        return Err(Error::ShortRead(len));
+    }
+
+    let palette = if let Some(palette_address) = sheet.palette_address {
+        let mut palette = read_colors(&read(palette_address, sheet.bpp.num_colors() * 2));
+        palette.resize(sheet.bpp.num_colors(), image::Rgba([0, 0, 0, 0xff]));
+        palette
+    } else {
+        grayscale(sheet.bpp)
+    };
+
+    let rows = (sheet.num_tiles + sheet.cols - 1) / sheet.cols;
+    let tiles = raw[..len]
+        .chunks(sheet.bpp.tile_bytes())
+        .map(|raw_tile| {
+            image::ImageBuffer::from_vec(
+                rom::TILE_WIDTH as u32,
+                rom::TILE_HEIGHT as u32,
+                match sheet.bpp {
+                    Bpp::Four => raw_tile.iter().flat_map(|v| [v & 0xf, v >> 4]).collect(),
+                    Bpp::Eight => raw_tile.to_vec(),
+                },
+            )
+            .unwrap()
+        })
+        .chain(std::iter::repeat_with(|| {
+            image::ImageBuffer::new(rom::TILE_WIDTH as u32, rom::TILE_HEIGHT as u32)
+        }))
+        .take(rows * sheet.cols)
+        .collect::<Vec<_>>();
+    let paletted = rom::merge_tiles(&tiles, sheet.cols);
+
+    Ok(image::ImageBuffer::from_vec(
+        paletted.width(),
+        paletted.height(),
+        paletted.into_iter().flat_map(|v| palette[*v as usize].0).collect(),
+    )
+    .unwrap())
+}
+
+/// Reads the address stored at a pointer in ROM, failing if the pointer can't be mapped.
+pub fn deref(mapper: &rom::MemoryMapper, pointer: u32) -> Result<u32, anyhow::Error> {
+    mapper.read_pointer(pointer)
+}
+
+/// Builds presets for the chip icons and artwork, which are laid out the same way in every game: the chip data
+/// contains pointers to the icon, the artwork and the artwork's palette, one after another.
+pub fn chip_presets(
+    mapper: &rom::MemoryMapper,
+    chip_data: u32,
+    chip_data_size: usize,
+    num_chips: usize,
+    graphics_pointers_offset: usize,
+    image_size: (usize, usize),
+    icon_palette_address: u32,
+) -> Result<Vec<Preset>, anyhow::Error> {
+    if graphics_pointers_offset + 12 > chip_data_size {
+        anyhow::bail!("chip graphics pointers don't fit in the chip data");
+    }
+    let chip_data_address = chip_data;
+    let chip_data = mapper.try_get(chip_data_address)?;
+    let chip_data = chip_data.get(..num_chips * chip_data_size).ok_or_else(|| {
+        anyhow::anyhow!(
+            "chip data for {} chips at {:08x} runs past the end of memory",
+            num_chips,
+            chip_data_address
+        )
+    })?;
+    Ok((0..num_chips)
+        .flat_map(|id| {
+            let raw = &chip_data[id * chip_data_size..(id + 1) * chip_data_size];
+            let pointers = &raw[graphics_pointers_offset..graphics_pointers_offset + 12];
+            let (image_cols, image_rows) = image_size;
+            [
+                Preset {
+                    name: format!("chip_icon_{}", id),
+                    sheet: Sheet {
+                        address: byteorder::LittleEndian::read_u32(&pointers[0..4]),
+                        compressed: false,
+                        num_tiles: 4,
+                        cols: 2,
+                        bpp: Bpp::Four,
+                        palette_address: Some(icon_palette_address),
+                    },
+                },
+                Preset {
+                    name: format!("chip_image_{}", id),
+                    sheet: Sheet {
+                        address: byteorder::LittleEndian::read_u32(&pointers[4..8]),
+                        compressed: false,
+                        num_tiles: image_cols * image_rows,
+                        cols: image_cols,
+                        bpp: Bpp::Four,
+                        palette_address: Some(byteorder::LittleEndian::read_u32(&pointers[8..12])),
+                    },
+                },
+            ]
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deref() {
+        let mut rom = vec![0; 0x0a];
+        rom[0x04..0x08].copy_from_slice(&0x08001234u32.to_le_bytes());
+        let mapper = rom::MemoryMapper::new(rom, vec![]);
+        assert_eq!(deref(&mapper, 0x08000004).unwrap(), 0x08001234);
+        assert!(deref(&mapper, 0x08000008).is_err());
+        assert!(deref(&mapper, 0x08100000).is_err());
+        assert!(deref(&mapper, 0x12345678).is_err());
+    }
+
+    #[test]
+    fn test_chip_presets() {
+        let mut rom = vec![0; 0x20];
+        for (i, pointer) in [0x08001000u32, 0x08002000, 0x08003000].into_iter().enumerate() {
+            rom[0x14 + i * 4..0x18 + i * 4].copy_from_slice(&pointer.to_le_bytes());
+        }
+        let mapper = rom::MemoryMapper::new(rom, vec![]);
+
+        let presets = chip_presets(&mapper, 0x08000010, 0x10, 1, 0x04, (2, 3), 0x08004000).unwrap();
+        assert_eq!(
+            presets
+                .iter()
+                .map(|preset| (
+                    preset.name.as_str(),
+                    preset.sheet.address,
+                    preset.sheet.num_tiles,
+                    preset.sheet.palette_address
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("chip_icon_0", 0x08001000, 4, Some(0x08004000)),
+                ("chip_image_0", 0x08002000, 6, Some(0x08003000)),
+            ]
+        );
+
+        // Runs off the end of the ROM.
+        assert!(chip_presets(&mapper, 0x08000010, 0x10, 2, 0x04, (2, 3), 0x08004000).is_err());
+        // Pointers don't fit in a chip's data.
+        assert!(chip_presets(&mapper, 0x08000010, 0x10, 1, 0x08, (2, 3), 0x08004000).is_err());
+        assert!(chip_presets(&mapper, 0x08100000, 0x10, 1, 0x04, (2, 3), 0x08004000).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use std::io::Read;
 use crate::rom;
 use byteorder::{ByteOrder, ReadBytesExt};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Part {
     String(String),
     Command { op: u8, params: Vec<u8> },
@@ -75,6 +75,158 @@ pub fn parse_entry(buf: &[u8], i: usize, options: &ParseOptions) -> Result<Vec<P
     )
 }
 
+/// Parses every entry of a text archive. The number of entries is taken from the first offset in the archive's offset
+/// table, which always points just past the end of the table.
+pub fn parse_archive(buf: &[u8], options: &ParseOptions) -> Result<Vec<Vec<Part>>, std::io::Error> {
+    let first_offset = byteorder::LittleEndian::read_u16(buf.get(..2).ok_or(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "could not read offset table",
+    ))?) as usize;
+    if first_offset > buf.len() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "offset table is out of bounds",
+        ));
+    }
+    let count = first_offset / 2;
+    (0..count)
+        .map(|i| {
+            if i + 1 < count {
+                parse_entry(buf, i, options)
+            } else {
+                let offset = byteorder::LittleEndian::read_u16(&buf[i * 2..(i + 1) * 2]) as usize;
+                parse(
+                    &buf.get(offset..).ok_or(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "could not read entry",
+                    ))?,
+                    options,
+                )
+            }
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EncodeError {
+    #[error("cannot encode {0:?} with this charset")]
+    UnencodableString(String),
+
+    #[error("unknown command: {0:02x}")]
+    UnknownCommand(u8),
+
+    #[error("command {op:02x} takes {expected} params, got {actual}")]
+    InvalidCommandParams { op: u8, expected: usize, actual: usize },
+
+    #[error("archive is too large: {0} bytes")]
+    ArchiveTooLarge(usize),
+}
+
+/// Maps charset entries back to their codes, keeping the first code for entries that appear more than once.
+fn reverse_charset(options: &ParseOptions) -> std::collections::HashMap<&str, usize> {
+    let mut reverse = std::collections::HashMap::new();
+    for (c, s) in options.charset.iter().enumerate() {
+        reverse.entry(s.as_str()).or_insert(c);
+    }
+    reverse
+}
+
+fn encode_char(c: usize, options: &ParseOptions, out: &mut Vec<u8>) -> bool {
+    if let Ok(op) = u8::try_from(c) {
+        if !options.extension_ops.contains(&op)
+            && op != options.eof_op
+            && op != options.newline_op
+            && !options.commands.contains_key(&op)
+        {
+            out.push(op);
+            return true;
+        }
+    }
+
+    for op in options.extension_ops.clone() {
+        if let Some(rest) = c.checked_sub(op as usize).and_then(|rest| u8::try_from(rest).ok()) {
+            out.push(op);
+            out.push(rest);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Encodes parts into the game's script format. This is the inverse of [`parse`], except that the EOF op is not
+/// written.
+///
+/// Charset entries that span several characters (e.g. `[MB]`) are matched greedily, longest first.
+pub fn encode(parts: &[Part], options: &ParseOptions) -> Result<Vec<u8>, EncodeError> {
+    let reverse = reverse_charset(options);
+    let max_len = options.charset.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+
+    let mut out = vec![];
+    for part in parts {
+        match part {
+            Part::String(s) => {
+                let chars = s.chars().collect::<Vec<_>>();
+                let mut i = 0;
+                while i < chars.len() {
+                    if chars[i] == '\n' {
+                        out.push(options.newline_op);
+                        i += 1;
+                        continue;
+                    }
+
+                    let (n, c) = (1..=max_len.min(chars.len() - i))
+                        .rev()
+                        .find_map(|n| {
+                            reverse
+                                .get(chars[i..i + n].iter().collect::<String>().as_str())
+                                .map(|c| (n, *c))
+                        })
+                        .ok_or_else(|| EncodeError::UnencodableString(chars[i..].iter().collect()))?;
+
+                    if !encode_char(c, options, &mut out) {
+                        return Err(EncodeError::UnencodableString(chars[i..i + n].iter().collect()));
+                    }
+                    i += n;
+                }
+            }
+            Part::Command { op, params } => {
+                let expected = *options.commands.get(op).ok_or(EncodeError::UnknownCommand(*op))?;
+                if params.len() != expected {
+                    return Err(EncodeError::InvalidCommandParams {
+                        op: *op,
+                        expected,
+                        actual: params.len(),
+                    });
+                }
+                out.push(*op);
+                out.extend_from_slice(params);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Builds a text archive from entries, with a fresh offset table. Each entry is terminated with the EOF op.
+pub fn build_archive(entries: &[Vec<Part>], options: &ParseOptions) -> Result<Vec<u8>, EncodeError> {
+    let mut offsets = Vec::with_capacity(entries.len());
+    let mut body = vec![];
+    let table_size = entries.len() * 2;
+    for parts in entries {
+        offsets.push(table_size + body.len());
+        body.extend(encode(parts, options)?);
+        body.push(options.eof_op);
+    }
+
+    let mut out = Vec::with_capacity(table_size + body.len());
+    for offset in offsets {
+        let offset = u16::try_from(offset).map_err(|_| EncodeError::ArchiveTooLarge(table_size + body.len()))?;
+        out.extend_from_slice(&offset.to_le_bytes());
+    }
+    out.extend(body);
+    Ok(out)
+}
+
 pub fn parse_modcard56_effect(parts: Vec<Part>, print_var_command: u8) -> rom::Modcard56EffectTemplate {
     parts
         .into_iter()
@@ -87,3 +239,111 @@ pub fn parse_modcard56_effect(parts: Vec<Part>, print_var_command: u8) -> rom::M
         })
         .collect()
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Checks that every charset entry and every command of a game's text format survive encoding and parsing.
+    pub fn assert_round_trips(options: &ParseOptions) {
+        // The last extension op followed by 0xff is the highest code the parser can produce, so any entries past it
+        // never show up in text.
+        let reachable = *options.extension_ops.end() as usize + 0x100;
+        let charset = &options.charset[..options.charset.len().min(reachable)];
+
+        for (c, s) in charset.iter().enumerate() {
+            let parts = vec![Part::String(s.clone())];
+            let encoded = encode(&parts, options).unwrap_or_else(|e| panic!("entry {:02x} ({:?}): {}", c, s, e));
+            assert_eq!(parse(&encoded, options).unwrap(), parts, "entry {:02x} ({:?})", c, s);
+        }
+
+        let all = charset.concat();
+        let parts = vec![Part::String(format!("{}\n{}", all, all))];
+        assert_eq!(parse(&encode(&parts, options).unwrap(), options).unwrap(), parts);
+
+        let s = Part::String(options.charset[1].clone());
+        for (op, len) in options.commands.iter() {
+            let parts = vec![
+                s.clone(),
+                Part::Command {
+                    op: *op,
+                    params: (0..*len as u8).map(|i| i + 1).collect(),
+                },
+                s.clone(),
+            ];
+            assert_eq!(
+                parse(&encode(&parts, options).unwrap(), options).unwrap(),
+                parts,
+                "command {:02x}",
+                op
+            );
+        }
+
+        let entries = charset
+            .iter()
+            .map(|s| vec![Part::String(s.clone())])
+            .chain(std::iter::once(vec![]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parse_archive(&build_archive(&entries, options).unwrap(), options).unwrap(),
+            entries
+        );
+    }
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            charset: ["a", "b", "[MB]"].iter().map(|s| s.to_string()).collect(),
+            extension_ops: 0xe4..=0xe4,
+            eof_op: 0xe6,
+            newline_op: 0xe9,
+            commands: std::collections::HashMap::from([(0xe7, 1)]),
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let options = options();
+        assert_eq!(
+            encode(&[Part::String("ab\n[MB]".to_string())], &options).unwrap(),
+            vec![0x00, 0x01, 0xe9, 0x02]
+        );
+        assert_round_trips(&options);
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let options = options();
+        assert!(matches!(
+            encode(&[Part::String("c".to_string())], &options),
+            Err(EncodeError::UnencodableString(s)) if s == "c"
+        ));
+        assert!(matches!(
+            encode(
+                &[Part::Command {
+                    op: 0xe8,
+                    params: vec![]
+                }],
+                &options
+            ),
+            Err(EncodeError::UnknownCommand(0xe8))
+        ));
+        assert!(matches!(
+            encode(
+                &[Part::Command {
+                    op: 0xe7,
+                    params: vec![]
+                }],
+                &options
+            ),
+            Err(EncodeError::InvalidCommandParams {
+                op: 0xe7,
+                expected: 1,
+                actual: 0
+            })
+        ));
+        assert!(matches!(
+            build_archive(&vec![vec![Part::String("a".repeat(0x10000))]; 2], &options),
+            Err(EncodeError::ArchiveTooLarge(_))
+        ));
+    }
+}
@@ -0,0 +1,794 @@
+use crate::{config, game, patch, rom};
+
+/// Size of the WRAM buffer handed to `Game::load_rom_assets` when there is no save to take it from. Only text that
+/// interpolates save data (e.g. the player's name) reads from it.
+const WRAM_SIZE: usize = 0x40000;
+
+#[derive(clap::Args)]
+pub struct RomArgs {
+    /// Directory to look for ROMs in. Defaults to the ROMs directory in the data path.
+    #[arg(long)]
+    roms_path: Option<std::path::PathBuf>,
+
+    /// Name of a patch to apply first. ROMs the patch does not support are skipped.
+    #[arg(long)]
+    patch: Option<String>,
+
+    /// Version of the patch to apply. Defaults to the latest version.
+    #[arg(long, requires = "patch")]
+    patch_version: Option<semver::Version>,
+
+    /// Path to a TOML file of ROM overrides, in the same format as `rom_overrides` in a patch's info.toml. Takes
+    /// precedence over the patch's own overrides.
+    #[arg(long)]
+    overrides_path: Option<std::path::PathBuf>,
+
+    /// Only use the ROM for this game, given as family and variant (e.g. bn6_0).
+    #[arg(long)]
+    game: Option<String>,
+}
+
+fn parse_address(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum Bpp {
+    #[value(name = "4")]
+    Four,
+    #[value(name = "8")]
+    Eight,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum CompressionFormat {
+    Lz77,
+    Rle,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Dump the chips, navicust parts, modcards, styles and navis of every ROM to JSON and PNG files.
+    DumpChips {
+        output_path: std::path::PathBuf,
+
+        #[command(flatten)]
+        rom_args: RomArgs,
+    },
+
+    /// Extract the text archives of every ROM to JSON.
+    ExtractText {
+        output_path: std::path::PathBuf,
+
+        #[command(flatten)]
+        rom_args: RomArgs,
+    },
+
+    /// Check that every text archive of every ROM survives being re-encoded and decoded again.
+    VerifyText {
+        #[command(flatten)]
+        rom_args: RomArgs,
+    },
+
+    /// Search the text archives of every ROM for a string, ignoring case.
+    SearchText {
+        query: String,
+
+        #[command(flatten)]
+        rom_args: RomArgs,
+    },
+
+    /// Render tiles from a ROM to PNG. Without an address, every known graphics table of every ROM is exported.
+    ExportGraphics {
+        output_path: std::path::PathBuf,
+
+        #[command(flatten)]
+        rom_args: RomArgs,
+
+        /// Address of the tile data, in hex (e.g. 0x08123456).
+        #[arg(long, value_parser = parse_address)]
+        address: Option<u32>,
+
+        /// Whether the tile data is LZ77-compressed.
+        #[arg(long, requires = "address")]
+        compressed: bool,
+
+        #[arg(long, default_value_t = 1, requires = "address")]
+        num_tiles: usize,
+
+        /// Number of tiles per row.
+        #[arg(long, default_value_t = 1, requires = "address")]
+        cols: usize,
+
+        #[arg(long, value_enum, default_value_t = Bpp::Four, requires = "address")]
+        bpp: Bpp,
+
+        /// Address of the palette, in hex. Without a palette, tiles are rendered in grayscale.
+        #[arg(long, value_parser = parse_address, requires = "address")]
+        palette_address: Option<u32>,
+    },
+
+    /// Compress a file in a GBA BIOS compression format.
+    Compress {
+        #[arg(value_enum)]
+        format: CompressionFormat,
+        input_path: std::path::PathBuf,
+        output_path: std::path::PathBuf,
+    },
+
+    /// Decompress a file in a GBA BIOS compression format.
+    Decompress {
+        #[arg(value_enum)]
+        format: CompressionFormat,
+        input_path: std::path::PathBuf,
+        output_path: std::path::PathBuf,
+    },
+}
+
+pub fn main(config: config::Config, command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::DumpChips { output_path, rom_args } => cmd_dump_chips(config, output_path, rom_args),
+        Command::ExtractText { output_path, rom_args } => cmd_extract_text(config, output_path, rom_args),
+        Command::VerifyText { rom_args } => cmd_verify_text(config, rom_args),
+        Command::SearchText { query, rom_args } => cmd_search_text(config, query, rom_args),
+        Command::ExportGraphics {
+            output_path,
+            rom_args,
+            address,
+            compressed,
+            num_tiles,
+            cols,
+            bpp,
+            palette_address,
+        } => cmd_export_graphics(
+            config,
+            output_path,
+            rom_args,
+            address.map(|address| rom::graphics::Sheet {
+                address,
+                compressed,
+                num_tiles,
+                cols,
+                bpp: match bpp {
+                    Bpp::Four => rom::graphics::Bpp::Four,
+                    Bpp::Eight => rom::graphics::Bpp::Eight,
+                },
+                palette_address,
+            }),
+        ),
+        Command::Compress {
+            format,
+            input_path,
+            output_path,
+        } => cmd_compress(format, input_path, output_path),
+        Command::Decompress {
+            format,
+            input_path,
+            output_path,
+        } => cmd_decompress(format, input_path, output_path),
+    }
+}
+
+struct LoadedRom {
+    game: &'static (dyn game::Game + Send + Sync),
+    rom: Vec<u8>,
+    patch: Option<Patch>,
+    assets: Box<dyn rom::Assets + Send + Sync>,
+}
+
+impl LoadedRom {
+    /// Name of the directory to write this ROM's output to.
+    fn dir_name(&self) -> String {
+        let (family, variant) = self.game.family_and_variant();
+        if let Some(patch) = self.patch.as_ref() {
+            format!("{}_{}_{}_v{}", family, variant, patch.name, patch.version)
+        } else {
+            format!("{}_{}", family, variant)
+        }
+    }
+
+    fn game(&self) -> Game {
+        let (family, variant) = self.game.family_and_variant();
+        let (rom_code, revision) = self.game.rom_code_and_revision();
+        Game {
+            family: family.to_string(),
+            variant,
+            rom_code: String::from_utf8_lossy(rom_code).to_string(),
+            revision,
+        }
+    }
+}
+
+/// Loads the assets of every ROM, applying the patch and overrides from the arguments.
+fn load_roms(config: &config::Config, rom_args: RomArgs) -> Result<Vec<LoadedRom>, anyhow::Error> {
+    let patch = if let Some(patch_name) = rom_args.patch {
+        let patches = patch::scan(&config.patches_path())?;
+        let patch = patches
+            .get(&patch_name)
+            .ok_or_else(|| anyhow::anyhow!("no such patch: {}", patch_name))?;
+        let version = if let Some(version) = rom_args.patch_version {
+            version
+        } else {
+            patch
+                .versions
+                .keys()
+                .max()
+                .ok_or_else(|| anyhow::anyhow!("patch {} has no versions", patch_name))?
+                .clone()
+        };
+        let version_metadata = patch
+            .versions
+            .get(&version)
+            .ok_or_else(|| anyhow::anyhow!("patch {} has no version {}", patch_name, version))?
+            .clone();
+        let chain = patch::resolve_chain(&patches, &patch_name, &version)
+            .ok_or_else(|| anyhow::anyhow!("could not resolve base patches of {} v{}", patch_name, version))?;
+        Some((patch_name, version, version_metadata, chain))
+    } else {
+        None
+    };
+
+    let overrides_from_file = if let Some(overrides_path) = rom_args.overrides_path {
+        Some(toml::from_str::<rom::Overrides>(&std::fs::read_to_string(
+            &overrides_path,
+        )?)?)
+    } else {
+        None
+    };
+
+    let roms = game::scan_roms(&rom_args.roms_path.unwrap_or_else(|| config.roms_path()));
+    if roms.is_empty() {
+        anyhow::bail!("no roms found");
+    }
+
+    let mut games = roms
+        .keys()
+        .copied()
+        .filter(|game| {
+            let (family, variant) = game.family_and_variant();
+            rom_args
+                .game
+                .as_ref()
+                .map(|name| *name == format!("{}_{}", family, variant))
+                .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+    if games.is_empty() {
+        anyhow::bail!("no roms found for {}", rom_args.game.unwrap_or_default());
+    }
+    games.sort_by_key(|game| game.family_and_variant());
+
+    let mut loaded = vec![];
+    for game in games {
+        let (family, variant) = game.family_and_variant();
+        let mut rom = roms.get(&game).unwrap().clone();
+
+        let mut overrides = rom::Overrides::default();
+        let mut patch_output = None;
+        if let Some((patch_name, version, version_metadata, chain)) = patch.as_ref() {
+            if !version_metadata.supported_games.contains(&game) {
+                eprintln!(
+                    "patch {} v{} does not support {:?}, skipping",
+                    patch_name,
+                    version,
+                    (family, variant)
+                );
+                continue;
+            }
+
+            rom = patch::apply_patch_chain_from_disk(&rom, game, &config.patches_path(), chain)?;
+            overrides = version_metadata.rom_overrides.clone();
+            patch_output = Some(Patch {
+                name: patch_name.clone(),
+                version: version.to_string(),
+            });
+        }
+        if let Some(overrides_from_file) = overrides_from_file.as_ref() {
+            overrides = overrides_from_file.clone();
+        }
+
+        let assets = match game.load_rom_assets(&rom, &vec![0u8; WRAM_SIZE], &overrides) {
+            Ok(assets) => assets,
+            Err(e) => {
+                eprintln!("failed to load assets for {:?}: {}", (family, variant), e);
+                continue;
+            }
+        };
+
+        loaded.push(LoadedRom {
+            game,
+            rom,
+            patch: patch_output,
+            assets,
+        });
+    }
+
+    Ok(loaded)
+}
+
+#[derive(serde::Serialize)]
+struct Game {
+    family: String,
+    variant: u8,
+    rom_code: String,
+    revision: u8,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct Patch {
+    name: String,
+    version: String,
+}
+
+#[derive(serde::Serialize)]
+struct Chip {
+    id: usize,
+    name: String,
+    description: String,
+    codes: Vec<char>,
+    element: usize,
+    class: rom::ChipClass,
+    dark: bool,
+    mb: u8,
+    damage: u32,
+    icon: String,
+    image: String,
+}
+
+#[derive(serde::Serialize)]
+struct NavicustPart {
+    id: usize,
+    variant: usize,
+    name: String,
+    description: String,
+    color: Option<rom::NavicustPartColor>,
+    is_solid: bool,
+    compressed_bitmap: String,
+    uncompressed_bitmap: String,
+}
+
+#[derive(serde::Serialize)]
+struct Modcard56Effect {
+    id: u8,
+    name: String,
+    parameter: u8,
+    is_ability: bool,
+    is_debuff: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Modcard56 {
+    id: usize,
+    name: String,
+    mb: u8,
+    effects: Vec<Modcard56Effect>,
+}
+
+#[derive(serde::Serialize)]
+struct Modcard4 {
+    id: usize,
+    name: String,
+    slot: u8,
+    effect: String,
+    bug: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct Style {
+    id: usize,
+    name: String,
+    extra_ncp_color: Option<rom::NavicustPartColor>,
+}
+
+#[derive(serde::Serialize)]
+struct Navi {
+    id: usize,
+    name: String,
+    emblem: String,
+}
+
+#[derive(serde::Serialize)]
+struct Output {
+    game: Game,
+    patch: Option<Patch>,
+    elements: Vec<String>,
+    chips: Vec<Chip>,
+    navicust_parts: Vec<NavicustPart>,
+    modcard56s: Vec<Modcard56>,
+    modcard4s: Vec<Modcard4>,
+    styles: Vec<Style>,
+    navis: Vec<Navi>,
+}
+
+/// Saves an image under the output directory and returns its path relative to it, for referencing from the JSON.
+fn save_image<P, C>(
+    output_path: &std::path::Path,
+    relative_path: &str,
+    image: &image::ImageBuffer<P, C>,
+) -> Result<String, anyhow::Error>
+where
+    P: image::PixelWithColorType,
+    [P::Subpixel]: image::EncodableLayout,
+    C: std::ops::Deref<Target = [P::Subpixel]>,
+{
+    let path = output_path.join(relative_path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    image.save(&path)?;
+    Ok(relative_path.to_string())
+}
+
+fn dump_assets(output_path: &std::path::Path, loaded: &LoadedRom) -> Result<Output, anyhow::Error> {
+    let assets = &loaded.assets;
+
+    let elements = (0..)
+        .map_while(|id| assets.element_icon(id).map(|icon| (id, icon)))
+        .map(|(id, icon)| save_image(output_path, &format!("elements/{}.png", id), &icon))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let chips = (0..assets.num_chips())
+        .flat_map(|id| assets.chip(id).map(|info| (id, info)))
+        .map(|(id, info)| {
+            Ok(Chip {
+                id,
+                name: info.name(),
+                description: info.description(),
+                codes: info.codes(),
+                element: info.element(),
+                class: info.class(),
+                dark: info.dark(),
+                mb: info.mb(),
+                damage: info.damage(),
+                icon: save_image(output_path, &format!("chips/{}_icon.png", id), &info.icon())?,
+                image: save_image(output_path, &format!("chips/{}.png", id), &info.image())?,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let (num_navicust_parts, num_navicust_part_variants) = assets.num_navicust_parts();
+    let navicust_parts = (0..num_navicust_parts)
+        .flat_map(|id| (0..num_navicust_part_variants).map(move |variant| (id, variant)))
+        .flat_map(|(id, variant)| assets.navicust_part(id, variant).map(|info| (id, variant, info)))
+        .map(|(id, variant, info)| {
+            Ok(NavicustPart {
+                id,
+                variant,
+                name: info.name(),
+                description: info.description(),
+                color: info.color(),
+                is_solid: info.is_solid(),
+                compressed_bitmap: save_image(
+                    output_path,
+                    &format!("navicust_parts/{}_{}_compressed.png", id, variant),
+                    &info.compressed_bitmap(),
+                )?,
+                uncompressed_bitmap: save_image(
+                    output_path,
+                    &format!("navicust_parts/{}_{}.png", id, variant),
+                    &info.uncompressed_bitmap(),
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let modcard56s = (0..assets.num_modcard56s())
+        .flat_map(|id| assets.modcard56(id).map(|info| (id, info)))
+        .map(|(id, info)| Modcard56 {
+            id,
+            name: info.name(),
+            mb: info.mb(),
+            effects: info
+                .effects()
+                .into_iter()
+                .map(|effect| Modcard56Effect {
+                    id: effect.id,
+                    name: effect.name,
+                    parameter: effect.parameter,
+                    is_ability: effect.is_ability,
+                    is_debuff: effect.is_debuff,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let modcard4s = (0..assets.num_modcard4s())
+        .flat_map(|id| assets.modcard4(id).map(|info| (id, info)))
+        .map(|(id, info)| Modcard4 {
+            id,
+            name: info.name(),
+            slot: info.slot(),
+            effect: info.effect(),
+            bug: info.bug(),
+        })
+        .collect();
+
+    let styles = (0..assets.num_styles())
+        .flat_map(|id| assets.style(id).map(|info| (id, info)))
+        .map(|(id, info)| Style {
+            id,
+            name: info.name(),
+            extra_ncp_color: info.extra_ncp_color(),
+        })
+        .collect();
+
+    let navis = (0..assets.num_navis())
+        .flat_map(|id| assets.navi(id).map(|info| (id, info)))
+        .map(|(id, info)| {
+            Ok(Navi {
+                id,
+                name: info.name(),
+                emblem: save_image(output_path, &format!("navis/{}.png", id), &info.emblem())?,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    Ok(Output {
+        game: loaded.game(),
+        patch: loaded.patch.clone(),
+        elements,
+        chips,
+        navicust_parts,
+        modcard56s,
+        modcard4s,
+        styles,
+        navis,
+    })
+}
+
+fn cmd_dump_chips(
+    config: config::Config,
+    output_path: std::path::PathBuf,
+    rom_args: RomArgs,
+) -> Result<(), anyhow::Error> {
+    for loaded in load_roms(&config, rom_args)? {
+        let game_output_path = output_path.join(loaded.dir_name());
+        std::fs::create_dir_all(&game_output_path)?;
+        let output = dump_assets(&game_output_path, &loaded)?;
+        serde_json::to_writer_pretty(std::fs::File::create(game_output_path.join("assets.json"))?, &output)?;
+        eprintln!(
+            "dumped {} chips for {:?} to {}",
+            output.chips.len(),
+            loaded.game.family_and_variant(),
+            game_output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TextPart {
+    String { text: String },
+    Command { op: u8, params: Vec<u8> },
+}
+
+#[derive(serde::Serialize)]
+struct TextArchive {
+    name: String,
+    address: String,
+    entries: Vec<Vec<TextPart>>,
+}
+
+#[derive(serde::Serialize)]
+struct TextOutput {
+    game: Game,
+    patch: Option<Patch>,
+    archives: Vec<TextArchive>,
+}
+
+fn cmd_extract_text(
+    config: config::Config,
+    output_path: std::path::PathBuf,
+    rom_args: RomArgs,
+) -> Result<(), anyhow::Error> {
+    for loaded in load_roms(&config, rom_args)? {
+        let game_output_path = output_path.join(loaded.dir_name());
+        std::fs::create_dir_all(&game_output_path)?;
+        let archives = loaded.assets.text_archives();
+        let num_archives = archives.len();
+        let output = TextOutput {
+            game: loaded.game(),
+            patch: loaded.patch,
+            archives: archives
+                .into_iter()
+                .map(|archive| TextArchive {
+                    name: archive.name,
+                    address: format!("{:08x}", archive.address),
+                    entries: archive
+                        .entries
+                        .into_iter()
+                        .map(|parts| {
+                            parts
+                                .into_iter()
+                                .map(|part| match part {
+                                    rom::text::Part::String(text) => TextPart::String { text },
+                                    rom::text::Part::Command { op, params } => TextPart::Command { op, params },
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                })
+                .collect(),
+        };
+        serde_json::to_writer_pretty(std::fs::File::create(game_output_path.join("text.json"))?, &output)?;
+        eprintln!(
+            "extracted {} text archives for {:?} to {}",
+            num_archives,
+            loaded.game.family_and_variant(),
+            game_output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_verify_text(config: config::Config, rom_args: RomArgs) -> Result<(), anyhow::Error> {
+    let mut num_failures = 0;
+    for loaded in load_roms(&config, rom_args)? {
+        let options = loaded.assets.text_parse_options();
+        for archive in loaded.assets.text_archives() {
+            let result = rom::text::build_archive(&archive.entries, options)
+                .map_err(anyhow::Error::from)
+                .and_then(|buf| Ok(rom::text::parse_archive(&buf, options)?));
+            match result {
+                Ok(entries) if entries == archive.entries => {}
+                Ok(entries) => {
+                    num_failures += 1;
+                    for (i, (expected, actual)) in archive.entries.iter().zip(entries.iter()).enumerate() {
+                        if expected != actual {
+                            eprintln!(
+                                "{}: {}: entry {} does not round-trip: expected {:?}, got {:?}",
+                                loaded.dir_name(),
+                                archive.name,
+                                i,
+                                expected,
+                                actual
+                            );
+                        }
+                    }
+                    if entries.len() != archive.entries.len() {
+                        eprintln!(
+                            "{}: {}: expected {} entries, got {}",
+                            loaded.dir_name(),
+                            archive.name,
+                            archive.entries.len(),
+                            entries.len()
+                        );
+                    }
+                }
+                Err(e) => {
+                    num_failures += 1;
+                    eprintln!("{}: {}: {}", loaded.dir_name(), archive.name, e);
+                }
+            }
+        }
+    }
+
+    if num_failures > 0 {
+        anyhow::bail!("{} text archives did not round-trip", num_failures);
+    }
+    eprintln!("all text archives round-trip");
+    Ok(())
+}
+
+fn cmd_search_text(config: config::Config, query: String, rom_args: RomArgs) -> Result<(), anyhow::Error> {
+    let query = query.to_lowercase();
+    for loaded in load_roms(&config, rom_args)? {
+        let dir_name = loaded.dir_name();
+        for archive in loaded.assets.text_archives() {
+            for (i, parts) in archive.entries.into_iter().enumerate() {
+                let text = parts
+                    .into_iter()
+                    .flat_map(|part| match part {
+                        rom::text::Part::String(s) => Some(s),
+                        _ => None,
+                    })
+                    .collect::<String>();
+                if text.to_lowercase().contains(&query) {
+                    println!("{}\t{}\t{}\t{}", dir_name, archive.name, i, text.replace('\n', "\\n"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_export_graphics(
+    config: config::Config,
+    output_path: std::path::PathBuf,
+    rom_args: RomArgs,
+    sheet: Option<rom::graphics::Sheet>,
+) -> Result<(), anyhow::Error> {
+    let loaded_roms = load_roms(&config, rom_args)?;
+    if sheet.is_some() && loaded_roms.len() > 1 {
+        anyhow::bail!("more than one rom found, pick one with --game");
+    }
+
+    for loaded in loaded_roms {
+        let game_output_path = output_path.join(loaded.dir_name());
+        std::fs::create_dir_all(&game_output_path)?;
+
+        let mut read = |address: u32, len: usize| {
+            if address < 0x08000000 || address >= 0x0a000000 {
+                return vec![];
+            }
+            let start = (address & !0x08000000) as usize;
+            loaded
+                .rom
+                .get(start..(start + len).min(loaded.rom.len()))
+                .map(|buf| buf.to_vec())
+                .unwrap_or_default()
+        };
+
+        let presets = if let Some(sheet) = sheet.clone() {
+            vec![rom::graphics::Preset {
+                name: format!("{:08x}", sheet.address),
+                sheet,
+            }]
+        } else {
+            match loaded.assets.graphics_presets() {
+                Ok(presets) => presets,
+                Err(e) => {
+                    eprintln!("failed to read graphics presets for {}: {}", loaded.dir_name(), e);
+                    continue;
+                }
+            }
+        };
+
+        let mut num_exported = 0;
+        for preset in presets {
+            let image = match rom::graphics::render(&preset.sheet, &mut read) {
+                Ok(image) => image,
+                Err(e) => {
+                    eprintln!("failed to render {}: {}", preset.name, e);
+                    continue;
+                }
+            };
+            image.save(game_output_path.join(format!("{}.png", preset.name)))?;
+            num_exported += 1;
+        }
+        eprintln!(
+            "exported {} images for {:?} to {}",
+            num_exported,
+            loaded.game.family_and_variant(),
+            game_output_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_compress(
+    format: CompressionFormat,
+    input_path: std::path::PathBuf,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let buf = std::fs::read(input_path)?;
+    std::fs::write(
+        output_path,
+        match format {
+            CompressionFormat::Lz77 => rom::lz77(&buf)?,
+            CompressionFormat::Rle => rom::rle(&buf)?,
+        },
+    )?;
+    Ok(())
+}
+
+fn cmd_decompress(
+    format: CompressionFormat,
+    input_path: std::path::PathBuf,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let buf = std::fs::read(input_path)?;
+    std::fs::write(
+        output_path,
+        match format {
+            CompressionFormat::Lz77 => rom::unlz77(&buf)?,
+            CompressionFormat::Rle => rom::unrle(&buf)?,
+        },
+    )?;
+    Ok(())
+}
@@ -1,3 +1,6 @@
+pub mod backup;
+pub mod container;
+
 use byteorder::ByteOrder;
 
 use crate::{game, scanner};
@@ -5,9 +8,15 @@ use crate::{game, scanner};
 #[derive(Clone)]
 pub struct ScannedSave {
     pub path: std::path::PathBuf,
+    pub container: container::Container,
     pub save: Box<dyn Save + Send + Sync>,
 }
 
+/// Reads a save file from disk, unwrapping it from its container.
+pub fn read_raw(path: &std::path::Path) -> Result<(container::Container, Vec<u8>), anyhow::Error> {
+    Ok(container::unwrap(&std::fs::read(path)?)?)
+}
+
 pub fn scan_saves(
     path: &std::path::Path,
 ) -> std::collections::HashMap<&'static (dyn game::Game + Send + Sync), Vec<ScannedSave>> {
@@ -27,8 +36,8 @@ pub fn scan_saves(
         }
 
         let path = entry.path();
-        let buf = match std::fs::read(path) {
-            Ok(buf) => buf,
+        let (container, buf) = match read_raw(path) {
+            Ok(v) => v,
             Err(e) => {
                 log::warn!("{}: {}", path.display(), e);
                 continue;
@@ -37,19 +46,22 @@ pub fn scan_saves(
 
         let mut ok = false;
         let mut errors = vec![];
-        for game in game::GAMES.iter() {
+        // Games loaded from definitions parse saves as the built-in games they are based on, so only built-in games are
+        // checked, instead of listing the same save again under every game defined on top of them.
+        for game in game::BUILTIN_GAMES.iter().copied() {
             match game.parse_save(&buf) {
                 Ok(save) => {
-                    log::info!("{}: {:?}", path.display(), game.family_and_variant());
-                    let saves = paths.entry(*game).or_insert_with(|| vec![]);
+                    log::info!("{}: {:?} ({:?})", path.display(), game.family_and_variant(), container);
+                    let saves = paths.entry(game).or_insert_with(|| vec![]);
                     saves.push(ScannedSave {
                         path: path.to_path_buf(),
+                        container,
                         save,
                     });
                     ok = true;
                 }
                 Err(e) => {
-                    errors.push((*game, e));
+                    errors.push((game, e));
                 }
             }
         }
@@ -125,6 +137,29 @@ where
     fn view_navi(&self) -> Option<Box<dyn NaviView + '_>> {
         None
     }
+
+    fn view_stats(&self) -> Option<Box<dyn StatsView + '_>> {
+        None
+    }
+
+    /// Converts the save to the other region's release of the same game, if the game has one.
+    fn to_region_counterpart(&self) -> Option<Box<dyn Save + Send + Sync>> {
+        None
+    }
+
+    /// Progress in this save that converting it with to_region_counterpart would drop.
+    fn region_conversion_losses(&self) -> Vec<RegionConversionLoss> {
+        vec![]
+    }
+}
+
+/// Progress a region conversion can't carry over to the other release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionConversionLoss {
+    /// Installed modcards, which only one of the releases has.
+    Modcards(usize),
+    /// Bytes of data in sections whose layout in the other release isn't known.
+    UnmappedBytes(usize),
 }
 
 impl Clone for Box<dyn Save + Send + Sync> {
@@ -210,6 +245,22 @@ pub trait NavicustView<'a> {
     fn navicust_part(&self, i: usize) -> Option<NavicustPart>;
 }
 
+pub trait StatsView<'a> {
+    fn hp(&self) -> Option<u16> {
+        None
+    }
+    fn max_hp(&self) -> Option<u16> {
+        None
+    }
+    fn zenny(&self) -> Option<u32> {
+        None
+    }
+    fn bugfrags(&self) -> Option<u32> {
+        None
+    }
+    fn pack_count(&self, id: usize, variant: usize) -> Option<usize>;
+}
+
 pub trait DarkAIView<'a> {
     fn chip_use_count(&self, id: usize) -> Option<u16>;
     fn secondary_chip_use_count(&self, id: usize) -> Option<u16>;
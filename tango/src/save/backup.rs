@@ -0,0 +1,337 @@
+use crate::game;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct Retention {
+    /// Maximum number of snapshots to keep per save, or 0 to keep all of them.
+    pub max_count: usize,
+
+    /// Maximum age of a snapshot in days, or 0 to keep them forever. The newest snapshot is always kept.
+    pub max_age_days: u32,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_count: 50,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub path: std::path::PathBuf,
+    pub time: std::time::SystemTime,
+}
+
+/// Returns the directory that snapshots of the given save are kept in.
+pub fn snapshots_dir(
+    backups_path: &std::path::Path,
+    saves_path: &std::path::Path,
+    save_path: &std::path::Path,
+) -> std::path::PathBuf {
+    backups_path.join(
+        save_path
+            .strip_prefix(saves_path)
+            .unwrap_or_else(|_| std::path::Path::new(save_path.file_name().unwrap_or_default())),
+    )
+}
+
+/// Lists all snapshots in the given directory, newest first.
+pub fn list(dir: &std::path::Path) -> Vec<Snapshot> {
+    let read_dir = match std::fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return vec![];
+        }
+        Err(e) => {
+            log::warn!("failed to read snapshots dir {}: {}", dir.display(), e);
+            return vec![];
+        }
+    };
+
+    let mut snapshots = read_dir
+        .flat_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+        .flat_map(|entry| {
+            Some(Snapshot {
+                time: entry.metadata().ok()?.modified().ok()?,
+                path: entry.path(),
+            })
+        })
+        .collect::<Vec<_>>();
+    snapshots.sort_by(|a, b| b.path.file_name().cmp(&a.path.file_name()));
+    snapshots
+}
+
+/// Writes a new snapshot of the save, unless it is identical to the newest snapshot.
+///
+/// Snapshots are named after the time they were taken, down to the microsecond. Should two land on the same name
+/// anyway, the later one gets a counter appended, so a snapshot is never silently skipped.
+pub fn take(
+    dir: &std::path::Path,
+    buf: &[u8],
+    retention: &Retention,
+) -> Result<Option<std::path::PathBuf>, anyhow::Error> {
+    if let Some(newest) = list(dir).first() {
+        if std::fs::read(&newest.path).ok().as_deref() == Some(buf) {
+            return Ok(None);
+        }
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let stem = time::OffsetDateTime::from(std::time::SystemTime::now())
+        .format(time::macros::format_description!(
+            "[year padding:zero][month padding:zero repr:numerical][day padding:zero][hour padding:zero][minute padding:zero][second padding:zero][subsecond digits:6]"
+        ))
+        .expect("format time");
+    let (path, mut f) = create_snapshot_file(dir, &stem)?;
+    std::io::Write::write_all(&mut f, buf)?;
+    log::info!("took save snapshot: {}", path.display());
+
+    prune(dir, retention)?;
+    Ok(Some(path))
+}
+
+/// Creates a new, empty snapshot file named after `stem`, appending a counter if that name is already taken.
+fn create_snapshot_file(
+    dir: &std::path::Path,
+    stem: &str,
+) -> Result<(std::path::PathBuf, std::fs::File), std::io::Error> {
+    let mut counter = 0;
+    loop {
+        // Suffixes sort after the bare name, so list still returns these newest first.
+        let path = dir.join(if counter == 0 {
+            format!("{}.sav", stem)
+        } else {
+            format!("{}_{:03}.sav", stem, counter)
+        });
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(f) => {
+                return Ok((path, f));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                counter += 1;
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Takes a snapshot of the save file on disk, but only if it is a valid save for the game. This avoids keeping
+/// snapshots of saves that are half-written.
+pub fn take_if_valid(
+    game: &'static (dyn game::Game + Send + Sync),
+    dir: &std::path::Path,
+    save_path: &std::path::Path,
+    retention: &Retention,
+) -> Result<Option<std::path::PathBuf>, anyhow::Error> {
+    let buf = match std::fs::read(save_path) {
+        Ok(buf) => buf,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        }
+        Err(e) => {
+            return Err(e.into());
+        }
+    };
+    let (_, raw) = super::container::unwrap(&buf)?;
+    if let Err(e) = game.parse_save(&raw) {
+        log::warn!("not taking snapshot of {}: {}", save_path.display(), e);
+        return Ok(None);
+    }
+    take(dir, &buf, retention)
+}
+
+/// Deletes snapshots that fall outside the retention policy.
+pub fn prune(dir: &std::path::Path, retention: &Retention) -> Result<(), anyhow::Error> {
+    let now = std::time::SystemTime::now();
+    let max_age = std::time::Duration::from_secs(retention.max_age_days as u64 * 24 * 60 * 60);
+
+    for (i, snapshot) in list(dir).into_iter().enumerate() {
+        if i == 0 {
+            continue;
+        }
+
+        let too_many = retention.max_count > 0 && i >= retention.max_count;
+        let too_old = retention.max_age_days > 0
+            && now
+                .duration_since(snapshot.time)
+                .map(|age| age > max_age)
+                .unwrap_or(false);
+
+        if too_many || too_old {
+            log::info!("pruning save snapshot: {}", snapshot.path.display());
+            std::fs::remove_file(&snapshot.path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores a snapshot over the save, taking a snapshot of the current save first so the restore can be undone.
+///
+/// If the current save can't be read or backed up, the save is left alone and an error is returned.
+pub fn restore(
+    dir: &std::path::Path,
+    snapshot: &Snapshot,
+    save_path: &std::path::Path,
+    retention: &Retention,
+) -> Result<(), anyhow::Error> {
+    let buf = std::fs::read(&snapshot.path)?;
+    match std::fs::read(save_path) {
+        Ok(current) => {
+            // take only skips writing when the newest snapshot already holds exactly this save.
+            take(dir, &current, retention)
+                .map_err(|e| anyhow::anyhow!("failed to back up {} before restoring: {}", save_path.display(), e))?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(e.into());
+        }
+    }
+    std::fs::write(save_path, buf)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_age(path: &std::path::Path, days: u64) {
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_take_skips_identical_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let retention = Retention::default();
+        assert!(take(dir.path(), b"a", &retention).unwrap().is_some());
+        assert!(take(dir.path(), b"a", &retention).unwrap().is_none());
+        assert!(take(dir.path(), b"b", &retention).unwrap().is_some());
+        assert_eq!(list(dir.path()).len(), 2);
+    }
+
+    #[test]
+    fn test_take_never_collides() {
+        let dir = tempfile::tempdir().unwrap();
+        let retention = Retention {
+            max_count: 0,
+            max_age_days: 0,
+        };
+        for i in 0..50u8 {
+            assert!(take(dir.path(), &[i], &retention).unwrap().is_some());
+        }
+        let snapshots = list(dir.path());
+        assert_eq!(snapshots.len(), 50);
+        // Newest first, even for snapshots that needed a counter.
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            assert_eq!(std::fs::read(&snapshot.path).unwrap(), vec![49 - i as u8]);
+        }
+    }
+
+    #[test]
+    fn test_create_snapshot_file_same_stem() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = (0..3u8)
+            .map(|i| {
+                let (path, mut f) = create_snapshot_file(dir.path(), "20221019123456000000").unwrap();
+                std::io::Write::write_all(&mut f, &[i]).unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            list(dir.path())
+                .into_iter()
+                .map(|snapshot| snapshot.path)
+                .collect::<Vec<_>>(),
+            paths.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_prune_max_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let retention = Retention {
+            max_count: 3,
+            max_age_days: 0,
+        };
+        for i in 0..5u8 {
+            take(dir.path(), &[i], &retention).unwrap();
+        }
+        let snapshots = list(dir.path());
+        assert_eq!(
+            snapshots
+                .iter()
+                .map(|snapshot| std::fs::read(&snapshot.path).unwrap())
+                .collect::<Vec<_>>(),
+            vec![vec![4], vec![3], vec![2]]
+        );
+    }
+
+    #[test]
+    fn test_prune_max_age_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let retention = Retention {
+            max_count: 0,
+            max_age_days: 30,
+        };
+        let old = take(dir.path(), b"old", &retention).unwrap().unwrap();
+        let recent = take(dir.path(), b"recent", &retention).unwrap().unwrap();
+        let newest = take(dir.path(), b"newest", &retention).unwrap().unwrap();
+        set_age(&old, 31);
+        set_age(&recent, 29);
+        set_age(&newest, 365);
+
+        prune(dir.path(), &retention).unwrap();
+        assert_eq!(
+            list(dir.path())
+                .into_iter()
+                .map(|snapshot| snapshot.path)
+                .collect::<Vec<_>>(),
+            vec![newest, recent]
+        );
+    }
+
+    #[test]
+    fn test_restore_backs_up_current_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let save_path = dir.path().join("save.sav");
+        let snapshots_dir = dir.path().join("snapshots");
+        let retention = Retention::default();
+
+        take(&snapshots_dir, b"old", &retention).unwrap();
+        std::fs::write(&save_path, b"current").unwrap();
+
+        let snapshot = list(&snapshots_dir).remove(0);
+        restore(&snapshots_dir, &snapshot, &save_path, &retention).unwrap();
+        assert_eq!(std::fs::read(&save_path).unwrap(), b"old");
+        assert_eq!(std::fs::read(&list(&snapshots_dir)[0].path).unwrap(), b"current");
+    }
+
+    #[test]
+    fn test_restore_fails_without_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let save_path = dir.path().join("save.sav");
+        let snapshots_dir = dir.path().join("snapshots");
+        let retention = Retention::default();
+
+        take(&snapshots_dir, b"old", &retention).unwrap();
+        std::fs::write(&save_path, b"current").unwrap();
+        let snapshot = list(&snapshots_dir).remove(0);
+
+        // Snapshots can't be written into a directory that is actually a file.
+        let broken_dir = dir.path().join("broken");
+        std::fs::write(&broken_dir, b"").unwrap();
+        assert!(restore(&broken_dir, &snapshot, &save_path, &retention).is_err());
+        assert_eq!(std::fs::read(&save_path).unwrap(), b"current");
+    }
+}
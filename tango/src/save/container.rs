@@ -0,0 +1,330 @@
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+
+/// Size of the raw save data that mGBA reads and writes for SRAM games.
+pub const RAW_SIZE: usize = 0x10000;
+
+const NOCASH_MAGIC: &[u8; 0x20] = b"NocashGbaBackupMediaSavDataFile\x1a";
+const NOCASH_MEDIA_TYPE_OFFSET: usize = 0x40;
+const NOCASH_COMPRESSION_OFFSET: usize = 0x44;
+
+const SHARKPORT_MAGIC: &[u8] = b"SharkPortSave";
+const SHARKPORT_PLATFORM_GBA: u32 = 0x000f0000;
+const SHARKPORT_GAME_HEADER_SIZE: usize = 0x1c;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, std::hash::Hash)]
+pub enum Container {
+    /// Plain SRAM contents, as written by mGBA, VBA and most flash carts. Trailing data (e.g. an RTC block) is
+    /// preserved as-is.
+    Raw,
+
+    /// No$GBA's .sav format, optionally with its run-length compression.
+    NoCashGba,
+
+    /// GameShark SP / Action Replay .sps format.
+    SharkPort,
+}
+
+pub const ALL: &[Container] = &[Container::Raw, Container::NoCashGba, Container::SharkPort];
+
+impl Container {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Raw => "sav",
+            Container::NoCashGba => "sav",
+            Container::SharkPort => "sps",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("unexpected eof")]
+    UnexpectedEOF,
+
+    #[error("unsupported media type: {0:02x?}")]
+    UnsupportedMediaType([u8; 4]),
+
+    #[error("unsupported compression method: {0}")]
+    UnsupportedCompression(u32),
+
+    #[error("unsupported platform: {0:08x}")]
+    UnsupportedPlatform(u32),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(_: std::io::Error) -> Self {
+        Error::UnexpectedEOF
+    }
+}
+
+/// Detects the container of a save file.
+pub fn detect(buf: &[u8]) -> Container {
+    if buf.starts_with(NOCASH_MAGIC) {
+        return Container::NoCashGba;
+    }
+
+    if buf.len() >= 4 + SHARKPORT_MAGIC.len()
+        && byteorder::LittleEndian::read_u32(&buf[..4]) as usize == SHARKPORT_MAGIC.len()
+        && &buf[4..4 + SHARKPORT_MAGIC.len()] == SHARKPORT_MAGIC
+    {
+        return Container::SharkPort;
+    }
+
+    Container::Raw
+}
+
+/// Detects the container of a save file and returns its raw SRAM contents, padded to at least [`RAW_SIZE`] so it can be
+/// passed to `Game::parse_save`.
+pub fn unwrap(buf: &[u8]) -> Result<(Container, Vec<u8>), Error> {
+    let container = detect(buf);
+    let mut raw = match container {
+        Container::Raw => buf.to_vec(),
+        Container::NoCashGba => unwrap_nocash(buf)?,
+        Container::SharkPort => unwrap_sharkport(buf)?,
+    };
+    if raw.len() < RAW_SIZE {
+        raw.resize(RAW_SIZE, 0);
+    }
+    Ok((container, raw))
+}
+
+/// Wraps raw SRAM contents into the given container.
+///
+/// The SharkPort format embeds part of the ROM header, so the ROM the save belongs to must be passed in.
+pub fn wrap(container: Container, raw: &[u8], rom: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(match container {
+        Container::Raw => raw.to_vec(),
+        Container::NoCashGba => wrap_nocash(raw),
+        Container::SharkPort => wrap_sharkport(raw, rom)?,
+    })
+}
+
+fn unwrap_nocash(buf: &[u8]) -> Result<Vec<u8>, Error> {
+    let media_type: [u8; 4] = buf
+        .get(NOCASH_MEDIA_TYPE_OFFSET..NOCASH_MEDIA_TYPE_OFFSET + 4)
+        .and_then(|v| v.try_into().ok())
+        .ok_or(Error::UnexpectedEOF)?;
+    if &media_type != b"SRAM" {
+        return Err(Error::UnsupportedMediaType(media_type));
+    }
+
+    let mut r = buf.get(NOCASH_COMPRESSION_OFFSET..).ok_or(Error::UnexpectedEOF)?;
+    match r.read_u32::<byteorder::LittleEndian>()? {
+        0 => {
+            let size = r.read_u32::<byteorder::LittleEndian>()? as usize;
+            Ok(r.get(..size).ok_or(Error::UnexpectedEOF)?.to_vec())
+        }
+        1 => {
+            let _packed_size = r.read_u32::<byteorder::LittleEndian>()?;
+            let size = r.read_u32::<byteorder::LittleEndian>()? as usize;
+            let mut out = Vec::with_capacity(size);
+            loop {
+                match r.read_u8()? {
+                    // End of data.
+                    0x00 => {
+                        break;
+                    }
+
+                    // Fill with a 16-bit count.
+                    0x80 => {
+                        let n = r.read_u16::<byteorder::LittleEndian>()? as usize;
+                        let v = r.read_u8()?;
+                        out.extend(std::iter::repeat(v).take(n));
+                    }
+
+                    // Fill with a short count.
+                    op if op > 0x80 => {
+                        let v = r.read_u8()?;
+                        out.extend(std::iter::repeat(v).take((op - 0x80) as usize));
+                    }
+
+                    // Copy literal bytes.
+                    op => {
+                        let n = op as usize;
+                        out.extend_from_slice(r.get(..n).ok_or(Error::UnexpectedEOF)?);
+                        r = &r[n..];
+                    }
+                }
+            }
+            out.resize(size, 0);
+            Ok(out)
+        }
+        n => Err(Error::UnsupportedCompression(n)),
+    }
+}
+
+fn wrap_nocash(raw: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0u8; NOCASH_MEDIA_TYPE_OFFSET];
+    buf[..NOCASH_MAGIC.len()].copy_from_slice(NOCASH_MAGIC);
+    buf.extend_from_slice(b"SRAM");
+    buf.write_u32::<byteorder::LittleEndian>(0).unwrap();
+    buf.write_u32::<byteorder::LittleEndian>(raw.len() as u32).unwrap();
+    buf.extend_from_slice(raw);
+    buf
+}
+
+fn read_sharkport_string<'a>(r: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = r.read_u32::<byteorder::LittleEndian>()? as usize;
+    let s = r.get(..len).ok_or(Error::UnexpectedEOF)?;
+    *r = &r[len..];
+    Ok(s)
+}
+
+fn write_sharkport_string(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.write_u32::<byteorder::LittleEndian>(s.len() as u32).unwrap();
+    buf.extend_from_slice(s);
+}
+
+fn sharkport_checksum(buf: &[u8]) -> u32 {
+    buf.iter()
+        .fold(0u32, |crc, v| crc.wrapping_add((*v as u32) << (crc % 0x18)))
+}
+
+fn unwrap_sharkport(buf: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut r = buf;
+    read_sharkport_string(&mut r)?;
+
+    let platform = r.read_u32::<byteorder::LittleEndian>()?;
+    if platform != SHARKPORT_PLATFORM_GBA {
+        return Err(Error::UnsupportedPlatform(platform));
+    }
+
+    // title, date, notes
+    for _ in 0..3 {
+        read_sharkport_string(&mut r)?;
+    }
+
+    let payload = read_sharkport_string(&mut r)?;
+    let checksum = r.read_u32::<byteorder::LittleEndian>()?;
+    if checksum != sharkport_checksum(payload) {
+        log::warn!(
+            "sharkport checksum mismatch: expected {:08x}, got {:08x}",
+            checksum,
+            sharkport_checksum(payload)
+        );
+    }
+
+    Ok(payload
+        .get(SHARKPORT_GAME_HEADER_SIZE..)
+        .ok_or(Error::UnexpectedEOF)?
+        .to_vec())
+}
+
+fn wrap_sharkport(raw: &[u8], rom: &[u8]) -> Result<Vec<u8>, Error> {
+    let rom_header = rom.get(0xa0..0xc0).ok_or(Error::UnexpectedEOF)?;
+    let title = &rom_header[..0xc];
+    let title = &title[..title.iter().position(|c| *c == 0).unwrap_or(title.len())];
+
+    let mut payload = vec![0u8; SHARKPORT_GAME_HEADER_SIZE];
+    payload[..0x10].copy_from_slice(&rom_header[..0x10]);
+    payload[0x10] = rom_header[0x1e];
+    payload[0x11] = rom_header[0x1f];
+    payload[0x12] = rom_header[0x1d];
+    payload[0x13] = rom_header[0x10];
+    payload[0x14] = 1;
+    payload.extend_from_slice(raw);
+
+    let mut buf = vec![];
+    write_sharkport_string(&mut buf, SHARKPORT_MAGIC);
+    buf.write_u32::<byteorder::LittleEndian>(SHARKPORT_PLATFORM_GBA)
+        .unwrap();
+    write_sharkport_string(&mut buf, title);
+    write_sharkport_string(&mut buf, b"");
+    write_sharkport_string(&mut buf, b"Exported by Tango");
+    write_sharkport_string(&mut buf, &payload);
+    buf.write_u32::<byteorder::LittleEndian>(sharkport_checksum(&payload))
+        .unwrap();
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_save() -> Vec<u8> {
+        (0..RAW_SIZE).map(|i| (i * 7 + i / 0x100) as u8).collect()
+    }
+
+    fn rom() -> Vec<u8> {
+        let mut rom = vec![0u8; 0x200];
+        rom[0xa0..0xac].copy_from_slice(b"ROCKEXE6_GXX");
+        rom[0xac..0xb0].copy_from_slice(b"BR5J");
+        rom[0xb0..0xb2].copy_from_slice(b"08");
+        rom[0xbc] = 1;
+        rom[0xbd] = 0x42;
+        rom
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let raw = raw_save();
+        for container in ALL {
+            let wrapped = wrap(*container, &raw, &rom()).unwrap();
+            assert_eq!(detect(&wrapped), *container);
+            let (unwrapped_container, unwrapped) = unwrap(&wrapped).unwrap();
+            assert_eq!(unwrapped_container, *container);
+            assert_eq!(unwrapped, raw);
+        }
+    }
+
+    #[test]
+    fn test_unwrap_pads_short_saves() {
+        let raw = vec![0xaa; 0x2000];
+        for container in ALL {
+            let (_, unwrapped) = unwrap(&wrap(*container, &raw, &rom()).unwrap()).unwrap();
+            assert_eq!(unwrapped.len(), RAW_SIZE);
+            assert_eq!(unwrapped[..raw.len()], raw[..]);
+            assert!(unwrapped[raw.len()..].iter().all(|b| *b == 0));
+        }
+    }
+
+    #[test]
+    fn test_unwrap_nocash_compressed() {
+        let mut buf = wrap_nocash(&[]);
+        buf.truncate(NOCASH_COMPRESSION_OFFSET);
+        buf.write_u32::<byteorder::LittleEndian>(1).unwrap();
+        buf.write_u32::<byteorder::LittleEndian>(0).unwrap();
+        buf.write_u32::<byteorder::LittleEndian>(0x400).unwrap();
+        // 3 literal bytes, a short fill of 4, a long fill of 0x100, then the end marker.
+        buf.extend_from_slice(&[0x03, 0x01, 0x02, 0x03]);
+        buf.extend_from_slice(&[0x84, 0xff]);
+        buf.extend_from_slice(&[0x80, 0x00, 0x01, 0x55]);
+        buf.push(0x00);
+
+        let (container, raw) = unwrap(&buf).unwrap();
+        assert_eq!(container, Container::NoCashGba);
+        let mut expected = vec![0x01, 0x02, 0x03, 0xff, 0xff, 0xff, 0xff];
+        expected.extend(std::iter::repeat(0x55).take(0x100));
+        assert_eq!(raw[..expected.len()], expected[..]);
+        assert!(raw[expected.len()..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_unwrap_errors() {
+        let mut nocash = wrap_nocash(&raw_save());
+        nocash[NOCASH_MEDIA_TYPE_OFFSET..NOCASH_MEDIA_TYPE_OFFSET + 4].copy_from_slice(b"EEPR");
+        assert!(matches!(unwrap(&nocash), Err(Error::UnsupportedMediaType(_))));
+
+        let mut nocash = wrap_nocash(&raw_save());
+        nocash[NOCASH_COMPRESSION_OFFSET] = 2;
+        assert!(matches!(unwrap(&nocash), Err(Error::UnsupportedCompression(2))));
+
+        let sharkport = wrap_sharkport(&raw_save(), &rom()).unwrap();
+        assert!(matches!(
+            unwrap(&sharkport[..sharkport.len() / 2]),
+            Err(Error::UnexpectedEOF)
+        ));
+
+        let mut sharkport = sharkport;
+        sharkport[4 + SHARKPORT_MAGIC.len()..4 + SHARKPORT_MAGIC.len() + 4].copy_from_slice(&[0, 0, 0x0d, 0]);
+        assert!(matches!(
+            unwrap(&sharkport),
+            Err(Error::UnsupportedPlatform(0x000d0000))
+        ));
+
+        assert!(matches!(
+            wrap(Container::SharkPort, &raw_save(), &[]),
+            Err(Error::UnexpectedEOF)
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::{config, game, rom, save};
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Detect the game of a save and dump its setup as JSON.
+    InspectSave {
+        save_path: std::path::PathBuf,
+
+        /// Directory to look for ROMs in. Defaults to the ROMs directory in the data path.
+        #[arg(long)]
+        roms_path: Option<std::path::PathBuf>,
+    },
+}
+
+pub fn main(config: config::Config, command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::InspectSave { save_path, roms_path } => cmd_inspect_save(config, save_path, roms_path),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Game {
+    family: String,
+    variant: u8,
+}
+
+#[derive(serde::Serialize)]
+struct Chip {
+    id: usize,
+    code: char,
+    name: Option<String>,
+    description: Option<String>,
+    is_regular: bool,
+    is_tag: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Folder {
+    index: usize,
+    equipped: bool,
+    chips: Vec<Option<Chip>>,
+}
+
+#[derive(serde::Serialize)]
+struct NavicustPart {
+    id: usize,
+    variant: usize,
+    col: u8,
+    row: u8,
+    rot: u8,
+    compressed: bool,
+    name: Option<String>,
+    description: Option<String>,
+    color: Option<rom::NavicustPartColor>,
+}
+
+#[derive(serde::Serialize)]
+struct Style {
+    id: usize,
+    name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct Navicust {
+    width: usize,
+    height: usize,
+    style: Option<Style>,
+    parts: Vec<NavicustPart>,
+}
+
+#[derive(serde::Serialize)]
+struct Modcard56Effect {
+    id: u8,
+    name: String,
+    parameter: u8,
+    is_ability: bool,
+    is_debuff: bool,
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type")]
+enum Modcard {
+    Modcard4 {
+        slot: usize,
+        id: usize,
+        enabled: bool,
+        name: Option<String>,
+        effect: Option<String>,
+        bug: Option<String>,
+    },
+    Modcard56 {
+        slot: usize,
+        id: usize,
+        enabled: bool,
+        name: Option<String>,
+        mb: Option<u8>,
+        effects: Vec<Modcard56Effect>,
+    },
+}
+
+#[derive(serde::Serialize)]
+struct Navi {
+    id: usize,
+    name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct DarkAIChip {
+    id: usize,
+    name: Option<String>,
+    use_count: Option<u16>,
+    secondary_use_count: Option<u16>,
+}
+
+#[derive(serde::Serialize)]
+struct LibraryChip {
+    id: usize,
+    code: char,
+    name: String,
+    count: usize,
+}
+
+#[derive(serde::Serialize)]
+struct Stats {
+    hp: Option<u16>,
+    max_hp: Option<u16>,
+    zenny: Option<u32>,
+    bugfrags: Option<u32>,
+    library: Vec<LibraryChip>,
+}
+
+#[derive(serde::Serialize)]
+struct Output {
+    game: Game,
+    container: String,
+    folders: Option<Vec<Folder>>,
+    navicust: Option<Navicust>,
+    modcards: Option<Vec<Modcard>>,
+    navi: Option<Navi>,
+    dark_ai: Option<Vec<DarkAIChip>>,
+    stats: Option<Stats>,
+}
+
+fn dump_folders<'a>(
+    chips_view: &Box<dyn save::ChipsView<'a> + 'a>,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) -> Vec<Folder> {
+    (0..chips_view.num_folders())
+        .map(|folder_index| {
+            let regular_chip_index = chips_view.regular_chip_index(folder_index);
+            let tag_chip_indexes = chips_view.tag_chip_indexes(folder_index);
+            Folder {
+                index: folder_index,
+                equipped: chips_view.equipped_folder_index() == folder_index,
+                chips: (0..30)
+                    .map(|chip_index| {
+                        let chip = chips_view.chip(folder_index, chip_index)?;
+                        let info = assets.and_then(|assets| assets.chip(chip.id));
+                        Some(Chip {
+                            id: chip.id,
+                            code: chip.code,
+                            name: info.as_ref().map(|info| info.name()),
+                            description: info.as_ref().map(|info| info.description()),
+                            is_regular: regular_chip_index == Some(chip_index),
+                            is_tag: tag_chip_indexes
+                                .map(|indexes| indexes.contains(&chip_index))
+                                .unwrap_or(false),
+                        })
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn dump_navicust<'a>(
+    navicust_view: &Box<dyn save::NavicustView<'a> + 'a>,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) -> Navicust {
+    Navicust {
+        width: navicust_view.width(),
+        height: navicust_view.height(),
+        style: navicust_view.style().map(|id| Style {
+            id,
+            name: assets.and_then(|assets| assets.style(id)).map(|style| style.name()),
+        }),
+        parts: (0..navicust_view.count())
+            .flat_map(|i| navicust_view.navicust_part(i))
+            .map(|part| {
+                let info = assets.and_then(|assets| assets.navicust_part(part.id, part.variant));
+                NavicustPart {
+                    id: part.id,
+                    variant: part.variant,
+                    col: part.col,
+                    row: part.row,
+                    rot: part.rot,
+                    compressed: part.compressed,
+                    name: info.as_ref().map(|info| info.name()),
+                    description: info.as_ref().map(|info| info.description()),
+                    color: info.as_ref().and_then(|info| info.color()),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn dump_modcards(
+    modcards_view: &save::ModcardsView,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) -> Vec<Modcard> {
+    match modcards_view {
+        save::ModcardsView::Modcard4s(modcard4s_view) => (0..6)
+            .flat_map(|slot| {
+                let modcard = modcard4s_view.modcard(slot)?;
+                let info = assets.and_then(|assets| assets.modcard4(modcard.id));
+                Some(Modcard::Modcard4 {
+                    slot,
+                    id: modcard.id,
+                    enabled: modcard.enabled,
+                    name: info.as_ref().map(|info| info.name()),
+                    effect: info.as_ref().map(|info| info.effect()),
+                    bug: info.as_ref().and_then(|info| info.bug()),
+                })
+            })
+            .collect(),
+        save::ModcardsView::Modcard56s(modcard56s_view) => (0..modcard56s_view.count())
+            .flat_map(|slot| {
+                let modcard = modcard56s_view.modcard(slot)?;
+                let info = assets.and_then(|assets| assets.modcard56(modcard.id));
+                Some(Modcard::Modcard56 {
+                    slot,
+                    id: modcard.id,
+                    enabled: modcard.enabled,
+                    name: info.as_ref().map(|info| info.name()),
+                    mb: info.as_ref().map(|info| info.mb()),
+                    effects: info
+                        .as_ref()
+                        .map(|info| info.effects())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|effect| Modcard56Effect {
+                            id: effect.id,
+                            name: effect.name,
+                            parameter: effect.parameter,
+                            is_ability: effect.is_ability,
+                            is_debuff: effect.is_debuff,
+                        })
+                        .collect(),
+                })
+            })
+            .collect(),
+    }
+}
+
+fn dump_dark_ai<'a>(
+    dark_ai_view: &Box<dyn save::DarkAIView<'a> + 'a>,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) -> Vec<DarkAIChip> {
+    (0..)
+        .map(|id| {
+            (
+                id,
+                dark_ai_view.chip_use_count(id),
+                dark_ai_view.secondary_chip_use_count(id),
+            )
+        })
+        .take_while(|(_, use_count, secondary_use_count)| use_count.is_some() || secondary_use_count.is_some())
+        .filter(|(_, use_count, secondary_use_count)| {
+            use_count.unwrap_or(0) > 0 || secondary_use_count.unwrap_or(0) > 0
+        })
+        .map(|(id, use_count, secondary_use_count)| DarkAIChip {
+            id,
+            name: assets.and_then(|assets| assets.chip(id)).map(|info| info.name()),
+            use_count,
+            secondary_use_count,
+        })
+        .collect()
+}
+
+fn dump_stats<'a>(
+    stats_view: &Box<dyn save::StatsView<'a> + 'a>,
+    assets: Option<&Box<dyn rom::Assets + Send + Sync>>,
+) -> Stats {
+    Stats {
+        hp: stats_view.hp(),
+        max_hp: stats_view.max_hp(),
+        zenny: stats_view.zenny(),
+        bugfrags: stats_view.bugfrags(),
+        library: assets
+            .map(|assets| {
+                (0..assets.num_chips())
+                    .flat_map(|id| assets.chip(id).map(|info| (id, info)))
+                    .flat_map(|(id, info)| {
+                        let name = info.name();
+                        info.codes()
+                            .into_iter()
+                            .enumerate()
+                            .flat_map(|(variant, code)| {
+                                let count = stats_view.pack_count(id, variant)?;
+                                if count == 0 {
+                                    return None;
+                                }
+                                Some(LibraryChip {
+                                    id,
+                                    code,
+                                    name: name.clone(),
+                                    count,
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn cmd_inspect_save(
+    config: config::Config,
+    save_path: std::path::PathBuf,
+    roms_path: Option<std::path::PathBuf>,
+) -> Result<(), anyhow::Error> {
+    let (container, raw) = save::read_raw(&save_path)?;
+
+    let (game, save) = game::games()
+        .iter()
+        .copied()
+        .find_map(|game| game.parse_save(&raw).ok().map(|save| (game, save)))
+        .ok_or_else(|| anyhow::anyhow!("could not detect game for save"))?;
+
+    let roms = game::scan_roms(&roms_path.unwrap_or_else(|| config.roms_path()));
+    let assets = if let Some(rom) = roms.get(&game) {
+        Some(game.load_rom_assets(rom, save.as_raw_wram(), &Default::default())?)
+    } else {
+        eprintln!(
+            "no rom found for {:?}, names will not be resolved",
+            game.family_and_variant()
+        );
+        None
+    };
+    let assets = assets.as_ref();
+
+    let (family, variant) = game.family_and_variant();
+    let output = Output {
+        game: Game {
+            family: family.to_string(),
+            variant,
+        },
+        container: format!("{:?}", container),
+        folders: save.view_chips().map(|chips_view| dump_folders(&chips_view, assets)),
+        navicust: save
+            .view_navicust()
+            .map(|navicust_view| dump_navicust(&navicust_view, assets)),
+        modcards: save
+            .view_modcards()
+            .map(|modcards_view| dump_modcards(&modcards_view, assets)),
+        navi: save.view_navi().map(|navi_view| Navi {
+            id: navi_view.navi(),
+            name: assets
+                .and_then(|assets| assets.navi(navi_view.navi()))
+                .map(|navi| navi.name()),
+        }),
+        dark_ai: save
+            .view_dark_ai()
+            .map(|dark_ai_view| dump_dark_ai(&dark_ai_view, assets)),
+        stats: save.view_stats().map(|stats_view| dump_stats(&stats_view, assets)),
+    };
+
+    serde_json::to_writer_pretty(std::io::stdout(), &output)?;
+    println!();
+    Ok(())
+}
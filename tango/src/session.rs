@@ -1,10 +1,14 @@
-use crate::{audio, battle, config, game, net, replay, replayer, rom, save, stats, video};
+use crate::{
+    audio, battle, cheats, config, game, lockstep, net, replay, replayer, rom, save, shadow, stats, sync, video,
+};
 use parking_lot::Mutex;
 use rand::SeedableRng;
 use std::sync::Arc;
 
 pub const EXPECTED_FPS: f32 = 16777216.0 / 280896.0;
 
+const SAVE_BACKUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 pub struct GameInfo {
     pub game: &'static (dyn game::Game + Send + Sync),
     pub patch: Option<(String, semver::Version)>,
@@ -28,6 +32,7 @@ pub struct Session {
     pause_on_next_frame: std::sync::Arc<std::sync::atomic::AtomicBool>,
     opponent_setup: Option<Setup>,
     own_setup: Option<Setup>,
+    graphics_presets: Vec<rom::graphics::Preset>,
 }
 
 pub struct CompletionToken {
@@ -35,6 +40,10 @@ pub struct CompletionToken {
 }
 
 impl CompletionToken {
+    pub fn new(flag: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Self {
+        Self { flag }
+    }
+
     pub fn complete(&self) {
         self.flag.store(true, std::sync::atomic::Ordering::SeqCst);
     }
@@ -45,12 +54,49 @@ pub struct PvP {
     cancellation_token: tokio_util::sync::CancellationToken,
 }
 
-pub struct SinglePlayer {}
+pub struct SinglePlayer {
+    backup_cancellation_token: tokio_util::sync::CancellationToken,
+    cheats: std::sync::Arc<Mutex<cheats::Engine>>,
+    /// Set once a cheat has been used in the session, to the scratch file the game saves to from then on.
+    scratch_save: std::sync::Arc<Mutex<Option<tempfile::NamedTempFile>>>,
+}
+
+impl SinglePlayer {
+    pub fn cheats(&self) -> &std::sync::Arc<Mutex<cheats::Engine>> {
+        &self.cheats
+    }
+
+    /// Whether a cheat has been used in this session, so the game has been saving to a scratch file since.
+    pub fn cheats_used(&self) -> bool {
+        self.scratch_save.lock().is_some()
+    }
+}
+
+/// Copies the save into a scratch file and has the core save there instead, so nothing the game saves from then on
+/// reaches the save on disk. The scratch file is deleted when it's dropped.
+fn redirect_save_to_scratch(
+    core: &mut mgba::core::CoreMutRef,
+    save_path: &std::path::Path,
+) -> Result<tempfile::NamedTempFile, anyhow::Error> {
+    let mut scratch_save = tempfile::NamedTempFile::new()?;
+    std::io::copy(&mut std::fs::File::open(save_path)?, scratch_save.as_file_mut())?;
+    core.load_save(mgba::vfile::VFile::open(
+        scratch_save.path(),
+        mgba::vfile::flags::O_RDWR,
+    )?)?;
+    Ok(scratch_save)
+}
+
+pub struct Practice {
+    shadow: std::sync::Arc<Mutex<shadow::Shadow>>,
+    replayer_state: replayer::State,
+}
 
 pub enum Mode {
     SinglePlayer(SinglePlayer),
     PvP(PvP),
     Replayer,
+    Practice(Practice),
 }
 
 impl Session {
@@ -97,11 +143,14 @@ impl Session {
 
         let completion_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
 
+        // The game signals the end of each game, but the session only completes once the set is decided.
+        let game_end_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
         traps.extend(hooks.primary_traps(
             joyflags.clone(),
             match_.clone(),
             CompletionToken {
-                flag: completion_flag.clone(),
+                flag: game_end_flag.clone(),
             },
         ));
         core.set_traps(
@@ -182,9 +231,13 @@ impl Session {
         ]));
         thread.set_frame_callback({
             let completion_flag = completion_flag.clone();
+            let game_end_flag = game_end_flag.clone();
+            let awaiting_next_game = std::sync::atomic::AtomicBool::new(false);
+            let match_ = match_.clone();
             let joyflags = joyflags.clone();
             let vbuf = vbuf.clone();
             let emu_tps_counter = emu_tps_counter.clone();
+            let handle = tokio::runtime::Handle::current();
             move |mut core, video_buffer, mut thread_handle| {
                 let mut vbuf = vbuf.lock();
                 vbuf.copy_from_slice(video_buffer);
@@ -192,6 +245,41 @@ impl Session {
                 core.set_keys(joyflags.load(std::sync::atomic::Ordering::Relaxed));
                 emu_tps_counter.lock().mark();
 
+                if game_end_flag.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                    let _guard = handle.enter();
+
+                    // Pause before ending the game: the loser's pick of side can unpause the core as soon as the game
+                    // has ended.
+                    thread_handle.pause();
+                    let continue_set = match &*sync::block_on(match_.lock()) {
+                        Some(match_) => match sync::block_on(match_.end_game()) {
+                            Ok(continue_set) => continue_set,
+                            Err(e) => {
+                                log::error!("failed to end game: {:?}", e);
+                                false
+                            }
+                        },
+                        None => false,
+                    };
+                    if continue_set {
+                        log::info!("set is not decided yet, waiting for the loser to pick a side");
+                        awaiting_next_game.store(true, std::sync::atomic::Ordering::SeqCst);
+                    } else {
+                        completion_flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                    }
+                } else if awaiting_next_game.load(std::sync::atomic::Ordering::SeqCst) {
+                    let _guard = handle.enter();
+                    let next_game_ready = match &*sync::block_on(match_.lock()) {
+                        Some(match_) => sync::block_on(match_.take_next_game_ready()),
+                        None => false,
+                    };
+                    if next_game_ready {
+                        log::info!("starting next game");
+                        awaiting_next_game.store(false, std::sync::atomic::Ordering::SeqCst);
+                        core.reset();
+                    }
+                }
+
                 if completion_flag.load(std::sync::atomic::Ordering::SeqCst) {
                     thread_handle.pause();
                 }
@@ -200,6 +288,7 @@ impl Session {
 
         Ok(Session {
             start_time: std::time::SystemTime::now(),
+            graphics_presets: load_graphics_presets(local_game, local_rom),
             game_info: GameInfo {
                 game: local_game,
                 patch: local_patch,
@@ -216,27 +305,45 @@ impl Session {
             pause_on_next_frame: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
             own_setup: {
                 let save = local_game.parse_save(&local_save)?;
-                let assets = local_game.load_rom_assets(&local_rom, save.as_raw_wram(), local_patch_overrides)?;
-                Some(Setup {
-                    game_lang: local_patch_overrides
-                        .language
-                        .clone()
-                        .unwrap_or_else(|| game.language()),
-                    save,
-                    assets,
-                })
+                match local_game.load_rom_assets(&local_rom, save.as_raw_wram(), local_patch_overrides) {
+                    Ok(assets) => Some(Setup {
+                        game_lang: local_patch_overrides
+                            .language
+                            .clone()
+                            .unwrap_or_else(|| game.language()),
+                        save,
+                        assets,
+                    }),
+                    // Games loaded from definitions have no ROM assets, so play without them.
+                    Err(e) if !game::is_builtin(local_game) => {
+                        log::warn!("failed to load own rom assets: {:?}", e);
+                        None
+                    }
+                    Err(e) => {
+                        return Err(e);
+                    }
+                }
             },
             opponent_setup: if reveal_setup {
                 let save = remote_game.parse_save(&remote_save)?;
-                let assets = remote_game.load_rom_assets(&remote_rom, save.as_raw_wram(), remote_patch_overrides)?;
-                Some(Setup {
-                    game_lang: remote_patch_overrides
-                        .language
-                        .clone()
-                        .unwrap_or_else(|| game.language()),
-                    save,
-                    assets,
-                })
+                match remote_game.load_rom_assets(&remote_rom, save.as_raw_wram(), remote_patch_overrides) {
+                    Ok(assets) => Some(Setup {
+                        game_lang: remote_patch_overrides
+                            .language
+                            .clone()
+                            .unwrap_or_else(|| game.language()),
+                        save,
+                        assets,
+                    }),
+                    // Games loaded from definitions have no ROM assets, so play without them.
+                    Err(e) if !game::is_builtin(remote_game) => {
+                        log::warn!("failed to load opponent rom assets: {:?}", e);
+                        None
+                    }
+                    Err(e) => {
+                        return Err(e);
+                    }
+                }
             } else {
                 None
             },
@@ -249,6 +356,9 @@ impl Session {
         patch: Option<(String, semver::Version)>,
         rom: &[u8],
         save_path: &std::path::Path,
+        backups_dir: &std::path::Path,
+        backup_retention: save::backup::Retention,
+        cheats_path: &std::path::Path,
         emu_tps_counter: Arc<Mutex<stats::Counter>>,
     ) -> Result<Self, anyhow::Error> {
         let mut core = mgba::core::Core::new_gba("tango")?;
@@ -256,6 +366,23 @@ impl Session {
 
         core.as_mut().load_rom(mgba::vfile::VFile::open_memory(rom))?;
 
+        if let Err(e) = save::backup::take_if_valid(game, backups_dir, save_path, &backup_retention) {
+            log::error!("failed to take save snapshot: {:?}", e);
+        }
+
+        // mGBA only writes back raw saves, and would discard any progress made on a save in another container.
+        if let Some(container) = std::fs::read(save_path)
+            .ok()
+            .map(|buf| save::container::detect(&buf))
+            .filter(|container| *container != save::container::Container::Raw)
+        {
+            anyhow::bail!(
+                "{}: save is in {:?} container, export it as a raw save to play it",
+                save_path.display(),
+                container
+            );
+        }
+
         let save_vf = mgba::vfile::VFile::open(save_path, mgba::vfile::flags::O_CREAT | mgba::vfile::flags::O_RDWR)?;
 
         core.as_mut().load_save(save_vf)?;
@@ -265,6 +392,21 @@ impl Session {
         let hooks = game.hooks();
         hooks.patch(core.as_mut());
 
+        // Cheats only ever exist in single-player sessions: PvP and replayer sessions have no way to enable them.
+        let codes_path = cheats::codes_path(cheats_path, game);
+        let mut codes = hooks.training_codes();
+        match cheats::load_codes(&codes_path) {
+            Ok(user_codes) => codes.extend(user_codes),
+            Err(e) => {
+                log::warn!("failed to load codes from {}: {:?}", codes_path.display(), e);
+            }
+        }
+        let cheats = Arc::new(Mutex::new(cheats::Engine::new(codes_path, codes)));
+
+        // Cheats only write to memory, so the save on disk is still clean when the first one is used. The game saves to
+        // a scratch file from then on, which is thrown away when the session ends, and no snapshots are taken of it.
+        let scratch_save = Arc::new(Mutex::new(None));
+
         let thread = mgba::thread::Thread::new(core);
 
         thread.start()?;
@@ -286,11 +428,33 @@ impl Session {
             let vbuf = vbuf.clone();
             let emu_tps_counter = emu_tps_counter.clone();
             let pause_on_next_frame = pause_on_next_frame.clone();
+            let cheats = cheats.clone();
+            let scratch_save = scratch_save.clone();
+            let save_path = save_path.to_path_buf();
             move |mut core, video_buffer, mut thread_handle| {
                 let mut vbuf = vbuf.lock();
                 vbuf.copy_from_slice(video_buffer);
                 video::fix_vbuf_alpha(&mut *vbuf);
                 core.set_keys(joyflags.load(std::sync::atomic::Ordering::Relaxed));
+                let mut cheats = cheats.lock();
+                if cheats.is_active() {
+                    let mut scratch_save = scratch_save.lock();
+                    if scratch_save.is_none() {
+                        match redirect_save_to_scratch(&mut core, &save_path) {
+                            Ok(f) => {
+                                log::info!("cheats used, {} will not be saved to", save_path.display());
+                                *scratch_save = Some(f);
+                            }
+                            Err(e) => {
+                                log::error!("failed to redirect save to a scratch file, disabling cheats: {:?}", e);
+                                cheats.disable_all();
+                            }
+                        }
+                    }
+                    if scratch_save.is_some() {
+                        cheats.apply(&mut core);
+                    }
+                }
                 emu_tps_counter.lock().mark();
 
                 if pause_on_next_frame.swap(false, std::sync::atomic::Ordering::SeqCst) {
@@ -298,14 +462,56 @@ impl Session {
                 }
             }
         });
+        let backup_cancellation_token = tokio_util::sync::CancellationToken::new();
+        tokio::task::spawn({
+            let backup_cancellation_token = backup_cancellation_token.clone();
+            let backups_dir = backups_dir.to_path_buf();
+            let save_path = save_path.to_path_buf();
+            let scratch_save = scratch_save.clone();
+            async move {
+                loop {
+                    let cancelled = tokio::select! {
+                        _ = tokio::time::sleep(SAVE_BACKUP_INTERVAL) => false,
+                        _ = backup_cancellation_token.cancelled() => true,
+                    };
+
+                    if scratch_save.lock().is_some() {
+                        break;
+                    }
+
+                    if let Err(e) = tokio::task::spawn_blocking({
+                        let backups_dir = backups_dir.clone();
+                        let save_path = save_path.clone();
+                        let backup_retention = backup_retention.clone();
+                        move || save::backup::take_if_valid(game, &backups_dir, &save_path, &backup_retention)
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|r| r)
+                    {
+                        log::error!("failed to take save snapshot: {:?}", e);
+                    }
+
+                    if cancelled {
+                        break;
+                    }
+                }
+            }
+        });
+
         Ok(Session {
             start_time: std::time::SystemTime::now(),
+            graphics_presets: load_graphics_presets(game, rom),
             game_info: GameInfo { game, patch },
             vbuf,
             _audio_binding: audio_binding,
             thread,
             joyflags,
-            mode: Mode::SinglePlayer(SinglePlayer {}),
+            mode: Mode::SinglePlayer(SinglePlayer {
+                backup_cancellation_token,
+                cheats,
+                scratch_save,
+            }),
             pause_on_next_frame,
             completion_flag: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
             own_setup: None,
@@ -398,6 +604,7 @@ impl Session {
 
         Ok(Session {
             start_time: std::time::SystemTime::now(),
+            graphics_presets: load_graphics_presets(game, rom),
             game_info: GameInfo { game, patch },
             vbuf,
             _audio_binding: audio_binding,
@@ -411,6 +618,146 @@ impl Session {
         })
     }
 
+    /// Starts a practice round from a replay: the local side is played live from the replay's starting state, while the
+    /// remote side runs the remote ROM from the replay's remote state and is driven by the recorded remote inputs. Once
+    /// the recording runs out, the remote stops pressing anything.
+    pub fn new_practice(
+        audio_binder: audio::LateBinder,
+        game: &'static (dyn game::Game + Send + Sync),
+        patch: Option<(String, semver::Version)>,
+        rom: &[u8],
+        remote_rom: &[u8],
+        emu_tps_counter: Arc<Mutex<stats::Counter>>,
+        replay: &replay::Replay,
+    ) -> Result<Self, anyhow::Error> {
+        if !replay.has_match_type() {
+            anyhow::bail!(
+                "replay version {:#04x} doesn't record the match type and can't be practiced",
+                replay.version
+            );
+        }
+
+        let first_input_pair = replay
+            .input_pairs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("replay has no inputs"))?;
+
+        let mut core = mgba::core::Core::new_gba("tango")?;
+        core.enable_video_buffer();
+
+        core.as_mut().load_rom(mgba::vfile::VFile::open_memory(&rom))?;
+
+        let hooks = game.hooks();
+        hooks.patch(core.as_mut());
+
+        let match_type = (replay.metadata.match_type as u8, replay.metadata.match_subtype as u8);
+
+        let shadow = Arc::new(Mutex::new(shadow::Shadow::new_from_state(
+            remote_rom,
+            &replay.remote_state,
+            match_type,
+            replay.local_player_index,
+            &first_input_pair.remote.packet,
+        )?));
+
+        let joyflags = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let completion_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        let remote_joyflags = remote_joyflags_by_tick(&replay.input_pairs)?;
+        let replayer_state = replayer::State::new_live(
+            match_type,
+            replay.local_player_index,
+            first_input_pair.local.packet.clone(),
+            Box::new({
+                let joyflags = joyflags.clone();
+                move |tick| {
+                    Some(lockstep::Pair {
+                        local: lockstep::PartialInput {
+                            local_tick: tick,
+                            remote_tick: tick,
+                            joyflags: joyflags.load(std::sync::atomic::Ordering::Relaxed) as u16,
+                        },
+                        remote: lockstep::PartialInput {
+                            local_tick: tick,
+                            remote_tick: tick,
+                            joyflags: remote_joyflags.get(tick as usize).copied().unwrap_or(0),
+                        },
+                    })
+                }
+            }),
+            Box::new({
+                let shadow = shadow.clone();
+                move |ip| Ok(shadow.lock().apply_input(ip)?.packet)
+            }),
+            Box::new({
+                let completion_flag = completion_flag.clone();
+                move || {
+                    completion_flag.store(true, std::sync::atomic::Ordering::SeqCst);
+                }
+            }),
+        );
+        let mut traps = hooks.common_traps();
+        traps.extend(hooks.replayer_traps(replayer_state.clone()));
+        core.set_traps(traps);
+
+        let thread = mgba::thread::Thread::new(core);
+
+        thread.start()?;
+        thread.handle().pause();
+        thread.handle().lock_audio().sync_mut().set_fps_target(EXPECTED_FPS);
+
+        let audio_binding = audio_binder.bind(Some(Box::new(audio::MGBAStream::new(
+            thread.handle(),
+            audio_binder.sample_rate(),
+        ))))?;
+
+        let local_state = replay.local_state.clone();
+        thread.handle().run_on_core(move |mut core| {
+            core.load_state(&local_state).expect("load state");
+        });
+        thread.handle().unpause();
+
+        let pause_on_next_frame = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let vbuf = Arc::new(Mutex::new(vec![
+            0u8;
+            (mgba::gba::SCREEN_WIDTH * mgba::gba::SCREEN_HEIGHT * 4)
+                as usize
+        ]));
+        thread.set_frame_callback({
+            let vbuf = vbuf.clone();
+            let emu_tps_counter = emu_tps_counter.clone();
+            let completion_flag = completion_flag.clone();
+            let pause_on_next_frame = pause_on_next_frame.clone();
+            move |_core, video_buffer, mut thread_handle| {
+                let mut vbuf = vbuf.lock();
+                vbuf.copy_from_slice(video_buffer);
+                video::fix_vbuf_alpha(&mut *vbuf);
+                emu_tps_counter.lock().mark();
+
+                if pause_on_next_frame.swap(false, std::sync::atomic::Ordering::SeqCst)
+                    || completion_flag.load(std::sync::atomic::Ordering::SeqCst)
+                {
+                    thread_handle.pause();
+                }
+            }
+        });
+
+        Ok(Session {
+            start_time: std::time::SystemTime::now(),
+            graphics_presets: load_graphics_presets(game, rom),
+            game_info: GameInfo { game, patch },
+            vbuf,
+            _audio_binding: audio_binding,
+            thread,
+            joyflags,
+            mode: Mode::Practice(Practice { shadow, replayer_state }),
+            completion_flag,
+            pause_on_next_frame,
+            own_setup: None,
+            opponent_setup: None,
+        })
+    }
+
     pub fn completed(&self) -> bool {
         self.completion_flag.load(std::sync::atomic::Ordering::SeqCst)
     }
@@ -419,6 +766,33 @@ impl Session {
         &self.mode
     }
 
+    /// Calls `f` with the primary's latest committed state and the shadow core running the remote side, if this
+    /// session has one and it has started. Both stay locked for the duration, so neither moves on to another tick in
+    /// between.
+    pub fn with_committed_states<R>(
+        &self,
+        f: impl FnOnce(Option<&battle::CommittedState>, &shadow::Shadow) -> R,
+    ) -> Option<R> {
+        match &self.mode {
+            Mode::PvP(pvp) => {
+                let match_ = pvp.match_.blocking_lock().clone()?;
+                let round_state = match_.blocking_lock_round_state();
+                let shadow = match_.shadow();
+                let shadow = shadow.lock();
+                Some(f(
+                    round_state.round.as_ref().and_then(|round| round.committed_state()),
+                    &shadow,
+                ))
+            }
+            Mode::Practice(practice) => {
+                let replayer_state = practice.replayer_state.lock_inner();
+                let shadow = practice.shadow.lock();
+                Some(f(replayer_state.committed_state(), &shadow))
+            }
+            _ => None,
+        }
+    }
+
     pub fn set_paused(&self, pause: bool) {
         let handle = self.thread.handle();
         if pause {
@@ -494,6 +868,46 @@ impl Session {
     pub fn own_setup(&self) -> &Option<Setup> {
         &self.own_setup
     }
+
+    pub fn graphics_presets(&self) -> &[rom::graphics::Preset] {
+        &self.graphics_presets
+    }
+}
+
+fn load_graphics_presets(game: &'static (dyn game::Game + Send + Sync), rom: &[u8]) -> Vec<rom::graphics::Preset> {
+    match game
+        .load_rom_assets(rom, &vec![0u8; 0x40000], &Default::default())
+        .and_then(|assets| assets.graphics_presets())
+    {
+        Ok(presets) => presets,
+        Err(e) => {
+            log::warn!("failed to load graphics presets: {:?}", e);
+            vec![]
+        }
+    }
+}
+
+/// Collects the remote's joyflags from a replay, indexed by the tick the replayer will ask for them on. The replayer
+/// counts ticks from 0, so the replay's inputs have to start at tick 0 with no gaps, and both sides of each pair have
+/// to be on the same tick.
+fn remote_joyflags_by_tick(
+    input_pairs: &[lockstep::Pair<lockstep::Input, lockstep::Input>],
+) -> anyhow::Result<Vec<u16>> {
+    input_pairs
+        .iter()
+        .enumerate()
+        .map(|(i, ip)| {
+            if ip.local.local_tick as usize != i || ip.remote.local_tick as usize != i {
+                anyhow::bail!(
+                    "replay input {} is misaligned: local tick = {}, remote tick = {}",
+                    i,
+                    ip.local.local_tick,
+                    ip.remote.local_tick
+                );
+            }
+            Ok(ip.remote.joyflags)
+        })
+        .collect()
 }
 
 impl Drop for Session {
@@ -502,7 +916,55 @@ impl Drop for Session {
             Mode::PvP(pvp) => {
                 pvp.cancellation_token.cancel();
             }
+            Mode::SinglePlayer(sp) => {
+                sp.backup_cancellation_token.cancel();
+            }
             _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input_pair(
+        local_tick: u32,
+        remote_tick: u32,
+        joyflags: u16,
+    ) -> lockstep::Pair<lockstep::Input, lockstep::Input> {
+        lockstep::Pair {
+            local: lockstep::Input {
+                local_tick,
+                remote_tick: local_tick,
+                joyflags: 0,
+                packet: vec![],
+            },
+            remote: lockstep::Input {
+                local_tick: remote_tick,
+                remote_tick,
+                joyflags,
+                packet: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_remote_joyflags_by_tick() {
+        assert_eq!(
+            remote_joyflags_by_tick(&[input_pair(0, 0, 0x1), input_pair(1, 1, 0x2), input_pair(2, 2, 0x4)]).unwrap(),
+            vec![0x1, 0x2, 0x4]
+        );
+        assert_eq!(remote_joyflags_by_tick(&[]).unwrap(), Vec::<u16>::new());
+    }
+
+    #[test]
+    fn test_remote_joyflags_by_tick_rejects_misaligned_inputs() {
+        // Not starting from tick 0.
+        assert!(remote_joyflags_by_tick(&[input_pair(1, 1, 0), input_pair(2, 2, 0)]).is_err());
+        // A gap.
+        assert!(remote_joyflags_by_tick(&[input_pair(0, 0, 0), input_pair(2, 2, 0)]).is_err());
+        // The remote input is on a different tick than the local one.
+        assert!(remote_joyflags_by_tick(&[input_pair(0, 0, 0), input_pair(1, 2, 0)]).is_err());
+    }
+}
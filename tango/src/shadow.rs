@@ -1,4 +1,4 @@
-use crate::{battle, game, lockstep};
+use crate::{battle, game, lockstep, net};
 
 pub struct Round {
     current_tick: u32,
@@ -89,6 +89,7 @@ struct AppliedState {
 
 struct InnerState {
     match_type: (u8, u8),
+    stage: net::protocol::StageSelection,
     is_offerer: bool,
     round_state: parking_lot::Mutex<RoundState>,
     rng: parking_lot::Mutex<rand_pcg::Mcg128Xsl64>,
@@ -96,10 +97,14 @@ struct InnerState {
     error: parking_lot::Mutex<Option<anyhow::Error>>,
 }
 
+/// How many of the latest applied states the shadow keeps for the debug window to compare against the primary.
+const NUM_RECENT_APPLIED_STATES: usize = 2;
+
 pub struct Shadow {
     core: mgba::core::Core,
     state: State,
     hooks: &'static (dyn game::Hooks + Send + Sync),
+    recent_applied_states: std::collections::VecDeque<AppliedState>,
 }
 
 #[derive(Clone)]
@@ -108,12 +113,14 @@ pub struct State(std::sync::Arc<InnerState>);
 impl State {
     pub fn new(
         match_type: (u8, u8),
+        stage: net::protocol::StageSelection,
         is_offerer: bool,
         rng: rand_pcg::Mcg128Xsl64,
         last_result: battle::BattleResult,
     ) -> State {
         State(std::sync::Arc::new(InnerState {
             match_type,
+            stage,
             is_offerer,
             rng: parking_lot::Mutex::new(rng),
             round_state: parking_lot::Mutex::new(RoundState {
@@ -129,6 +136,10 @@ impl State {
         self.0.match_type
     }
 
+    pub fn stage(&self) -> net::protocol::StageSelection {
+        self.0.stage
+    }
+
     pub fn is_offerer(&self) -> bool {
         self.0.is_offerer
     }
@@ -168,6 +179,10 @@ impl State {
         *self.0.error.lock() = Some(err);
     }
 
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.0.error.lock().take()
+    }
+
     pub fn set_applied_state(&self, state: mgba::state::State, tick: u32) {
         *self.0.applied_state.lock() = Some(AppliedState { tick, state });
     }
@@ -178,6 +193,7 @@ impl Shadow {
         rom: &[u8],
         save: &[u8],
         match_type: (u8, u8),
+        stage: net::protocol::StageSelection,
         is_offerer: bool,
         battle_result: battle::BattleResult,
         rng: rand_pcg::Mcg128Xsl64,
@@ -187,7 +203,7 @@ impl Shadow {
         core.as_mut().load_rom(mgba::vfile::VFile::open_memory(rom))?;
         core.as_mut().load_save(mgba::vfile::VFile::open_memory(save))?;
 
-        let state = State::new(match_type, is_offerer, rng, battle_result);
+        let state = State::new(match_type, stage, is_offerer, rng, battle_result);
 
         let game = game::find_by_rom_info(&core.as_mut().rom_code(), core.as_mut().rom_revision()).unwrap();
         let hooks = game.hooks();
@@ -198,7 +214,96 @@ impl Shadow {
         core.set_traps(traps);
         core.as_mut().reset();
 
-        Ok(Shadow { core, hooks, state })
+        Ok(Shadow {
+            core,
+            hooks,
+            state,
+            recent_applied_states: std::collections::VecDeque::new(),
+        })
+    }
+
+    /// Creates a shadow that resumes from a first committed state instead of booting from a save, e.g. a replay's
+    /// remote state. `first_remote_packet` is the packet the remote sent on tick 0.
+    pub fn new_from_state(
+        rom: &[u8],
+        state: &mgba::state::State,
+        match_type: (u8, u8),
+        local_player_index: u8,
+        first_remote_packet: &[u8],
+    ) -> anyhow::Result<Self> {
+        let mut core = mgba::core::Core::new_gba("tango")?;
+
+        core.as_mut().load_rom(mgba::vfile::VFile::open_memory(rom))?;
+
+        let shadow_state = State::new(
+            match_type,
+            Default::default(),
+            false,
+            rand_pcg::Mcg128Xsl64::new(0),
+            battle::BattleResult::Win,
+        );
+
+        let game = game::find_by_rom_info(&core.as_mut().rom_code(), core.as_mut().rom_revision())
+            .ok_or_else(|| anyhow::anyhow!("unknown remote rom"))?;
+        let hooks = game.hooks();
+        hooks.patch(core.as_mut());
+
+        let mut traps = hooks.common_traps();
+        traps.extend(hooks.shadow_traps(shadow_state.clone()));
+        core.set_traps(traps);
+        core.as_mut().reset();
+        core.as_mut().load_state(state)?;
+
+        {
+            let mut round_state = shadow_state.lock_round_state();
+            let mut round = Round {
+                current_tick: 0,
+                local_player_index,
+                first_committed_state: None,
+                pending_shadow_input: None,
+                pending_remote_packet: None,
+                input_injected: false,
+            };
+            round.set_first_committed_state(state.clone(), first_remote_packet);
+            round_state.round = Some(round);
+        }
+
+        Ok(Shadow {
+            core,
+            hooks,
+            state: shadow_state,
+            recent_applied_states: std::collections::VecDeque::new(),
+        })
+    }
+
+    pub fn hooks(&self) -> &'static (dyn game::Hooks + Send + Sync) {
+        self.hooks
+    }
+
+    /// The state the shadow was in at the given tick, if it is one of the last few ticks it applied input up to.
+    pub fn applied_state_at(&self, tick: u32) -> Option<&mgba::state::State> {
+        self.recent_applied_states
+            .iter()
+            .find(|applied_state| applied_state.tick == tick)
+            .map(|applied_state| &applied_state.state)
+    }
+
+    /// The last tick the shadow applied input up to.
+    pub fn last_applied_tick(&self) -> Option<u32> {
+        self.recent_applied_states
+            .back()
+            .map(|applied_state| applied_state.tick)
+    }
+
+    /// Reboots the shadow core for the next game of a set, dropping everything left over from the last one.
+    pub fn reset(&mut self, last_result: battle::BattleResult) {
+        self.core.as_mut().reset();
+        *self.state.0.applied_state.lock() = None;
+        *self.state.0.error.lock() = None;
+        self.recent_applied_states.clear();
+        let mut round_state = self.state.lock_round_state();
+        round_state.round = None;
+        round_state.last_result = Some(last_result);
     }
 
     pub fn advance_until_first_committed_state(&mut self) -> anyhow::Result<mgba::state::State> {
@@ -273,6 +378,10 @@ impl Shadow {
             let mut round_state = self.state.lock_round_state();
             let round = round_state.round.as_mut().expect("round");
             round.current_tick = applied_state.tick;
+            if self.recent_applied_states.len() == NUM_RECENT_APPLIED_STATES {
+                self.recent_applied_states.pop_front();
+            }
+            self.recent_applied_states.push_back(applied_state);
             return Ok(pending_remote_packet);
         }
     }